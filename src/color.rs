@@ -5,7 +5,11 @@
 use std::f32::consts::PI;
 use std::fmt;
 
-use super::{BasicParseError, ParseError, Parser, ToCss, Token};
+use super::color_space::normalize_hue;
+use super::{
+    AbsoluteColor, BasicParseError, ColorInterpolationMethod, ColorSpace, ParseError, Parser,
+    SystemColor, ThemeProvider, ToCss, Token,
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -130,12 +134,572 @@ impl ToCss for RGBA {
 }
 
 /// A <color> value.
-#[derive(Clone, Copy, PartialEq, Debug)]
+///
+/// `Color` is not `Copy`: the context-dependent `color-mix()` and `light-dark()`
+/// values are boxed. Use `clone`, or `to_absolute` for a `Copy` color.
+#[derive(Clone, PartialEq, Debug)]
 pub enum Color {
     /// The 'currentcolor' keyword
     CurrentColor,
-    /// Everything else gets converted to RGBA during parsing
+    /// Colors of the legacy syntax, `rgb()`, `hsl()` and `hwb()` are converted
+    /// to RGBA during parsing
     RGBA(RGBA),
+    /// A color in another color space, such as `lab()` or `color(display-p3 …)`
+    Absolute(AbsoluteColor),
+    /// A system color keyword, such as `Canvas`
+    System(SystemColor),
+    /// A `light-dark()` color, which depends on the used color scheme
+    LightDark(Box<LightDark>),
+    /// A `color-mix()` that depends on the context, see `Color::to_absolute`.
+    /// Other mixes are computed during parsing.
+    ColorMix(Box<ColorMix>),
+    /// A relative color whose origin depends on the context, see `Color::to_absolute`.
+    /// Other relative colors are computed during parsing.
+    Relative(Box<RelativeColor>),
+}
+
+/// The used color scheme of an element, from the `color-scheme` property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorScheme {
+    /// `light`
+    Light,
+    /// `dark`
+    Dark,
+}
+
+impl Color {
+    /// The color with float components, or `None` if it depends on the context:
+    /// on `currentcolor`, system colors or the color scheme of `light-dark()`.
+    pub fn to_absolute(&self) -> Option<AbsoluteColor> {
+        match *self {
+            Color::CurrentColor | Color::System(_) | Color::LightDark(_) => None,
+            Color::RGBA(rgba) => Some(rgba.into()),
+            Color::Absolute(color) => Some(color),
+            Color::ColorMix(ref mix) => mix.to_absolute(),
+            Color::Relative(_) => None,
+        }
+    }
+
+    /// The color with float components, given the value of `currentcolor`,
+    /// the theme for system colors and the used color scheme.
+    pub fn resolve(
+        &self,
+        current_color: &AbsoluteColor,
+        theme: &dyn ThemeProvider,
+        color_scheme: ColorScheme,
+    ) -> AbsoluteColor {
+        match *self {
+            Color::CurrentColor => *current_color,
+            Color::System(color) => theme.system_color(color, color_scheme),
+            Color::LightDark(ref light_dark) => {
+                light_dark
+                    .select(color_scheme)
+                    .resolve(current_color, theme, color_scheme)
+            }
+            Color::ColorMix(ref mix) => mix.resolve(current_color, theme, color_scheme),
+            Color::Relative(ref relative) => relative.resolve(current_color, theme, color_scheme),
+            _ => self.to_absolute().unwrap(),
+        }
+    }
+
+    /// The color in sRGB, clipped to its gamut,
+    /// or `None` if it depends on the context, see `Color::to_absolute`.
+    pub fn to_rgba(&self) -> Option<RGBA> {
+        match *self {
+            Color::RGBA(rgba) => Some(rgba),
+            _ => self.to_absolute().map(|color| color.to_rgba()),
+        }
+    }
+
+    /// Interpolate between this color, at a `progress` of 0, and `other`, at 1,
+    /// or return `None` if either depends on the context, see `Color::to_absolute`.
+    ///
+    /// See `AbsoluteColor::interpolate`.
+    pub fn interpolate(
+        &self,
+        other: &Color,
+        progress: f32,
+        method: ColorInterpolationMethod,
+    ) -> Option<AbsoluteColor> {
+        let start = self.to_absolute()?;
+        let end = other.to_absolute()?;
+        Some(start.interpolate(&end, progress, method))
+    }
+}
+
+/// `color-mix(<color-interpolation-method>, <color> <percentage>?, <color> <percentage>?)`
+///
+/// https://drafts.csswg.org/css-color-5/#color-mix
+#[derive(Clone, PartialEq, Debug)]
+pub struct ColorMix {
+    /// The color space and hue interpolation method.
+    pub interpolation: ColorInterpolationMethod,
+    /// The first color.
+    pub left: Color,
+    /// The percentage of the first color, from 0.0 to 1.0, if specified.
+    pub left_percentage: Option<f32>,
+    /// The second color.
+    pub right: Color,
+    /// The percentage of the second color, from 0.0 to 1.0, if specified.
+    pub right_percentage: Option<f32>,
+}
+
+impl ColorMix {
+    /// The progress from the first color to the second,
+    /// and the multiplier of the alpha when percentages sum to less than 100%.
+    ///
+    /// https://drafts.csswg.org/css-color-5/#color-mix-percent-norm
+    pub fn normalized_percentages(&self) -> (f32, f32) {
+        let (left, right) = match (self.left_percentage, self.right_percentage) {
+            (None, None) => (0.5, 0.5),
+            (Some(left), None) => (left, 1. - left),
+            (None, Some(right)) => (1. - right, right),
+            (Some(left), Some(right)) => (left, right),
+        };
+        let sum = left + right;
+        if sum == 0. {
+            return (0.5, 0.);
+        }
+        (right / sum, sum.min(1.))
+    }
+
+    /// Mix two colors with the percentages and interpolation method of this `color-mix()`.
+    pub fn mix(&self, left: &AbsoluteColor, right: &AbsoluteColor) -> AbsoluteColor {
+        let (progress, alpha_multiplier) = self.normalized_percentages();
+        let mut color = left.interpolate(right, progress, self.interpolation);
+        color.alpha = color.alpha.map(|alpha| alpha * alpha_multiplier);
+        color
+    }
+
+    /// The mixed color, or `None` if it depends on the context, see `Color::to_absolute`.
+    pub fn to_absolute(&self) -> Option<AbsoluteColor> {
+        Some(self.mix(&self.left.to_absolute()?, &self.right.to_absolute()?))
+    }
+
+    /// The mixed color, given the context, see `Color::resolve`.
+    pub fn resolve(
+        &self,
+        current_color: &AbsoluteColor,
+        theme: &dyn ThemeProvider,
+        color_scheme: ColorScheme,
+    ) -> AbsoluteColor {
+        self.mix(
+            &self.left.resolve(current_color, theme, color_scheme),
+            &self.right.resolve(current_color, theme, color_scheme),
+        )
+    }
+}
+
+impl ToCss for ColorMix {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("color-mix(")?;
+        self.interpolation.to_css(dest)?;
+        for &(color, percentage) in &[
+            (&self.left, self.left_percentage),
+            (&self.right, self.right_percentage),
+        ] {
+            dest.write_str(", ")?;
+            color.to_css(dest)?;
+            if let Some(percentage) = percentage {
+                dest.write_char(' ')?;
+                (percentage * 100.).to_css(dest)?;
+                dest.write_char('%')?;
+            }
+        }
+        dest.write_char(')')
+    }
+}
+
+/// `light-dark(<color>, <color>)`
+///
+/// https://drafts.csswg.org/css-color-5/#light-dark
+#[derive(Clone, PartialEq, Debug)]
+pub struct LightDark {
+    /// The color for the light color scheme.
+    pub light: Color,
+    /// The color for the dark color scheme.
+    pub dark: Color,
+}
+
+impl LightDark {
+    /// The color for the given color scheme.
+    pub fn select(&self, color_scheme: ColorScheme) -> &Color {
+        match color_scheme {
+            ColorScheme::Light => &self.light,
+            ColorScheme::Dark => &self.dark,
+        }
+    }
+}
+
+impl ToCss for LightDark {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("light-dark(")?;
+        self.light.to_css(dest)?;
+        dest.write_str(", ")?;
+        self.dark.to_css(dest)?;
+        dest.write_char(')')
+    }
+}
+
+/// A relative color, such as `oklch(from currentcolor l c calc(h + 180))`,
+/// whose origin color depends on the context, see `Color::to_absolute`.
+///
+/// https://drafts.csswg.org/css-color-5/#relative-colors
+#[derive(Clone, PartialEq, Debug)]
+pub struct RelativeColor {
+    /// The origin color, after `from`.
+    pub origin: Color,
+    /// The color space of the color function.
+    pub space: ColorSpace,
+    /// Whether the function is `color()` rather than that of the color space,
+    /// such as `rgb()` for `srgb`.
+    pub color_function: bool,
+    /// The components, in the range of the color function, such as 0 to 255 for `rgb()`.
+    pub components: [RelativeComponent; 3],
+    /// The alpha, the `alpha` channel keyword when omitted.
+    pub alpha: RelativeComponent,
+}
+
+impl RelativeColor {
+    /// The color with float components, given the context, see `Color::resolve`.
+    pub fn resolve(
+        &self,
+        current_color: &AbsoluteColor,
+        theme: &dyn ThemeProvider,
+        color_scheme: ColorScheme,
+    ) -> AbsoluteColor {
+        let origin = self.origin.resolve(current_color, theme, color_scheme);
+        self.resolve_with_origin(&origin)
+    }
+
+    /// The color with the channel keywords standing for the channels of `origin`.
+    fn resolve_with_origin(&self, origin: &AbsoluteColor) -> AbsoluteColor {
+        let scale = self.scale();
+        let channels = ChannelValues::new(self.space, scale, origin);
+        let mut components = [None; 3];
+        for (component, relative) in components.iter_mut().zip(&self.components) {
+            *component = relative.resolve(&channels);
+        }
+        if let Some(i) = self.space.hue_index() {
+            components[i] = components[i].map(normalize_hue);
+        }
+        let alpha = self.alpha.resolve(&channels);
+        relative_color_result(modern_color(self.space, scale, components, alpha))
+    }
+
+    /// 255 for the components of `rgb()`, 1 otherwise.
+    fn scale(&self) -> f32 {
+        if self.space == ColorSpace::Srgb && !self.color_function {
+            255.
+        } else {
+            1.
+        }
+    }
+}
+
+impl ToCss for RelativeColor {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.space {
+            _ if self.color_function => dest.write_str("color(from ")?,
+            ColorSpace::Srgb => dest.write_str("rgb(from ")?,
+            space => {
+                dest.write_str(space.name())?;
+                dest.write_str("(from ")?;
+            }
+        }
+        self.origin.to_css(dest)?;
+        if self.color_function {
+            dest.write_char(' ')?;
+            dest.write_str(self.space.name())?;
+        }
+        for component in &self.components {
+            dest.write_char(' ')?;
+            component.to_css(dest)?;
+        }
+        if self.alpha != RelativeComponent::Calc(ChannelCalc::Channel("alpha")) {
+            dest.write_str(" / ")?;
+            self.alpha.to_css(dest)?;
+        }
+        dest.write_char(')')
+    }
+}
+
+/// A component or the alpha of a relative color.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RelativeComponent {
+    /// `none`
+    None,
+    /// A number. Percentages and angles are converted to the number they stand for.
+    Number(f32),
+    /// A channel keyword of the origin color, or a `calc()` of channel keywords.
+    Calc(ChannelCalc),
+}
+
+impl RelativeComponent {
+    fn resolve(&self, channels: &ChannelValues) -> Option<f32> {
+        match *self {
+            RelativeComponent::None => None,
+            RelativeComponent::Number(value) => Some(value),
+            RelativeComponent::Calc(ref calc) => Some(calc.resolve(channels)),
+        }
+    }
+}
+
+impl ToCss for RelativeComponent {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            RelativeComponent::None => dest.write_str("none"),
+            RelativeComponent::Number(value) => value.to_css(dest),
+            RelativeComponent::Calc(ref calc) => calc.to_css(dest),
+        }
+    }
+}
+
+/// A channel keyword of the origin color of a relative color, such as `r` or `alpha`,
+/// or a `calc()` of numbers and channel keywords.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChannelCalc {
+    /// A number.
+    Number(f32),
+    /// A channel keyword, in lowercase.
+    /// Names that are not channels of the color space are zero, like missing components.
+    Channel(&'static str),
+    /// `+`
+    Sum(Box<ChannelCalc>, Box<ChannelCalc>),
+    /// `-`
+    Difference(Box<ChannelCalc>, Box<ChannelCalc>),
+    /// `*`
+    Product(Box<ChannelCalc>, Box<ChannelCalc>),
+    /// `/`
+    Quotient(Box<ChannelCalc>, Box<ChannelCalc>),
+}
+
+impl ChannelCalc {
+    fn resolve(&self, channels: &ChannelValues) -> f32 {
+        match *self {
+            ChannelCalc::Number(value) => value,
+            ChannelCalc::Channel(name) => channels.get(name),
+            ChannelCalc::Sum(ref a, ref b) => a.resolve(channels) + b.resolve(channels),
+            ChannelCalc::Difference(ref a, ref b) => a.resolve(channels) - b.resolve(channels),
+            ChannelCalc::Product(ref a, ref b) => a.resolve(channels) * b.resolve(channels),
+            ChannelCalc::Quotient(ref a, ref b) => a.resolve(channels) / b.resolve(channels),
+        }
+    }
+
+    /// Serialize inside `calc()`, with parentheses if the operation binds less tightly
+    /// than `precedence`: 1 for sums and differences, 2 for products and quotients.
+    fn to_css_inside_calc<W>(&self, dest: &mut W, precedence: u8) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let (left, operator, right, own_precedence) = match *self {
+            ChannelCalc::Number(value) => return value.to_css(dest),
+            ChannelCalc::Channel(name) => return dest.write_str(name),
+            ChannelCalc::Sum(ref a, ref b) => (a, " + ", b, 1),
+            ChannelCalc::Difference(ref a, ref b) => (a, " - ", b, 1),
+            ChannelCalc::Product(ref a, ref b) => (a, " * ", b, 2),
+            ChannelCalc::Quotient(ref a, ref b) => (a, " / ", b, 2),
+        };
+        let parenthesized = own_precedence < precedence;
+        if parenthesized {
+            dest.write_char('(')?;
+        }
+        left.to_css_inside_calc(dest, own_precedence)?;
+        dest.write_str(operator)?;
+        // Operations are left-associative, so an operation on the right keeps its parentheses.
+        right.to_css_inside_calc(dest, own_precedence + 1)?;
+        if parenthesized {
+            dest.write_char(')')?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for ChannelCalc {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            ChannelCalc::Channel(name) => dest.write_str(name),
+            _ => {
+                dest.write_str("calc(")?;
+                self.to_css_inside_calc(dest, 0)?;
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+/// The channel keywords of the color space of a relative color, such as `r` or `alpha`,
+/// which stand for the channels of the origin color in its components.
+///
+/// https://drafts.csswg.org/css-color-5/#relative-colors
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelKeywords {
+    names: [&'static str; 3],
+}
+
+impl ChannelKeywords {
+    fn new(space: ColorSpace) -> Self {
+        let names = match space {
+            ColorSpace::Hsl => ["h", "s", "l"],
+            ColorSpace::Hwb => ["h", "w", "b"],
+            ColorSpace::Lab | ColorSpace::Oklab => ["l", "a", "b"],
+            ColorSpace::Lch | ColorSpace::Oklch => ["l", "c", "h"],
+            ColorSpace::XyzD50 | ColorSpace::XyzD65 => ["x", "y", "z"],
+            _ => ["r", "g", "b"],
+        };
+        ChannelKeywords { names }
+    }
+
+    /// The lowercase name of a channel keyword, matched ASCII case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&'static str> {
+        if name.eq_ignore_ascii_case("alpha") {
+            return Some("alpha");
+        }
+        self.names
+            .iter()
+            .find(|channel| name.eq_ignore_ascii_case(channel))
+            .copied()
+    }
+
+    /// Parse a channel keyword, or a `calc()` of numbers and channel keywords
+    /// with `+`, `-`, `*`, `/` and parentheses.
+    pub fn parse<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<ChannelCalc, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let token = input.next()?.clone();
+        match token {
+            Token::Ident(ref name) => {
+                if let Some(name) = self.get(name) {
+                    return Ok(ChannelCalc::Channel(name));
+                }
+            }
+            Token::Function(ref name) if name.eq_ignore_ascii_case("calc") => {
+                return self.parse_nested_sum(input)
+            }
+            _ => {}
+        }
+        Err(location.new_basic_unexpected_token_error(token))
+    }
+
+    fn parse_nested_sum<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<ChannelCalc, BasicParseError<'i>> {
+        let result: Result<_, ParseError<()>> =
+            input.parse_nested_block(|input| self.parse_sum(input).map_err(Into::into));
+        result.map_err(ParseError::basic)
+    }
+
+    fn parse_sum<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<ChannelCalc, BasicParseError<'i>> {
+        let mut sum = self.parse_product(input)?;
+        while let Some(operator) = parse_operator(input, &['+', '-']) {
+            let value = Box::new(self.parse_product(input)?);
+            sum = if operator == '+' {
+                ChannelCalc::Sum(Box::new(sum), value)
+            } else {
+                ChannelCalc::Difference(Box::new(sum), value)
+            };
+        }
+        Ok(sum)
+    }
+
+    fn parse_product<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<ChannelCalc, BasicParseError<'i>> {
+        let mut product = self.parse_value(input)?;
+        while let Some(operator) = parse_operator(input, &['*', '/']) {
+            let value = Box::new(self.parse_value(input)?);
+            product = if operator == '*' {
+                ChannelCalc::Product(Box::new(product), value)
+            } else {
+                ChannelCalc::Quotient(Box::new(product), value)
+            };
+        }
+        Ok(product)
+    }
+
+    fn parse_value<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<ChannelCalc, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let token = input.next()?.clone();
+        match token {
+            Token::Number { value, .. } => return Ok(ChannelCalc::Number(value)),
+            Token::Ident(ref name) => {
+                if let Some(name) = self.get(name) {
+                    return Ok(ChannelCalc::Channel(name));
+                }
+            }
+            Token::ParenthesisBlock => return self.parse_nested_sum(input),
+            Token::Function(ref name) if name.eq_ignore_ascii_case("calc") => {
+                return self.parse_nested_sum(input)
+            }
+            _ => {}
+        }
+        Err(location.new_basic_unexpected_token_error(token))
+    }
+}
+
+/// The values of the channel keywords: the channels of the origin of a relative color.
+struct ChannelValues {
+    keywords: ChannelKeywords,
+    values: [f32; 3],
+    alpha: f32,
+}
+
+impl ChannelValues {
+    /// The channels of `origin` converted to `space`, with components multiplied by `scale`,
+    /// such as 255 for `rgb()`. Missing components are zero.
+    fn new(space: ColorSpace, scale: f32, origin: &AbsoluteColor) -> Self {
+        let [c0, c1, c2] = origin.to_space(space).resolved_components();
+        ChannelValues {
+            keywords: ChannelKeywords::new(space),
+            values: [c0 * scale, c1 * scale, c2 * scale],
+            alpha: origin.resolved_alpha(),
+        }
+    }
+
+    fn get(&self, name: &str) -> f32 {
+        if name == "alpha" {
+            return self.alpha;
+        }
+        self.keywords
+            .names
+            .iter()
+            .position(|channel| *channel == name)
+            .map_or(0., |index| self.values[index])
+    }
+}
+
+fn parse_operator<'i, 't>(input: &mut Parser<'i, 't>, operators: &[char]) -> Option<char> {
+    input
+        .try_parse(|input| match input.next() {
+            Ok(&Token::Delim(c)) if operators.contains(&c) => Ok(c),
+            _ => Err(()),
+        })
+        .ok()
 }
 
 impl ToCss for Color {
@@ -145,7 +709,12 @@ impl ToCss for Color {
     {
         match *self {
             Color::CurrentColor => dest.write_str("currentcolor"),
+            Color::System(ref color) => color.to_css(dest),
+            Color::LightDark(ref light_dark) => light_dark.to_css(dest),
             Color::RGBA(ref rgba) => rgba.to_css(dest),
+            Color::Absolute(ref color) => color.to_css(dest),
+            Color::ColorMix(ref mix) => mix.to_css(dest),
+            Color::Relative(ref relative) => relative.to_css(dest),
         }
     }
 }
@@ -262,6 +831,19 @@ pub trait ColorComponentParser<'i> {
             ref t => return Err(location.new_unexpected_token_error(t.clone())),
         })
     }
+
+    /// Parse a channel keyword of the origin color of a relative color,
+    /// or a `calc()` of numbers and channel keywords, as a component of the relative color.
+    /// Other values are parsed with `parse_number_or_percentage` and `parse_angle_or_number`.
+    ///
+    /// The default implementation uses `ChannelKeywords::parse`.
+    fn parse_channel_calc<'t>(
+        &self,
+        input: &mut Parser<'i, 't>,
+        channels: &ChannelKeywords,
+    ) -> Result<ChannelCalc, ParseError<'i, Self::Error>> {
+        channels.parse(input).map_err(From::from)
+    }
 }
 
 struct DefaultComponentParser;
@@ -270,9 +852,7 @@ impl<'i> ColorComponentParser<'i> for DefaultComponentParser {
 }
 
 impl Color {
-    /// Parse a <color> value, per CSS Color Module Level 3.
-    ///
-    /// FIXME(#2) Deprecated CSS2 System Colors are not supported yet.
+    /// Parse a <color> value, per CSS Color Module Level 4.
     pub fn parse_with<'i, 't, ComponentParser>(
         component_parser: &ComponentParser,
         input: &mut Parser<'i, 't>,
@@ -286,7 +866,8 @@ impl Color {
             Token::Hash(ref value) | Token::IDHash(ref value) => {
                 Color::parse_hash(value.as_bytes())
             }
-            Token::Ident(ref value) => parse_color_keyword(&*value),
+            Token::Ident(ref value) => parse_color_keyword(&*value)
+                .or_else(|()| SystemColor::from_name(value).map(Color::System).ok_or(())),
             Token::Function(ref name) => {
                 let name = name.clone();
                 return input.parse_nested_block(|arguments| {
@@ -298,7 +879,7 @@ impl Color {
         .map_err(|()| location.new_unexpected_token_error(token.clone()))
     }
 
-    /// Parse a <color> value, per CSS Color Module Level 3.
+    /// Parse a <color> value, per CSS Color Module Level 4.
     pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Color, BasicParseError<'i>> {
         let component_parser = DefaultComponentParser;
         Self::parse_with(&component_parser, input).map_err(ParseError::basic)
@@ -562,9 +1143,39 @@ fn parse_color_function<'i, 't, ComponentParser>(
 where
     ComponentParser: ColorComponentParser<'i>,
 {
+    if arguments
+        .try_parse(|i| i.expect_ident_matching("from"))
+        .is_ok()
+    {
+        return parse_relative_color(component_parser, name, arguments);
+    }
     let (red, green, blue, uses_commas) = match_ignore_ascii_case! { name,
         "rgb" | "rgba" => parse_rgb_components_rgb(component_parser, arguments)?,
         "hsl" | "hsla" => parse_rgb_components_hsl(component_parser, arguments)?,
+        "hwb" => {
+            // Like `hsl()`, this is converted to RGBA, with `none` as zero.
+            let space = ColorSpace::Hwb;
+            let color = parse_modern_components(component_parser, space, arguments)?;
+            return Ok(Color::RGBA(color.to_rgba()));
+        },
+        "lab" | "lch" | "oklab" | "oklch" => {
+            let space = ColorSpace::from_name(name).unwrap();
+            let color = parse_modern_components(component_parser, space, arguments)?;
+            return Ok(Color::Absolute(color));
+        },
+        "color" => {
+            let space = parse_predefined_space(arguments)?;
+            let color = parse_modern_components(component_parser, space, arguments)?;
+            return Ok(Color::Absolute(color));
+        },
+        "color-mix" => return parse_color_mix(component_parser, arguments),
+        "light-dark" => {
+            let light = Color::parse_with(component_parser, arguments)?;
+            arguments.expect_comma()?;
+            let dark = Color::parse_with(component_parser, arguments)?;
+            arguments.expect_exhausted()?;
+            return Ok(Color::LightDark(Box::new(LightDark { light, dark })));
+        },
         _ => return Err(arguments.new_unexpected_token_error(Token::Ident(name.to_owned().into()))),
     };
 
@@ -685,3 +1296,293 @@ where
     let blue = clamp_unit_f32(hue_to_rgb(m1, m2, hue_times_3 - 1.));
     return Ok((red, green, blue, uses_commas));
 }
+
+/// Parse the components of a color function with the modern syntax, such as `lab()`,
+/// after the color space for `color()`.
+///
+/// https://drafts.csswg.org/css-color-4/#specifying-lab-lch
+/// https://drafts.csswg.org/css-color-4/#specifying-oklab-oklch
+/// https://drafts.csswg.org/css-color-4/#color-function
+fn parse_modern_components<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    space: ColorSpace,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<AbsoluteColor, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    let references = percentage_references(space, 1.);
+    let mut components = [None; 3];
+    for (i, component) in components.iter_mut().enumerate() {
+        *component = if space.hue_index() == Some(i) {
+            parse_hue(component_parser, arguments)?
+        } else {
+            parse_component(component_parser, arguments, references[i])?
+        };
+    }
+    let alpha = parse_modern_alpha(component_parser, arguments)?;
+    Ok(modern_color(space, 1., components, alpha))
+}
+
+/// The values of 100% for the components of a color space other than hues.
+/// `scale` is the value of 100% for `rgb()` and the spaces of `color()`.
+fn percentage_references(space: ColorSpace, scale: f32) -> [f32; 3] {
+    match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => [0., 100., 100.],
+        ColorSpace::Lab => [100., 125., 125.],
+        ColorSpace::Lch => [100., 150., 0.],
+        ColorSpace::Oklab => [1., 0.4, 0.4],
+        ColorSpace::Oklch => [1., 0.4, 0.],
+        _ => [scale; 3],
+    }
+}
+
+/// The color of the components of a color function with the modern syntax,
+/// with the lightness and chroma clamped, and the components divided by `scale`,
+/// such as 255 for `rgb()`.
+fn modern_color(
+    space: ColorSpace,
+    scale: f32,
+    mut components: [Option<f32>; 3],
+    alpha: Option<f32>,
+) -> AbsoluteColor {
+    match space {
+        ColorSpace::Lab | ColorSpace::Lch | ColorSpace::Oklab | ColorSpace::Oklch => {
+            let reference = percentage_references(space, scale)[0];
+            components[0] = components[0].map(|l| l.max(0.).min(reference));
+        }
+        _ => {}
+    }
+    if let ColorSpace::Lch | ColorSpace::Oklch = space {
+        components[1] = components[1].map(|c| c.max(0.));
+    }
+    for component in &mut components {
+        *component = component.map(|c| c / scale);
+    }
+    AbsoluteColor {
+        space,
+        components,
+        alpha: alpha.map(|alpha| alpha.clamp(0., 1.)),
+    }
+}
+
+/// The color space of `color()`, other than `hsl`, `hwb` and the spaces of Lab.
+fn parse_predefined_space<'i, 't, E>(
+    arguments: &mut Parser<'i, 't>,
+) -> Result<ColorSpace, ParseError<'i, E>> {
+    let location = arguments.current_source_location();
+    let name = arguments.expect_ident()?.clone();
+    match ColorSpace::from_name(&name) {
+        Some(space) if space.is_predefined() => Ok(space),
+        _ => Err(location.new_unexpected_token_error(Token::Ident(name))),
+    }
+}
+
+fn parse_none<'i, 't>(arguments: &mut Parser<'i, 't>) -> bool {
+    arguments
+        .try_parse(|i| i.expect_ident_matching("none"))
+        .is_ok()
+}
+
+/// Parse `none`, a number, or a percentage of `percentage_reference`.
+fn parse_component<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+    percentage_reference: f32,
+) -> Result<Option<f32>, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    if parse_none(arguments) {
+        return Ok(None);
+    }
+    Ok(Some(
+        match component_parser.parse_number_or_percentage(arguments)? {
+            NumberOrPercentage::Number { value } => value,
+            NumberOrPercentage::Percentage { unit_value } => unit_value * percentage_reference,
+        },
+    ))
+}
+
+/// Parse `none` or a hue, in degrees from 0 to 360 excluded.
+fn parse_hue<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<Option<f32>, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    if parse_none(arguments) {
+        return Ok(None);
+    }
+    let degrees = component_parser.parse_angle_or_number(arguments)?.degrees();
+    Ok(Some(normalize_hue(degrees)))
+}
+
+/// Parse an optional `/ <alpha-value>` or `/ none` at the end of the arguments.
+fn parse_modern_alpha<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<Option<f32>, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    if arguments.is_exhausted() {
+        return Ok(Some(1.));
+    }
+    arguments.expect_delim('/')?;
+    let alpha = parse_component(component_parser, arguments, 1.)?;
+    arguments.expect_exhausted()?;
+    Ok(alpha)
+}
+
+/// `<color-function>(from <color> …)`
+///
+/// https://drafts.csswg.org/css-color-5/#relative-colors
+fn parse_relative_color<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    name: &str,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<Color, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    let origin = Color::parse_with(component_parser, arguments)?;
+    let (space, color_function) = match_ignore_ascii_case! { name,
+        "rgb" | "rgba" => (ColorSpace::Srgb, false),
+        "hsl" | "hsla" => (ColorSpace::Hsl, false),
+        "hwb" => (ColorSpace::Hwb, false),
+        "lab" => (ColorSpace::Lab, false),
+        "lch" => (ColorSpace::Lch, false),
+        "oklab" => (ColorSpace::Oklab, false),
+        "oklch" => (ColorSpace::Oklch, false),
+        "color" => (parse_predefined_space(arguments)?, true),
+        _ => return Err(arguments.new_unexpected_token_error(Token::Ident(name.to_owned().into()))),
+    };
+    let mut relative = RelativeColor {
+        origin,
+        space,
+        color_function,
+        components: [
+            RelativeComponent::None,
+            RelativeComponent::None,
+            RelativeComponent::None,
+        ],
+        alpha: RelativeComponent::Calc(ChannelCalc::Channel("alpha")),
+    };
+    let channels = ChannelKeywords::new(space);
+    let references = percentage_references(space, relative.scale());
+    for (i, component) in relative.components.iter_mut().enumerate() {
+        let hue = space.hue_index() == Some(i);
+        *component =
+            parse_relative_component(component_parser, arguments, &channels, hue, references[i])?;
+    }
+    if !arguments.is_exhausted() {
+        arguments.expect_delim('/')?;
+        relative.alpha =
+            parse_relative_component(component_parser, arguments, &channels, false, 1.)?;
+        arguments.expect_exhausted()?;
+    }
+    Ok(match relative.origin.to_absolute() {
+        Some(origin) => Color::Absolute(relative.resolve_with_origin(&origin)),
+        None => Color::Relative(Box::new(relative)),
+    })
+}
+
+/// Parse a component of a relative color: a channel keyword or `calc()`
+/// with `ColorComponentParser::parse_channel_calc`, or a component as for absolute colors.
+fn parse_relative_component<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+    channels: &ChannelKeywords,
+    hue: bool,
+    percentage_reference: f32,
+) -> Result<RelativeComponent, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    if let Ok(calc) = arguments.try_parse(|i| component_parser.parse_channel_calc(i, channels)) {
+        return Ok(RelativeComponent::Calc(calc));
+    }
+    let value = if hue {
+        parse_hue(component_parser, arguments)?
+    } else {
+        parse_component(component_parser, arguments, percentage_reference)?
+    };
+    Ok(value.map_or(RelativeComponent::None, RelativeComponent::Number))
+}
+
+/// Relative colors with `hsl()` and `hwb()` are not converted to RGBA like
+/// their absolute counterparts, as their components can be out of range.
+fn relative_color_result(color: AbsoluteColor) -> AbsoluteColor {
+    match color.space {
+        ColorSpace::Hsl | ColorSpace::Hwb => color.to_space(ColorSpace::Srgb),
+        _ => color,
+    }
+}
+
+/// https://drafts.csswg.org/css-color-5/#color-mix
+fn parse_color_mix<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<Color, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    let interpolation = ColorInterpolationMethod::parse(arguments)?;
+    arguments.expect_comma()?;
+    let (left, left_percentage) = parse_color_mix_component(component_parser, arguments)?;
+    arguments.expect_comma()?;
+    let (right, right_percentage) = parse_color_mix_component(component_parser, arguments)?;
+    if let (Some(left), Some(right)) = (left_percentage, right_percentage) {
+        if left + right == 0. {
+            return Err(arguments.new_error_for_next_token());
+        }
+    }
+    let mix = ColorMix {
+        interpolation,
+        left,
+        left_percentage,
+        right,
+        right_percentage,
+    };
+    Ok(match mix.to_absolute() {
+        Some(color) => Color::Absolute(color),
+        None => Color::ColorMix(Box::new(mix)),
+    })
+}
+
+/// `<color> && <percentage [0,100]>?`
+fn parse_color_mix_component<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<(Color, Option<f32>), ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    let mut percentage = parse_color_mix_percentage(component_parser, arguments)?;
+    let color = Color::parse_with(component_parser, arguments)?;
+    if percentage.is_none() {
+        percentage = parse_color_mix_percentage(component_parser, arguments)?;
+    }
+    Ok((color, percentage))
+}
+
+fn parse_color_mix_percentage<'i, 't, ComponentParser>(
+    component_parser: &ComponentParser,
+    arguments: &mut Parser<'i, 't>,
+) -> Result<Option<f32>, ParseError<'i, ComponentParser::Error>>
+where
+    ComponentParser: ColorComponentParser<'i>,
+{
+    let start = arguments.state();
+    let percentage = match arguments.try_parse(|i| component_parser.parse_percentage(i)) {
+        Ok(percentage) => percentage,
+        Err(_) => return Ok(None),
+    };
+    if !(0. ..=1.).contains(&percentage) {
+        arguments.reset(&start);
+        return Err(arguments.new_error_for_next_token());
+    }
+    Ok(Some(percentage))
+}
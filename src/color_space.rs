@@ -0,0 +1,875 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-color-4/#color-conversion-code
+
+use std::fmt;
+
+use super::{BasicParseError, Parser, ToCss, Token, RGBA};
+
+/// A color space, as in `color(display-p3 …)` or `color-mix(in oklch, …)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// `srgb`
+    Srgb,
+    /// `srgb-linear`
+    SrgbLinear,
+    /// `display-p3`
+    DisplayP3,
+    /// `a98-rgb`
+    A98Rgb,
+    /// `prophoto-rgb`
+    ProphotoRgb,
+    /// `rec2020`
+    Rec2020,
+    /// `xyz-d50`
+    XyzD50,
+    /// `xyz-d65`, also spelled `xyz`
+    XyzD65,
+    /// `hsl`, with saturation and lightness from 0 to 100.
+    Hsl,
+    /// `hwb`, with whiteness and blackness from 0 to 100.
+    Hwb,
+    /// `lab`
+    Lab,
+    /// `lch`
+    Lch,
+    /// `oklab`
+    Oklab,
+    /// `oklch`
+    Oklch,
+}
+
+impl ColorSpace {
+    /// The color space with this name, ASCII case-insensitively.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match_ignore_ascii_case! { name,
+            "srgb" => ColorSpace::Srgb,
+            "srgb-linear" => ColorSpace::SrgbLinear,
+            "display-p3" => ColorSpace::DisplayP3,
+            "a98-rgb" => ColorSpace::A98Rgb,
+            "prophoto-rgb" => ColorSpace::ProphotoRgb,
+            "rec2020" => ColorSpace::Rec2020,
+            "xyz-d50" => ColorSpace::XyzD50,
+            "xyz" | "xyz-d65" => ColorSpace::XyzD65,
+            "hsl" => ColorSpace::Hsl,
+            "hwb" => ColorSpace::Hwb,
+            "lab" => ColorSpace::Lab,
+            "lch" => ColorSpace::Lch,
+            "oklab" => ColorSpace::Oklab,
+            "oklch" => ColorSpace::Oklch,
+            _ => return None,
+        })
+    }
+
+    /// The canonical name of the color space.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::SrgbLinear => "srgb-linear",
+            ColorSpace::DisplayP3 => "display-p3",
+            ColorSpace::A98Rgb => "a98-rgb",
+            ColorSpace::ProphotoRgb => "prophoto-rgb",
+            ColorSpace::Rec2020 => "rec2020",
+            ColorSpace::XyzD50 => "xyz-d50",
+            ColorSpace::XyzD65 => "xyz-d65",
+            ColorSpace::Hsl => "hsl",
+            ColorSpace::Hwb => "hwb",
+            ColorSpace::Lab => "lab",
+            ColorSpace::Lch => "lch",
+            ColorSpace::Oklab => "oklab",
+            ColorSpace::Oklch => "oklch",
+        }
+    }
+
+    /// Whether this is one of the spaces of the `color()` function.
+    pub fn is_predefined(&self) -> bool {
+        !matches!(
+            *self,
+            ColorSpace::Hsl
+                | ColorSpace::Hwb
+                | ColorSpace::Lab
+                | ColorSpace::Lch
+                | ColorSpace::Oklab
+                | ColorSpace::Oklch
+        )
+    }
+
+    /// The index of the hue component of a cylindrical color space.
+    pub fn hue_index(&self) -> Option<usize> {
+        match *self {
+            ColorSpace::Hsl | ColorSpace::Hwb => Some(0),
+            ColorSpace::Lch | ColorSpace::Oklch => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// A color in any color space, with float components.
+///
+/// `None` is a missing component, written `none`.
+/// Conversions to another color space treat it as zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AbsoluteColor {
+    /// The color space of the components.
+    pub space: ColorSpace,
+    /// The components, in the order of the CSS function of the color space,
+    /// with hues in degrees.
+    pub components: [Option<f32>; 3],
+    /// The alpha component, from 0 to 1.
+    pub alpha: Option<f32>,
+}
+
+impl From<RGBA> for AbsoluteColor {
+    fn from(rgba: RGBA) -> Self {
+        AbsoluteColor::new(
+            ColorSpace::Srgb,
+            [rgba.red_f32(), rgba.green_f32(), rgba.blue_f32()],
+            rgba.alpha_f32(),
+        )
+    }
+}
+
+impl AbsoluteColor {
+    /// Create a color without missing components.
+    pub fn new(space: ColorSpace, components: [f32; 3], alpha: f32) -> Self {
+        AbsoluteColor {
+            space,
+            components: [
+                Some(components[0]),
+                Some(components[1]),
+                Some(components[2]),
+            ],
+            alpha: Some(alpha),
+        }
+    }
+
+    /// The components, with missing ones as zero.
+    pub fn resolved_components(&self) -> [f32; 3] {
+        let [c0, c1, c2] = self.components;
+        [c0.unwrap_or(0.), c1.unwrap_or(0.), c2.unwrap_or(0.)]
+    }
+
+    /// The alpha component, missing being zero.
+    pub fn resolved_alpha(&self) -> f32 {
+        self.alpha.unwrap_or(0.)
+    }
+
+    /// Convert the color to another color space.
+    ///
+    /// Missing components are zero, except that the alpha stays missing,
+    /// and that a hue is missing when it is powerless in the new color space,
+    /// such as for grays in `lch`.
+    pub fn to_space(&self, space: ColorSpace) -> Self {
+        if space == self.space {
+            return *self;
+        }
+        let [c0, c1, c2] = self.resolved_components();
+        let source = [f64::from(c0), f64::from(c1), f64::from(c2)];
+        let converted = convert(self.space, space, source);
+        let mut components = [
+            Some(converted[0] as f32),
+            Some(converted[1] as f32),
+            Some(converted[2] as f32),
+        ];
+        if let Some(hue) = space.hue_index() {
+            components[hue] = if is_achromatic(space, converted) {
+                None
+            } else {
+                // A hue just below 360 in f64 can round to 360 as an f32.
+                Some(normalize_hue(converted[hue] as f32))
+            };
+        }
+        AbsoluteColor {
+            space,
+            components,
+            alpha: self.alpha,
+        }
+    }
+
+    /// Convert the color to sRGB, clipping components out of the sRGB gamut.
+    /// See `AbsoluteColor::to_rgba_in_gamut` for the CSS gamut mapping.
+    pub fn to_rgba(&self) -> RGBA {
+        let [red, green, blue] = self.to_space(ColorSpace::Srgb).resolved_components();
+        RGBA::from_floats(red, green, blue, self.resolved_alpha())
+    }
+}
+
+/// Categories of analogous components, carried forward when missing
+/// in a color converted for interpolation.
+///
+/// https://drafts.csswg.org/css-color-4/#interpolation-missing
+#[derive(Clone, Copy, PartialEq)]
+enum Analogous {
+    Red,
+    Green,
+    Blue,
+    Lightness,
+    Colorfulness,
+    Hue,
+    OpponentA,
+    OpponentB,
+}
+
+fn analogous_components(space: ColorSpace) -> [Option<Analogous>; 3] {
+    use self::Analogous::*;
+    match space {
+        ColorSpace::Hsl => [Some(Hue), Some(Colorfulness), Some(Lightness)],
+        ColorSpace::Hwb => [Some(Hue), None, None],
+        ColorSpace::Lab | ColorSpace::Oklab => [Some(Lightness), Some(OpponentA), Some(OpponentB)],
+        ColorSpace::Lch | ColorSpace::Oklch => [Some(Lightness), Some(Colorfulness), Some(Hue)],
+        _ => [Some(Red), Some(Green), Some(Blue)],
+    }
+}
+
+/// How hues are interpolated in a cylindrical color space.
+///
+/// https://drafts.csswg.org/css-color-4/#hue-interpolation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HueInterpolationMethod {
+    /// `shorter hue`, the default
+    Shorter,
+    /// `longer hue`
+    Longer,
+    /// `increasing hue`
+    Increasing,
+    /// `decreasing hue`
+    Decreasing,
+}
+
+impl HueInterpolationMethod {
+    /// The keyword before `hue`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HueInterpolationMethod::Shorter => "shorter",
+            HueInterpolationMethod::Longer => "longer",
+            HueInterpolationMethod::Increasing => "increasing",
+            HueInterpolationMethod::Decreasing => "decreasing",
+        }
+    }
+
+    /// Adjust two hues in degrees from 0 to 360 so that linear interpolation
+    /// between them follows this method.
+    fn fixup(&self, h1: &mut f32, h2: &mut f32) {
+        let difference = *h2 - *h1;
+        match *self {
+            HueInterpolationMethod::Shorter => {
+                if difference > 180. {
+                    *h1 += 360.
+                } else if difference < -180. {
+                    *h2 += 360.
+                }
+            }
+            HueInterpolationMethod::Longer => {
+                if 0. < difference && difference < 180. {
+                    *h1 += 360.
+                } else if -180. < difference && difference <= 0. {
+                    *h2 += 360.
+                }
+            }
+            HueInterpolationMethod::Increasing => {
+                if *h2 < *h1 {
+                    *h2 += 360.
+                }
+            }
+            HueInterpolationMethod::Decreasing => {
+                if *h1 < *h2 {
+                    *h1 += 360.
+                }
+            }
+        }
+    }
+}
+
+/// `<color-interpolation-method>`: `in <color-space> [<hue-interpolation-method> hue]?`
+///
+/// https://drafts.csswg.org/css-color-4/#color-interpolation-method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColorInterpolationMethod {
+    /// The color space in which to interpolate.
+    pub space: ColorSpace,
+    /// How hues are interpolated, for cylindrical color spaces.
+    pub hue: HueInterpolationMethod,
+}
+
+impl ColorInterpolationMethod {
+    /// Create an interpolation method with shorter hue interpolation.
+    pub fn new(space: ColorSpace) -> Self {
+        ColorInterpolationMethod {
+            space,
+            hue: HueInterpolationMethod::Shorter,
+        }
+    }
+
+    /// Parse a color interpolation method, starting with the `in` keyword.
+    ///
+    /// A hue interpolation method is only valid with a cylindrical color space.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        input.expect_ident_matching("in")?;
+        let location = input.current_source_location();
+        let name = input.expect_ident()?.clone();
+        let space = ColorSpace::from_name(&name)
+            .ok_or_else(|| location.new_basic_unexpected_token_error(Token::Ident(name)))?;
+        let mut method = ColorInterpolationMethod::new(space);
+        if space.hue_index().is_none() {
+            return Ok(method);
+        }
+        let hue = input.try_parse(|input| -> Result<_, BasicParseError<'i>> {
+            let location = input.current_source_location();
+            let name = input.expect_ident()?.clone();
+            let hue = match_ignore_ascii_case! { &name,
+                "shorter" => HueInterpolationMethod::Shorter,
+                "longer" => HueInterpolationMethod::Longer,
+                "increasing" => HueInterpolationMethod::Increasing,
+                "decreasing" => HueInterpolationMethod::Decreasing,
+                _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(name))),
+            };
+            input.expect_ident_matching("hue")?;
+            Ok(hue)
+        });
+        if let Ok(hue) = hue {
+            method.hue = hue;
+        }
+        Ok(method)
+    }
+}
+
+impl ToCss for ColorInterpolationMethod {
+    /// The shorter hue interpolation method is omitted, as the default.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("in ")?;
+        dest.write_str(self.space.name())?;
+        if self.hue != HueInterpolationMethod::Shorter {
+            dest.write_char(' ')?;
+            dest.write_str(self.hue.name())?;
+            dest.write_str(" hue")?;
+        }
+        Ok(())
+    }
+}
+
+impl AbsoluteColor {
+    /// Convert the color to the color space of an interpolation,
+    /// keeping components missing when an analogous component was.
+    fn to_interpolation_space(self, space: ColorSpace) -> Self {
+        let mut converted = self.to_space(space);
+        if space != self.space {
+            let source = analogous_components(self.space);
+            let target = analogous_components(space);
+            for (component, category) in self.components.iter().zip(&source) {
+                if let (None, Some(category)) = (component, category) {
+                    for (converted, target) in converted.components.iter_mut().zip(&target) {
+                        if target.as_ref() == Some(category) {
+                            *converted = None;
+                        }
+                    }
+                }
+            }
+        }
+        converted
+    }
+
+    /// Interpolate between this color, at a `progress` of 0, and `other`, at 1,
+    /// in the color space of `method` and with premultiplied alpha.
+    ///
+    /// A component missing in only one of the colors takes the value of the other.
+    ///
+    /// https://drafts.csswg.org/css-color-4/#interpolation
+    pub fn interpolate(
+        &self,
+        other: &AbsoluteColor,
+        progress: f32,
+        method: ColorInterpolationMethod,
+    ) -> AbsoluteColor {
+        let space = method.space;
+        let start = self.to_interpolation_space(space);
+        let end = other.to_interpolation_space(space);
+        // A component missing in only one color takes the value of the other.
+        let lerp = |a: Option<f32>, b: Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => Some((a, b, a + (b - a) * progress)),
+            (Some(c), None) | (None, Some(c)) => Some((c, c, c)),
+            (None, None) => None,
+        };
+
+        let (start_alpha, end_alpha, alpha) = match lerp(start.alpha, end.alpha) {
+            Some((start_alpha, end_alpha, alpha)) => (start_alpha, end_alpha, Some(alpha)),
+            None => (1., 1., None),
+        };
+        let hue_index = space.hue_index();
+        let mut components = [None; 3];
+        for (i, component) in components.iter_mut().enumerate() {
+            let (a, b) = match lerp(start.components[i], end.components[i]) {
+                Some((a, b, _)) => (a, b),
+                None => continue,
+            };
+            *component = Some(if Some(i) == hue_index {
+                let (mut h1, mut h2) = (a, b);
+                method.hue.fixup(&mut h1, &mut h2);
+                normalize_hue(h1 + (h2 - h1) * progress)
+            } else {
+                let (a, b) = (a * start_alpha, b * end_alpha);
+                let premultiplied = a + (b - a) * progress;
+                match alpha {
+                    Some(alpha) if alpha != 0. => premultiplied / alpha,
+                    _ => premultiplied,
+                }
+            });
+        }
+        AbsoluteColor {
+            space,
+            components,
+            alpha,
+        }
+    }
+}
+
+impl ToCss for AbsoluteColor {
+    /// https://drafts.csswg.org/css-color-4/#serializing-color-values
+    ///
+    /// `hsl` and `hwb` colors serialize as `rgb()`.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.space {
+            ColorSpace::Hsl | ColorSpace::Hwb => return self.to_rgba().to_css(dest),
+            ColorSpace::Lab | ColorSpace::Lch | ColorSpace::Oklab | ColorSpace::Oklch => {
+                dest.write_str(self.space.name())?;
+                dest.write_char('(')?;
+            }
+            _ => {
+                dest.write_str("color(")?;
+                dest.write_str(self.space.name())?;
+                dest.write_char(' ')?;
+            }
+        }
+        for (i, component) in self.components.iter().enumerate() {
+            if i > 0 {
+                dest.write_char(' ')?;
+            }
+            serialize_component(*component, dest)?;
+        }
+        if self.alpha != Some(1.) {
+            dest.write_str(" / ")?;
+            serialize_component(self.alpha, dest)?;
+        }
+        dest.write_char(')')
+    }
+}
+
+fn serialize_component<W>(component: Option<f32>, dest: &mut W) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    match component {
+        Some(value) => value.to_css(dest),
+        None => dest.write_str("none"),
+    }
+}
+
+type Matrix = [[f64; 3]; 3];
+
+fn multiply(m: &Matrix, v: [f64; 3]) -> [f64; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+fn map(v: [f64; 3], f: impl Fn(f64) -> f64) -> [f64; 3] {
+    [f(v[0]), f(v[1]), f(v[2])]
+}
+
+const LINEAR_SRGB_TO_XYZ: Matrix = [
+    [0.41239079926595934, 0.357584339383878, 0.1804807884018343],
+    [0.21263900587151027, 0.715168678767756, 0.07219231536073371],
+    [0.01933081871559182, 0.11919477979462598, 0.9505321522496607],
+];
+const XYZ_TO_LINEAR_SRGB: Matrix = [
+    [3.2409699419045226, -1.537383177570094, -0.4986107602930034],
+    [-0.9692436362808796, 1.8759675015077202, 0.04155505740717559],
+    [
+        0.05563007969699366,
+        -0.20397695888897652,
+        1.0569715142428786,
+    ],
+];
+const LINEAR_P3_TO_XYZ: Matrix = [
+    [0.4865709486482162, 0.26566769316909306, 0.1982172852343625],
+    [0.2289745640697488, 0.6917385218365064, 0.079286914093745],
+    [0., 0.04511338185890264, 1.043944368900976],
+];
+const XYZ_TO_LINEAR_P3: Matrix = [
+    [2.493496911941425, -0.9313836179191239, -0.40271078445071684],
+    [
+        -0.8294889695615747,
+        1.7626640603183463,
+        0.023624685841943577,
+    ],
+    [
+        0.03584583024378447,
+        -0.07617238926804182,
+        0.9568845240076872,
+    ],
+];
+const LINEAR_A98_TO_XYZ: Matrix = [
+    [0.5766690429101305, 0.1855582379065463, 0.1882286462349947],
+    [0.29734497525053605, 0.6273635662554661, 0.07529145849399788],
+    [0.02703136138641234, 0.07068885253582723, 0.9913375368376388],
+];
+const XYZ_TO_LINEAR_A98: Matrix = [
+    [
+        2.0415879038107465,
+        -0.5650069742788596,
+        -0.34473135077832956,
+    ],
+    [-0.9692436362808795, 1.8759675015077202, 0.04155505740717557],
+    [
+        0.013444280632031142,
+        -0.11836239223101838,
+        1.0151749943912054,
+    ],
+];
+/// To XYZ D50.
+const LINEAR_PROPHOTO_TO_XYZ: Matrix = [
+    [0.7977666449006423, 0.13518129740053308, 0.0313477341283922],
+    [0.2880748288194013, 0.711835234241873, 0.00008993693872564],
+    [0., 0., 0.8251046025104602],
+];
+const XYZ_TO_LINEAR_PROPHOTO: Matrix = [
+    [
+        1.3457868816471583,
+        -0.25557208737979464,
+        -0.05110186497554526,
+    ],
+    [-0.5446307051249019, 1.5082477428451468, 0.02052744743642139],
+    [0., 0., 1.2119675456389452],
+];
+const LINEAR_REC2020_TO_XYZ: Matrix = [
+    [0.6369580483012914, 0.14461690358620832, 0.1688809751641721],
+    [0.2627002120112671, 0.6779980715188708, 0.05930171646986196],
+    [0., 0.028072693049087428, 1.060985057710791],
+];
+const XYZ_TO_LINEAR_REC2020: Matrix = [
+    [1.716651187971268, -0.355670783776392, -0.25336628137366],
+    [-0.666684351832489, 1.616481236634939, 0.0157685458139111],
+    [0.017639857445311, -0.042770613257809, 0.942103121235474],
+];
+/// Bradford chromatic adaptation.
+const D50_TO_D65: Matrix = [
+    [0.955473421488075, -0.02309845494876471, 0.06325924320057072],
+    [
+        -0.0283697093338637,
+        1.0099953980813041,
+        0.021041441191917323,
+    ],
+    [
+        0.012314014864481998,
+        -0.020507649298898964,
+        1.330365926242124,
+    ],
+];
+const D65_TO_D50: Matrix = [
+    [
+        1.0479297925449969,
+        0.022946870601609652,
+        -0.05019226628920524,
+    ],
+    [
+        0.02962780877005599,
+        0.9904344267538799,
+        -0.017073799063418826,
+    ],
+    [
+        -0.009243040646204504,
+        0.015055191490298152,
+        0.7518742814281371,
+    ],
+];
+const XYZ_TO_LMS: Matrix = [
+    [0.819022437996703, 0.3619062600528904, -0.1288737815209879],
+    [0.0329836539323885, 0.9292868615863434, 0.0361446663506424],
+    [0.0481771893596242, 0.2642395317527308, 0.6335478284694309],
+];
+const LMS_TO_XYZ: Matrix = [
+    [1.2268798758459243, -0.5578149944602171, 0.2813910456659647],
+    [-0.0405757452148008, 1.112286803280317, -0.0717110580655164],
+    [-0.0763729366746601, -0.4214933324022432, 1.5869240198367816],
+];
+const LMS_TO_OKLAB: Matrix = [
+    [0.210454268309314, 0.7936177747023054, -0.0040720430116193],
+    [1.9779985324311684, -2.42859224204858, 0.450593709617411],
+    [0.0259040424655478, 0.7827717124575296, -0.8086757549230774],
+];
+const OKLAB_TO_LMS: Matrix = [
+    [1., 0.3963377773761749, 0.2158037573099136],
+    [1., -0.1055613458156586, -0.0638541728258133],
+    [1., -0.0894841775298119, -1.2914855480194092],
+];
+const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1., (1. - 0.3457 - 0.3585) / 0.3585];
+
+fn srgb_to_linear(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs <= 0.04045 {
+        c / 12.92
+    } else {
+        c.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs > 0.0031308 {
+        c.signum() * (1.055 * abs.powf(1. / 2.4) - 0.055)
+    } else {
+        12.92 * c
+    }
+}
+
+fn a98_to_linear(c: f64) -> f64 {
+    c.signum() * c.abs().powf(563. / 256.)
+}
+
+fn linear_to_a98(c: f64) -> f64 {
+    c.signum() * c.abs().powf(256. / 563.)
+}
+
+fn prophoto_to_linear(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs <= 16. / 512. {
+        c / 16.
+    } else {
+        c.signum() * abs.powf(1.8)
+    }
+}
+
+fn linear_to_prophoto(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs >= 1. / 512. {
+        c.signum() * abs.powf(1. / 1.8)
+    } else {
+        16. * c
+    }
+}
+
+const REC2020_ALPHA: f64 = 1.09929682680944;
+const REC2020_BETA: f64 = 0.018053968510807;
+
+fn rec2020_to_linear(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs < REC2020_BETA * 4.5 {
+        c / 4.5
+    } else {
+        c.signum() * ((abs + REC2020_ALPHA - 1.) / REC2020_ALPHA).powf(1. / 0.45)
+    }
+}
+
+fn linear_to_rec2020(c: f64) -> f64 {
+    let abs = c.abs();
+    if abs > REC2020_BETA {
+        c.signum() * (REC2020_ALPHA * abs.powf(0.45) - (REC2020_ALPHA - 1.))
+    } else {
+        4.5 * c
+    }
+}
+
+const LAB_KAPPA: f64 = 24389. / 27.;
+const LAB_EPSILON: f64 = 216. / 24389.;
+
+fn lab_to_xyz_d50([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let f1 = (l + 16.) / 116.;
+    let f0 = a / 500. + f1;
+    let f2 = f1 - b / 200.;
+    let inverse = |f: f64| {
+        if f.powi(3) > LAB_EPSILON {
+            f.powi(3)
+        } else {
+            (116. * f - 16.) / LAB_KAPPA
+        }
+    };
+    let y = if l > LAB_KAPPA * LAB_EPSILON {
+        f1.powi(3)
+    } else {
+        l / LAB_KAPPA
+    };
+    [
+        inverse(f0) * D50_WHITE[0],
+        y * D50_WHITE[1],
+        inverse(f2) * D50_WHITE[2],
+    ]
+}
+
+fn xyz_d50_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let f = |c: f64| {
+        if c > LAB_EPSILON {
+            c.cbrt()
+        } else {
+            (LAB_KAPPA * c + 16.) / 116.
+        }
+    };
+    let f0 = f(xyz[0] / D50_WHITE[0]);
+    let f1 = f(xyz[1] / D50_WHITE[1]);
+    let f2 = f(xyz[2] / D50_WHITE[2]);
+    [116. * f1 - 16., 500. * (f0 - f1), 200. * (f1 - f2)]
+}
+
+/// Bring a hue into the range [0, 360) degrees.
+pub(crate) fn normalize_hue(degrees: f32) -> f32 {
+    let hue = degrees.rem_euclid(360.);
+    // Rounding can give 360 for tiny negative hues.
+    if hue == 360. {
+        0.
+    } else {
+        hue
+    }
+}
+
+fn polar_to_rectangular([l, c, h]: [f64; 3]) -> [f64; 3] {
+    let h = h.to_radians();
+    [l, c * h.cos(), c * h.sin()]
+}
+
+fn rectangular_to_polar([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.);
+    [l, a.hypot(b), hue]
+}
+
+/// HSL with saturation and lightness from 0 to 100, to sRGB.
+fn hsl_to_srgb([h, s, l]: [f64; 3]) -> [f64; 3] {
+    let (s, l) = (s / 100., l / 100.);
+    let f = |n: f64| {
+        let k = (n + h / 30.).rem_euclid(12.);
+        let a = s * l.min(1. - l);
+        l - a * (k - 3.).min(9. - k).clamp(-1., 1.)
+    };
+    [f(0.), f(8.), f(4.)]
+}
+
+fn srgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (min + max) / 2.;
+    let d = max - min;
+    let (mut hue, mut saturation) = (0., 0.);
+    if d != 0. {
+        saturation = if lightness == 0. || lightness == 1. {
+            0.
+        } else {
+            (max - lightness) / lightness.min(1. - lightness)
+        };
+        hue = if max == r {
+            (g - b) / d + if g < b { 6. } else { 0. }
+        } else if max == g {
+            (b - r) / d + 2.
+        } else {
+            (r - g) / d + 4.
+        } * 60.;
+    }
+    [hue, saturation * 100., lightness * 100.]
+}
+
+fn hwb_to_srgb([h, w, b]: [f64; 3]) -> [f64; 3] {
+    let (w, b) = (w / 100., b / 100.);
+    if w + b >= 1. {
+        let gray = w / (w + b);
+        return [gray, gray, gray];
+    }
+    map(hsl_to_srgb([h, 100., 50.]), |c| c * (1. - w - b) + w)
+}
+
+fn srgb_to_hwb(rgb: [f64; 3]) -> [f64; 3] {
+    let [hue, _, _] = srgb_to_hsl(rgb);
+    let white = rgb[0].min(rgb[1]).min(rgb[2]);
+    let black = 1. - rgb[0].max(rgb[1]).max(rgb[2]);
+    [hue, white * 100., black * 100.]
+}
+
+/// The color space of which a cylindrical color space is a polar form.
+fn rectangular_space(space: ColorSpace) -> ColorSpace {
+    match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => ColorSpace::Srgb,
+        ColorSpace::Lch => ColorSpace::Lab,
+        ColorSpace::Oklch => ColorSpace::Oklab,
+        _ => space,
+    }
+}
+
+fn convert(from: ColorSpace, to: ColorSpace, c: [f64; 3]) -> [f64; 3] {
+    // Avoid the rounding errors of a round trip through XYZ,
+    // such as between `hsl` and `srgb`.
+    if rectangular_space(from) != rectangular_space(to) {
+        return from_xyz_d65(to, to_xyz_d65(from, c));
+    }
+    let rectangular = match from {
+        ColorSpace::Hsl => hsl_to_srgb(c),
+        ColorSpace::Hwb => hwb_to_srgb(c),
+        ColorSpace::Lch | ColorSpace::Oklch => polar_to_rectangular(c),
+        _ => c,
+    };
+    match to {
+        ColorSpace::Hsl => srgb_to_hsl(rectangular),
+        ColorSpace::Hwb => srgb_to_hwb(rectangular),
+        ColorSpace::Lch | ColorSpace::Oklch => rectangular_to_polar(rectangular),
+        _ => rectangular,
+    }
+}
+
+fn to_xyz_d65(space: ColorSpace, c: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Srgb => multiply(&LINEAR_SRGB_TO_XYZ, map(c, srgb_to_linear)),
+        ColorSpace::SrgbLinear => multiply(&LINEAR_SRGB_TO_XYZ, c),
+        ColorSpace::DisplayP3 => multiply(&LINEAR_P3_TO_XYZ, map(c, srgb_to_linear)),
+        ColorSpace::A98Rgb => multiply(&LINEAR_A98_TO_XYZ, map(c, a98_to_linear)),
+        ColorSpace::ProphotoRgb => multiply(
+            &D50_TO_D65,
+            multiply(&LINEAR_PROPHOTO_TO_XYZ, map(c, prophoto_to_linear)),
+        ),
+        ColorSpace::Rec2020 => multiply(&LINEAR_REC2020_TO_XYZ, map(c, rec2020_to_linear)),
+        ColorSpace::XyzD50 => multiply(&D50_TO_D65, c),
+        ColorSpace::XyzD65 => c,
+        ColorSpace::Hsl => to_xyz_d65(ColorSpace::Srgb, hsl_to_srgb(c)),
+        ColorSpace::Hwb => to_xyz_d65(ColorSpace::Srgb, hwb_to_srgb(c)),
+        ColorSpace::Lab => multiply(&D50_TO_D65, lab_to_xyz_d50(c)),
+        ColorSpace::Lch => to_xyz_d65(ColorSpace::Lab, polar_to_rectangular(c)),
+        ColorSpace::Oklab => multiply(&LMS_TO_XYZ, map(multiply(&OKLAB_TO_LMS, c), |c| c.powi(3))),
+        ColorSpace::Oklch => to_xyz_d65(ColorSpace::Oklab, polar_to_rectangular(c)),
+    }
+}
+
+fn from_xyz_d65(space: ColorSpace, xyz: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Srgb => map(multiply(&XYZ_TO_LINEAR_SRGB, xyz), linear_to_srgb),
+        ColorSpace::SrgbLinear => multiply(&XYZ_TO_LINEAR_SRGB, xyz),
+        ColorSpace::DisplayP3 => map(multiply(&XYZ_TO_LINEAR_P3, xyz), linear_to_srgb),
+        ColorSpace::A98Rgb => map(multiply(&XYZ_TO_LINEAR_A98, xyz), linear_to_a98),
+        ColorSpace::ProphotoRgb => map(
+            multiply(&XYZ_TO_LINEAR_PROPHOTO, multiply(&D65_TO_D50, xyz)),
+            linear_to_prophoto,
+        ),
+        ColorSpace::Rec2020 => map(multiply(&XYZ_TO_LINEAR_REC2020, xyz), linear_to_rec2020),
+        ColorSpace::XyzD50 => multiply(&D65_TO_D50, xyz),
+        ColorSpace::XyzD65 => xyz,
+        ColorSpace::Hsl => srgb_to_hsl(from_xyz_d65(ColorSpace::Srgb, xyz)),
+        ColorSpace::Hwb => srgb_to_hwb(from_xyz_d65(ColorSpace::Srgb, xyz)),
+        ColorSpace::Lab => xyz_d50_to_lab(multiply(&D65_TO_D50, xyz)),
+        ColorSpace::Lch => rectangular_to_polar(from_xyz_d65(ColorSpace::Lab, xyz)),
+        ColorSpace::Oklab => multiply(&LMS_TO_OKLAB, map(multiply(&XYZ_TO_LMS, xyz), f64::cbrt)),
+        ColorSpace::Oklch => rectangular_to_polar(from_xyz_d65(ColorSpace::Oklab, xyz)),
+    }
+}
+
+/// Whether the hue of these components of a cylindrical color space is powerless.
+fn is_achromatic(space: ColorSpace, c: [f64; 3]) -> bool {
+    match space {
+        ColorSpace::Hsl => c[1].abs() < 1e-4,
+        ColorSpace::Hwb => c[1] + c[2] >= 100. - 1e-4,
+        ColorSpace::Lch => c[1] < 1.5e-3,
+        ColorSpace::Oklch => c[1] < 4e-6,
+        _ => false,
+    }
+}
@@ -0,0 +1,199 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The boolean condition grammar shared by `@media`, `@supports` and `@container`:
+//! https://drafts.csswg.org/mediaqueries-4/#mq-syntax
+
+use std::fmt;
+
+use super::{ParseError, Parser, ToCss, Token};
+use crate::serializer::write_separated;
+
+/// A condition made of `not`, `and` and `or` operators over leaves of type `L`,
+/// such as `<media-condition>` or `<supports-condition>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition<L> {
+    /// A leaf of the condition, such as a media feature or a declaration test.
+    /// Leaves serialize their own parentheses, if any.
+    Leaf(L),
+    /// `not <in-parens>`
+    Not(Box<Condition<L>>),
+    /// `<in-parens> [ and <in-parens> ]+`
+    And(Vec<Condition<L>>),
+    /// `<in-parens> [ or <in-parens> ]+`
+    Or(Vec<Condition<L>>),
+    /// A nested condition in parentheses.
+    InParens(Box<Condition<L>>),
+    /// A `<general-enclosed>` function or parenthesized block,
+    /// kept as its source text.
+    ///
+    /// This is reserved for future extensions and always evaluates to “unknown”.
+    GeneralEnclosed(String),
+}
+
+impl<L> Condition<L> {
+    /// Parse a condition, with `parse_leaf` called at each `<*-in-parens>` position
+    /// that is not a nested condition.
+    ///
+    /// If `allow_or` is false, this parses the `<*-condition-without-or>` production
+    /// used after `and` in media queries.
+    ///
+    /// Whatever `parse_leaf` rejects is parsed as `<general-enclosed>` if possible.
+    pub fn parse_with<'i, 't, P, E>(
+        input: &mut Parser<'i, 't>,
+        allow_or: bool,
+        parse_leaf: &mut P,
+    ) -> Result<Self, ParseError<'i, E>>
+    where
+        P: for<'tt> FnMut(&mut Parser<'i, 'tt>) -> Result<L, ParseError<'i, E>>,
+    {
+        if input
+            .try_parse(|input| input.expect_ident_matching("not"))
+            .is_ok()
+        {
+            let inner = Self::parse_in_parens(input, parse_leaf)?;
+            return Ok(Condition::Not(Box::new(inner)));
+        }
+
+        let first = Self::parse_in_parens(input, parse_leaf)?;
+        let state = input.state();
+        let is_and = match input.next() {
+            Ok(Token::Ident(ident)) if ident.eq_ignore_ascii_case("and") => true,
+            Ok(Token::Ident(ident)) if allow_or && ident.eq_ignore_ascii_case("or") => false,
+            _ => {
+                input.reset(&state);
+                return Ok(first);
+            }
+        };
+        let keyword = if is_and { "and" } else { "or" };
+        let mut conditions = vec![first, Self::parse_in_parens(input, parse_leaf)?];
+        while input
+            .try_parse(|input| input.expect_ident_matching(keyword))
+            .is_ok()
+        {
+            conditions.push(Self::parse_in_parens(input, parse_leaf)?);
+        }
+        Ok(if is_and {
+            Condition::And(conditions)
+        } else {
+            Condition::Or(conditions)
+        })
+    }
+
+    /// Parse a single `<*-in-parens>` component.
+    pub fn parse_in_parens<'i, 't, P, E>(
+        input: &mut Parser<'i, 't>,
+        parse_leaf: &mut P,
+    ) -> Result<Self, ParseError<'i, E>>
+    where
+        P: for<'tt> FnMut(&mut Parser<'i, 'tt>) -> Result<L, ParseError<'i, E>>,
+    {
+        let nested = input.try_parse(|input| {
+            input.expect_parenthesis_block()?;
+            input.parse_nested_block(|input| Self::parse_with(input, true, parse_leaf))
+        });
+        if let Ok(nested) = nested {
+            return Ok(Condition::InParens(Box::new(nested)));
+        }
+        if let Ok(leaf) = input.try_parse(|input| parse_leaf(input)) {
+            return Ok(Condition::Leaf(leaf));
+        }
+
+        // <general-enclosed> = [ <function-token> <any-value>? ) ] | ( <any-value>? )
+        input.skip_whitespace();
+        let start = input.position();
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::Function(_) | Token::ParenthesisBlock => {}
+            ref token => return Err(location.new_unexpected_token_error(token.clone())),
+        }
+        input.parse_nested_block(|input| {
+            while input.next().is_ok() {}
+            Ok(())
+        })?;
+        Ok(Condition::GeneralEnclosed(
+            input.slice_from(start).to_owned(),
+        ))
+    }
+
+    /// Evaluate the condition with three-valued logic,
+    /// given a function that evaluates leaves.
+    ///
+    /// `None` means “unknown”, as for `<general-enclosed>`.
+    /// Callers usually treat an unknown result at the top level as false.
+    pub fn evaluate<F>(&self, evaluate_leaf: &mut F) -> Option<bool>
+    where
+        F: FnMut(&L) -> Option<bool>,
+    {
+        self.evaluate_with_general_enclosed(None, evaluate_leaf)
+    }
+
+    /// Like `evaluate`, but with the given result for `<general-enclosed>`.
+    ///
+    /// `@supports` conditions use `Some(false)` here,
+    /// where media queries use “unknown”.
+    pub fn evaluate_with_general_enclosed<F>(
+        &self,
+        general_enclosed: Option<bool>,
+        evaluate_leaf: &mut F,
+    ) -> Option<bool>
+    where
+        F: FnMut(&L) -> Option<bool>,
+    {
+        let mut evaluate = |condition: &Condition<L>| {
+            condition.evaluate_with_general_enclosed(general_enclosed, evaluate_leaf)
+        };
+        match *self {
+            Condition::Leaf(ref leaf) => evaluate_leaf(leaf),
+            Condition::Not(ref inner) => evaluate(inner).map(|result| !result),
+            Condition::InParens(ref inner) => evaluate(inner),
+            Condition::GeneralEnclosed(_) => general_enclosed,
+            Condition::And(ref conditions) => {
+                let mut result = Some(true);
+                for condition in conditions {
+                    match evaluate(condition) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                result
+            }
+            Condition::Or(ref conditions) => {
+                let mut result = Some(false);
+                for condition in conditions {
+                    match evaluate(condition) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+impl<L: ToCss> ToCss for Condition<L> {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            Condition::Leaf(ref leaf) => leaf.to_css(dest),
+            Condition::Not(ref inner) => {
+                dest.write_str("not ")?;
+                inner.to_css(dest)
+            }
+            Condition::InParens(ref inner) => {
+                dest.write_char('(')?;
+                inner.to_css(dest)?;
+                dest.write_char(')')
+            }
+            Condition::GeneralEnclosed(ref text) => dest.write_str(text),
+            Condition::And(ref conditions) => write_separated(conditions, " and ", dest),
+            Condition::Or(ref conditions) => write_separated(conditions, " or ", dest),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-conditional-5/#container-queries
+
+use std::fmt;
+
+use super::{BasicParseError, Condition, MediaFeature, ParseError, Parser, ToCss, Token};
+use crate::media_queries::FeatureValue;
+use crate::serializer::serialize_identifier;
+
+/// A `<container-query>`: `not`, `and` and `or` over container features.
+pub type ContainerCondition = Condition<ContainerFeature>;
+
+/// A `<container-condition>`, as found in the prelude of `@container` rules:
+/// an optional container name followed by an optional query, at least one being present.
+///
+/// The prelude is a comma-separated list of these, which can be parsed with
+/// `Parser::parse_comma_separated`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerQuery {
+    /// The `<container-name>`, if any.
+    pub name: Option<String>,
+    /// The query, if any.
+    pub condition: Option<ContainerCondition>,
+}
+
+impl ContainerQuery {
+    /// Parse a `<container-condition>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let name = input
+            .try_parse(|input| -> Result<_, BasicParseError<'i>> {
+                let location = input.current_source_location();
+                let ident = input.expect_ident()?;
+                match_ignore_ascii_case! { ident,
+                    "none" | "and" | "not" | "or" => {
+                        Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone())))
+                    },
+                    _ => Ok(ident.to_string()),
+                }
+            })
+            .ok();
+        let condition = if name.is_some() && input.is_exhausted() {
+            None
+        } else {
+            let condition: Result<_, ParseError<()>> =
+                ContainerCondition::parse_with(input, true, &mut |input| {
+                    ContainerFeature::parse(input).map_err(ParseError::from)
+                });
+            Some(condition.map_err(ParseError::basic)?)
+        };
+        Ok(ContainerQuery { name, condition })
+    }
+
+    /// Whether the given container is named as required by this query,
+    /// and matches its condition.
+    ///
+    /// Finding the query container among the ancestors of an element is left to the caller,
+    /// who would typically try the nearest ancestors first.
+    /// A condition that evaluates to “unknown” makes the query not match.
+    pub fn matches<C: QueryContainer + ?Sized>(&self, container: &C) -> bool {
+        if let Some(ref name) = self.name {
+            if !container.has_name(name) {
+                return false;
+            }
+        }
+        match self.condition {
+            Some(ref condition) => condition
+                .evaluate(&mut |feature| feature.evaluate(container))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl ToCss for ContainerQuery {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if let Some(ref name) = self.name {
+            serialize_identifier(name, dest)?;
+            if self.condition.is_some() {
+                dest.write_char(' ')?;
+            }
+        }
+        if let Some(ref condition) = self.condition {
+            condition.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `<query-in-parens>` leaf of a container query.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContainerFeature {
+    /// A `<size-feature>`, with the same syntax as media features,
+    /// such as `(min-width: 400px)` or `(400px < inline-size)`.
+    Size(MediaFeature),
+    /// `style( <style-query> )`
+    Style(StyleQuery),
+    /// `scroll-state( <scroll-state-query> )`
+    ScrollState(ScrollStateQuery),
+}
+
+impl ContainerFeature {
+    /// Parse a size feature in parentheses, or a `style()` or `scroll-state()` function.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if let Ok(feature) = input.try_parse(MediaFeature::parse) {
+            return Ok(ContainerFeature::Size(feature));
+        }
+        let location = input.current_source_location();
+        let function = input.expect_function()?.clone();
+        let result: Result<_, ParseError<()>> = input.parse_nested_block(|input| {
+            Ok(match_ignore_ascii_case! { &function,
+                "style" => ContainerFeature::Style(StyleQuery::parse(input)?),
+                "scroll-state" => ContainerFeature::ScrollState(ScrollStateQuery::parse(input)?),
+                _ => return Err(location.new_unexpected_token_error(Token::Function(function.clone()))),
+            })
+        });
+        result.map_err(ParseError::basic)
+    }
+
+    /// Evaluate this feature against the given container.
+    pub fn evaluate<C: QueryContainer + ?Sized>(&self, container: &C) -> Option<bool> {
+        match *self {
+            ContainerFeature::Size(ref feature) => feature
+                .evaluate_with(container.font_size(), &mut |name| {
+                    size_feature(container, name)
+                }),
+            ContainerFeature::Style(ref query) => query.evaluate(container),
+            ContainerFeature::ScrollState(ref query) => query.evaluate(container),
+        }
+    }
+}
+
+impl ToCss for ContainerFeature {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            ContainerFeature::Size(ref feature) => feature.to_css(dest),
+            ContainerFeature::Style(ref query) => {
+                dest.write_str("style(")?;
+                query.to_css(dest)?;
+                dest.write_char(')')
+            }
+            ContainerFeature::ScrollState(ref query) => {
+                dest.write_str("scroll-state(")?;
+                query.to_css(dest)?;
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+fn size_feature<C: QueryContainer + ?Sized>(container: &C, name: &str) -> Option<FeatureValue> {
+    Some(match_ignore_ascii_case! { name,
+        "width" => FeatureValue::Length(container.width()?),
+        "height" => FeatureValue::Length(container.height()?),
+        "inline-size" => FeatureValue::Length(container.inline_size()?),
+        "block-size" => FeatureValue::Length(container.block_size()?),
+        "aspect-ratio" => FeatureValue::Ratio(container.width()?, container.height()?),
+        "orientation" => {
+            let portrait = container.height()? >= container.width()?;
+            FeatureValue::Ident(if portrait { "portrait" } else { "landscape" }.to_owned())
+        },
+        _ => return None,
+    })
+}
+
+/// The argument of `style()`: a single `<style-feature>`, or a condition over them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleQuery {
+    /// A single feature without parentheses, as in `style(--responsive: true)`.
+    Feature(StyleFeature),
+    /// A condition, as in `style((--a: 1) or (--b: 2))`.
+    Condition(Condition<StyleFeature>),
+}
+
+impl StyleQuery {
+    /// Parse the argument of `style()`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let condition: Result<_, ParseError<()>> = input.try_parse(|input| {
+            input.parse_entirely(|input| {
+                Condition::parse_with(input, true, &mut |input| {
+                    StyleFeature::parse(input).map_err(ParseError::from)
+                })
+            })
+        });
+        if let Ok(condition) = condition {
+            return Ok(StyleQuery::Condition(condition));
+        }
+        StyleFeature::parse_inside_parens(input).map(StyleQuery::Feature)
+    }
+
+    /// Evaluate this query against the computed style of the given container.
+    pub fn evaluate<C: QueryContainer + ?Sized>(&self, container: &C) -> Option<bool> {
+        match *self {
+            StyleQuery::Feature(ref feature) => feature.evaluate(container),
+            StyleQuery::Condition(ref condition) => {
+                condition.evaluate(&mut |feature| feature.evaluate(container))
+            }
+        }
+    }
+}
+
+impl ToCss for StyleQuery {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            StyleQuery::Feature(ref feature) => feature.to_css_inside_parens(dest),
+            StyleQuery::Condition(ref condition) => condition.to_css(dest),
+        }
+    }
+}
+
+/// A `<style-feature>`, including its parentheses: `(<property>: <value>)` or `(<property>)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleFeature {
+    /// The property name, ASCII-lowercased unless it is a custom property.
+    pub name: String,
+    /// The value as source text, with surrounding whitespace removed.
+    /// `None` tests whether the property has a value other than its initial value.
+    pub value: Option<String>,
+}
+
+impl StyleFeature {
+    /// Parse a `<style-feature>`, including its parentheses.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        input.expect_parenthesis_block()?;
+        let result: Result<_, ParseError<()>> =
+            input.parse_nested_block(|input| Ok(Self::parse_inside_parens(input)?));
+        result.map_err(ParseError::basic)
+    }
+
+    /// Parse a `<style-feature>` without its parentheses.
+    pub fn parse_inside_parens<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, BasicParseError<'i>> {
+        let name = input.expect_ident()?;
+        let name = if name.starts_with("--") {
+            name.to_string()
+        } else {
+            name.to_ascii_lowercase()
+        };
+        if input.is_exhausted() {
+            return Ok(StyleFeature { name, value: None });
+        }
+        input.expect_colon()?;
+        input.skip_whitespace();
+        let start = input.position();
+        while input.next().is_ok() {}
+        let value = input.slice_from(start).trim_end().to_owned();
+        Ok(StyleFeature {
+            name,
+            value: Some(value),
+        })
+    }
+
+    /// Evaluate this feature against the computed style of the given container.
+    pub fn evaluate<C: QueryContainer + ?Sized>(&self, container: &C) -> Option<bool> {
+        match self.value {
+            Some(ref value) => container.style_matches(&self.name, value),
+            None => container.has_non_initial_style(&self.name),
+        }
+    }
+
+    /// Serialize this feature without the surrounding parentheses.
+    pub fn to_css_inside_parens<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        serialize_identifier(&self.name, dest)?;
+        if let Some(ref value) = self.value {
+            dest.write_str(": ")?;
+            dest.write_str(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for StyleFeature {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_char('(')?;
+        self.to_css_inside_parens(dest)?;
+        dest.write_char(')')
+    }
+}
+
+/// The argument of `scroll-state()`: a single feature, or a condition over them.
+///
+/// Scroll-state features use the media feature syntax, such as `stuck: top`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScrollStateQuery {
+    /// A single feature without parentheses, as in `scroll-state(stuck: top)`.
+    Feature(MediaFeature),
+    /// A condition, as in `scroll-state((stuck: top) or (snapped: x))`.
+    Condition(Condition<MediaFeature>),
+}
+
+impl ScrollStateQuery {
+    /// Parse the argument of `scroll-state()`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let condition: Result<_, ParseError<()>> = input.try_parse(|input| {
+            input.parse_entirely(|input| {
+                Condition::parse_with(input, true, &mut |input| {
+                    MediaFeature::parse(input).map_err(ParseError::from)
+                })
+            })
+        });
+        if let Ok(condition) = condition {
+            return Ok(ScrollStateQuery::Condition(condition));
+        }
+        MediaFeature::parse_inside_parens(input).map(ScrollStateQuery::Feature)
+    }
+
+    /// Evaluate this query against the scroll state of the given container.
+    pub fn evaluate<C: QueryContainer + ?Sized>(&self, container: &C) -> Option<bool> {
+        let mut evaluate = |feature: &MediaFeature| {
+            feature.evaluate_with(container.font_size(), &mut |name| {
+                Some(FeatureValue::Ident(
+                    container.scroll_state(name)?.to_ascii_lowercase(),
+                ))
+            })
+        };
+        match *self {
+            ScrollStateQuery::Feature(ref feature) => evaluate(feature),
+            ScrollStateQuery::Condition(ref condition) => condition.evaluate(&mut evaluate),
+        }
+    }
+}
+
+impl ToCss for ScrollStateQuery {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            ScrollStateQuery::Feature(ref feature) => feature.to_css_inside_parens(dest),
+            ScrollStateQuery::Condition(ref condition) => condition.to_css(dest),
+        }
+    }
+}
+
+/// A query container that container queries are evaluated against.
+///
+/// Lengths are in CSS pixels. Size features evaluate to “unknown” in an axis
+/// where the container has no size containment, which is what `None` means here.
+pub trait QueryContainer {
+    /// Whether `container-name` includes the given name.
+    fn has_name(&self, name: &str) -> bool;
+
+    /// The width of the content box, if the container is a size container in that axis.
+    fn width(&self) -> Option<f32>;
+
+    /// The height of the content box, if the container is a size container in that axis.
+    fn height(&self) -> Option<f32>;
+
+    /// The size in the inline axis. Defaults to the width (horizontal writing mode).
+    fn inline_size(&self) -> Option<f32> {
+        self.width()
+    }
+
+    /// The size in the block axis. Defaults to the height (horizontal writing mode).
+    fn block_size(&self) -> Option<f32> {
+        self.height()
+    }
+
+    /// The computed font size of the container, used for `em` and other font-relative units.
+    fn font_size(&self) -> f32 {
+        16.
+    }
+
+    /// The computed value of the given property, serialized,
+    /// or `None` for unsupported properties.
+    ///
+    /// `name` is ASCII-lowercased unless it is a custom property.
+    fn computed_value(&self, name: &str) -> Option<String>;
+
+    /// Whether the computed value of the given property matches `value`,
+    /// or `None` if this is unknown.
+    ///
+    /// The default implementation compares serializations, which works for custom properties
+    /// but not values that need computing, such as `1em` against `16px`.
+    fn style_matches(&self, name: &str, value: &str) -> Option<bool> {
+        Some(self.computed_value(name)?.trim() == value)
+    }
+
+    /// Whether the given property has a value other than its initial value.
+    ///
+    /// The default implementation only supports custom properties,
+    /// whose initial value is the guaranteed-invalid value (an empty serialization).
+    fn has_non_initial_style(&self, name: &str) -> Option<bool> {
+        if !name.starts_with("--") {
+            return None;
+        }
+        Some(match self.computed_value(name) {
+            Some(value) => !value.trim().is_empty(),
+            None => false,
+        })
+    }
+
+    /// The keyword value of a scroll-state feature, such as `top` for `stuck`,
+    /// or `None` if the container is not a scroll-state container.
+    fn scroll_state(&self, name: &str) -> Option<&str> {
+        let _ = name;
+        None
+    }
+}
@@ -0,0 +1,159 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Luminance, contrast and alpha compositing of `RGBA` colors, for accessibility checks.
+
+use super::{AbsoluteColor, ColorInterpolationMethod, ColorSpace, RGBA};
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl RGBA {
+    /// The red, green and blue components in linear light, from 0 to 1.
+    pub fn linear_rgb(&self) -> [f32; 3] {
+        [
+            srgb_to_linear(self.red_f32()),
+            srgb_to_linear(self.green_f32()),
+            srgb_to_linear(self.blue_f32()),
+        ]
+    }
+
+    /// The relative luminance, from 0 for black to 1 for white, ignoring the alpha.
+    ///
+    /// https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+    pub fn relative_luminance(&self) -> f32 {
+        let [red, green, blue] = self.linear_rgb();
+        // In double precision so that white is exactly 1, and black on white 21.
+        (0.2126 * f64::from(red) + 0.7152 * f64::from(green) + 0.0722 * f64::from(blue)) as f32
+    }
+
+    /// The WCAG 2 contrast ratio with another color, from 1 to 21, ignoring the alphas.
+    ///
+    /// Translucent colors should be composited with `RGBA::over` first.
+    ///
+    /// https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+    pub fn contrast_ratio(&self, other: &RGBA) -> f32 {
+        let l1 = f64::from(self.relative_luminance());
+        let l2 = f64::from(other.relative_luminance());
+        ((l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)) as f32
+    }
+
+    /// The APCA lightness contrast `Lc` of this color as text on `background`,
+    /// ignoring the alphas: about 106 for black on white, and -108 for white on black.
+    ///
+    /// https://github.com/Myndex/apca-w3 (version 0.0.98G-4g)
+    pub fn apca_contrast(&self, background: &RGBA) -> f32 {
+        const BLACK_THRESHOLD: f32 = 0.022;
+        const BLACK_CLAMP: f32 = 1.414;
+        const DELTA_Y_MIN: f32 = 0.0005;
+        const SCALE: f32 = 1.14;
+        const LOW_OFFSET: f32 = 0.027;
+        const LOW_CLIP: f32 = 0.1;
+
+        let luminance = |color: &RGBA| {
+            let y = 0.2126729 * color.red_f32().powf(2.4)
+                + 0.7151522 * color.green_f32().powf(2.4)
+                + 0.0721750 * color.blue_f32().powf(2.4);
+            if y < BLACK_THRESHOLD {
+                y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+            } else {
+                y
+            }
+        };
+        let (text, background) = (luminance(self), luminance(background));
+        if (background - text).abs() < DELTA_Y_MIN {
+            return 0.;
+        }
+        let contrast = if background > text {
+            // Dark text on a light background.
+            let contrast = (background.powf(0.56) - text.powf(0.57)) * SCALE;
+            if contrast < LOW_CLIP {
+                0.
+            } else {
+                contrast - LOW_OFFSET
+            }
+        } else {
+            // Light text on a dark background.
+            let contrast = (background.powf(0.65) - text.powf(0.62)) * SCALE;
+            if contrast > -LOW_CLIP {
+                0.
+            } else {
+                contrast + LOW_OFFSET
+            }
+        };
+        contrast * 100.
+    }
+
+    /// Composite this color over a backdrop with the source-over operator,
+    /// in gamma-encoded sRGB like web browsers.
+    ///
+    /// https://drafts.fxtf.org/compositing-1/#porterduffcompositingoperators_srcover
+    pub fn over(&self, backdrop: &RGBA) -> RGBA {
+        let (source_alpha, backdrop_alpha) = (self.alpha_f32(), backdrop.alpha_f32());
+        let alpha = source_alpha + backdrop_alpha * (1. - source_alpha);
+        if alpha == 0. {
+            return RGBA::transparent();
+        }
+        let composite = |source: f32, backdrop: f32| {
+            (source * source_alpha + backdrop * backdrop_alpha * (1. - source_alpha)) / alpha
+        };
+        RGBA::from_floats(
+            composite(self.red_f32(), backdrop.red_f32()),
+            composite(self.green_f32(), backdrop.green_f32()),
+            composite(self.blue_f32(), backdrop.blue_f32()),
+            alpha,
+        )
+    }
+
+    /// The color closest to this one, in OKLab, whose WCAG 2 contrast ratio
+    /// with `background` is at least `target`: this color if it already is,
+    /// or this color mixed with black or white in OKLab.
+    ///
+    /// Returns `None` if neither black nor white reach the target.
+    /// The alphas are ignored.
+    pub fn nearest_with_contrast(&self, background: &RGBA, target: f32) -> Option<RGBA> {
+        let opaque = RGBA {
+            alpha: 255,
+            ..*self
+        };
+        if opaque.contrast_ratio(background) >= target {
+            return Some(opaque);
+        }
+        let start = AbsoluteColor::from(opaque);
+        let method = ColorInterpolationMethod::new(ColorSpace::Oklab);
+        let distance = |color: &RGBA| {
+            let [l1, a1, b1] = start.to_space(ColorSpace::Oklab).resolved_components();
+            let [l2, a2, b2] = AbsoluteColor::from(*color)
+                .to_space(ColorSpace::Oklab)
+                .resolved_components();
+            ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+        };
+        [RGBA::new(0, 0, 0, 255), RGBA::new(255, 255, 255, 255)]
+            .iter()
+            .filter(|end| end.contrast_ratio(background) >= target)
+            .map(|end| {
+                // Binary search of the smallest mix reaching the target. This color does not,
+                // and past the luminance of the background the contrast only increases.
+                let end = AbsoluteColor::from(*end);
+                let mix = |progress| start.interpolate(&end, progress, method).to_rgba();
+                let (mut low, mut high) = (0., 1.);
+                while high - low > 1. / 1024. {
+                    let middle = (low + high) / 2.;
+                    if mix(middle).contrast_ratio(background) >= target {
+                        high = middle
+                    } else {
+                        low = middle
+                    }
+                }
+                mix(high)
+            })
+            .filter(|color| color.contrast_ratio(background) >= target)
+            .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap())
+    }
+}
@@ -0,0 +1,1075 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-counter-styles-3/
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{
+    AtRuleParser, BasicParseError, CowRcStr, DeclarationListParser, DeclarationParser, ParseError,
+    Parser, ParserInput, ToCss, Token,
+};
+use crate::parser::is_css_wide_keyword;
+use crate::serializer::{
+    serialize_identifier, serialize_string, write_separated, DeclarationWriter,
+};
+
+/// The names of the counter styles predefined by CSS Counter Styles,
+/// which are matched ASCII case-insensitively.
+///
+/// The complex predefined styles (Chinese, Japanese, Korean and Ethiopic numbering)
+/// need their own algorithms, and are not supported.
+const PREDEFINED_NAMES: &[&str] = &[
+    "decimal",
+    "decimal-leading-zero",
+    "arabic-indic",
+    "armenian",
+    "upper-armenian",
+    "lower-armenian",
+    "bengali",
+    "cambodian",
+    "khmer",
+    "cjk-decimal",
+    "devanagari",
+    "georgian",
+    "gujarati",
+    "gurmukhi",
+    "hebrew",
+    "kannada",
+    "lao",
+    "malayalam",
+    "mongolian",
+    "myanmar",
+    "oriya",
+    "persian",
+    "lower-roman",
+    "upper-roman",
+    "tamil",
+    "telugu",
+    "thai",
+    "tibetan",
+    "lower-alpha",
+    "lower-latin",
+    "upper-alpha",
+    "upper-latin",
+    "lower-greek",
+    "hiragana",
+    "hiragana-iroha",
+    "katakana",
+    "katakana-iroha",
+    "disc",
+    "circle",
+    "square",
+    "disclosure-open",
+    "disclosure-closed",
+    "cjk-earthly-branch",
+    "cjk-heavenly-stem",
+];
+
+/// A `<counter-style-name>`.
+///
+/// Names are case-sensitive, except that the names of predefined counter styles
+/// are ASCII-lowercased when parsed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CounterStyleName(pub String);
+
+impl CounterStyleName {
+    /// Parse a `<counter-style-name>`: a `<custom-ident>` other than `none`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let name = input.expect_ident_cloned()?;
+        if name.eq_ignore_ascii_case("none") || is_css_wide_keyword(&name) {
+            return Err(location.new_basic_unexpected_token_error(Token::Ident(name.clone())));
+        }
+        let lowercase = name.to_ascii_lowercase();
+        if PREDEFINED_NAMES.contains(&&*lowercase) {
+            return Ok(CounterStyleName(lowercase));
+        }
+        Ok(CounterStyleName(name.as_ref().to_owned()))
+    }
+
+    /// Parse the prelude of a `@counter-style` rule, after the at-keyword.
+    ///
+    /// This also rejects the names of the predefined counter styles
+    /// that can not be overridden, such as `decimal` and `disc`.
+    pub fn parse_rule_name<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let name = Self::parse(input)?;
+        if !name.can_be_defined() {
+            return Err(location.new_basic_unexpected_token_error(Token::Ident(name.0.into())));
+        }
+        Ok(name)
+    }
+
+    fn can_be_defined(&self) -> bool {
+        !matches!(
+            &*self.0,
+            "decimal" | "disc" | "square" | "circle" | "disclosure-open" | "disclosure-closed"
+        )
+    }
+}
+
+impl ToCss for CounterStyleName {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        serialize_identifier(&self.0, dest)
+    }
+}
+
+/// A `<symbol>`. Images are not supported.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    /// A `<string>`.
+    String(String),
+    /// A `<custom-ident>`, which stands for its own name.
+    Ident(String),
+}
+
+impl Symbol {
+    /// Parse a `<symbol>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::QuotedString(ref symbol) => Ok(Symbol::String(symbol.as_ref().to_owned())),
+            Token::Ident(ref symbol) => Ok(Symbol::Ident(symbol.as_ref().to_owned())),
+            ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+        }
+    }
+
+    /// The text this symbol stands for.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Symbol::String(ref symbol) | Symbol::Ident(ref symbol) => symbol,
+        }
+    }
+}
+
+impl ToCss for Symbol {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            Symbol::String(ref symbol) => serialize_string(symbol, dest),
+            Symbol::Ident(ref symbol) => serialize_identifier(symbol, dest),
+        }
+    }
+}
+
+/// The `system` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum System {
+    /// `cyclic`
+    Cyclic,
+    /// `numeric`
+    Numeric,
+    /// `alphabetic`
+    Alphabetic,
+    /// `symbolic`, the initial value.
+    Symbolic,
+    /// `additive`
+    Additive,
+    /// `fixed <integer>?`, with the value of the first symbol (1 by default).
+    Fixed(i32),
+    /// `extends <counter-style-name>`
+    Extends(CounterStyleName),
+}
+
+impl System {
+    /// Parse the `system` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident_cloned()?;
+        Ok(match_ignore_ascii_case! { &ident,
+            "cyclic" => System::Cyclic,
+            "numeric" => System::Numeric,
+            "alphabetic" => System::Alphabetic,
+            "symbolic" => System::Symbolic,
+            "additive" => System::Additive,
+            "fixed" => System::Fixed(input.try_parse(|input| input.expect_integer()).unwrap_or(1)),
+            "extends" => System::Extends(CounterStyleName::parse(input)?),
+            _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone()))),
+        })
+    }
+
+    /// Whether counter styles with this system use the `negative` descriptor.
+    /// (This is unknown for `extends`, which depends on the extended style.)
+    fn uses_negative(&self) -> bool {
+        match *self {
+            System::Numeric | System::Alphabetic | System::Symbolic | System::Additive => true,
+            System::Cyclic | System::Fixed(_) | System::Extends(_) => false,
+        }
+    }
+
+    /// The range used for `range: auto`.
+    fn auto_range(&self) -> CounterRange {
+        match *self {
+            System::Alphabetic | System::Symbolic => CounterRange {
+                start: Some(1),
+                end: None,
+            },
+            System::Additive => CounterRange {
+                start: Some(0),
+                end: None,
+            },
+            _ => CounterRange {
+                start: None,
+                end: None,
+            },
+        }
+    }
+}
+
+impl ToCss for System {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            System::Cyclic => dest.write_str("cyclic"),
+            System::Numeric => dest.write_str("numeric"),
+            System::Alphabetic => dest.write_str("alphabetic"),
+            System::Symbolic => dest.write_str("symbolic"),
+            System::Additive => dest.write_str("additive"),
+            System::Fixed(first) => {
+                dest.write_str("fixed ")?;
+                first.to_css(dest)
+            }
+            System::Extends(ref name) => {
+                dest.write_str("extends ")?;
+                name.to_css(dest)
+            }
+        }
+    }
+}
+
+/// One entry of the `additive-symbols` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdditiveSymbol {
+    /// The non-negative weight.
+    pub weight: i32,
+    /// The symbol.
+    pub symbol: Symbol,
+}
+
+impl AdditiveSymbol {
+    /// Parse `<integer [0,∞]> && <symbol>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let symbol = input.try_parse(Symbol::parse).ok();
+        let weight = parse_non_negative_integer(input)?;
+        let symbol = match symbol {
+            Some(symbol) => symbol,
+            None => Symbol::parse(input)?,
+        };
+        Ok(AdditiveSymbol { weight, symbol })
+    }
+}
+
+impl ToCss for AdditiveSymbol {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.weight.to_css(dest)?;
+        dest.write_char(' ')?;
+        self.symbol.to_css(dest)
+    }
+}
+
+/// The `negative` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Negative {
+    /// The symbol added before the representation of negative values.
+    pub prefix: Symbol,
+    /// The symbol added after it, if any.
+    pub suffix: Option<Symbol>,
+}
+
+impl ToCss for Negative {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.prefix.to_css(dest)?;
+        if let Some(ref suffix) = self.suffix {
+            dest.write_char(' ')?;
+            suffix.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// One range of the `range` descriptor. `None` is `infinite`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CounterRange {
+    /// The lower bound, inclusive.
+    pub start: Option<i32>,
+    /// The upper bound, inclusive.
+    pub end: Option<i32>,
+}
+
+impl CounterRange {
+    /// Parse `[ <integer> | infinite ]{2}`. The lower bound must not be greater than the upper.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let parse_bound = |input: &mut Parser<'i, 't>| -> Result<_, BasicParseError<'i>> {
+            if input
+                .try_parse(|input| input.expect_ident_matching("infinite"))
+                .is_ok()
+            {
+                return Ok(None);
+            }
+            Ok(Some(input.expect_integer()?))
+        };
+        let start = parse_bound(input)?;
+        let location = input.current_source_location();
+        let end = parse_bound(input)?;
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(location.new_basic_unexpected_token_error(Token::Number {
+                    has_sign: end < 0,
+                    value: end as f32,
+                    int_value: Some(end),
+                }));
+            }
+        }
+        Ok(CounterRange { start, end })
+    }
+
+    /// Whether this range includes the given value.
+    pub fn contains(&self, value: i32) -> bool {
+        self.start.iter().all(|&start| start <= value) && self.end.iter().all(|&end| value <= end)
+    }
+}
+
+impl ToCss for CounterRange {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.start {
+            Some(start) => start.to_css(dest)?,
+            None => dest.write_str("infinite")?,
+        }
+        dest.write_char(' ')?;
+        match self.end {
+            Some(end) => end.to_css(dest),
+            None => dest.write_str("infinite"),
+        }
+    }
+}
+
+/// The `pad` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pad {
+    /// The minimum number of symbols in a representation.
+    pub min_length: i32,
+    /// The symbol repeated before shorter representations.
+    pub symbol: Symbol,
+}
+
+impl ToCss for Pad {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.min_length.to_css(dest)?;
+        dest.write_char(' ')?;
+        self.symbol.to_css(dest)
+    }
+}
+
+/// The `speak-as` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpeakAs {
+    /// `auto`
+    Auto,
+    /// `bullets`
+    Bullets,
+    /// `numbers`
+    Numbers,
+    /// `words`
+    Words,
+    /// `spell-out`
+    SpellOut,
+    /// Speak as another counter style would.
+    Other(CounterStyleName),
+}
+
+impl SpeakAs {
+    /// Parse the `speak-as` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let keyword = input.try_parse(|input| -> Result<_, BasicParseError<'i>> {
+            let location = input.current_source_location();
+            let ident = input.expect_ident()?;
+            Ok(match_ignore_ascii_case! { ident,
+                "auto" => SpeakAs::Auto,
+                "bullets" => SpeakAs::Bullets,
+                "numbers" => SpeakAs::Numbers,
+                "words" => SpeakAs::Words,
+                "spell-out" => SpeakAs::SpellOut,
+                _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone()))),
+            })
+        });
+        match keyword {
+            Ok(keyword) => Ok(keyword),
+            Err(_) => Ok(SpeakAs::Other(CounterStyleName::parse(input)?)),
+        }
+    }
+}
+
+impl ToCss for SpeakAs {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            SpeakAs::Auto => dest.write_str("auto"),
+            SpeakAs::Bullets => dest.write_str("bullets"),
+            SpeakAs::Numbers => dest.write_str("numbers"),
+            SpeakAs::Words => dest.write_str("words"),
+            SpeakAs::SpellOut => dest.write_str("spell-out"),
+            SpeakAs::Other(ref name) => name.to_css(dest),
+        }
+    }
+}
+
+/// The descriptors of a `@counter-style` rule.
+///
+/// Descriptors that are not specified, or whose value is invalid, are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CounterStyle {
+    /// `system`
+    pub system: Option<System>,
+    /// `symbols`
+    pub symbols: Option<Vec<Symbol>>,
+    /// `additive-symbols`
+    pub additive_symbols: Option<Vec<AdditiveSymbol>>,
+    /// `negative`
+    pub negative: Option<Negative>,
+    /// `prefix`
+    pub prefix: Option<Symbol>,
+    /// `suffix`
+    pub suffix: Option<Symbol>,
+    /// `range`. `auto` is an empty list.
+    pub range: Option<Vec<CounterRange>>,
+    /// `pad`
+    pub pad: Option<Pad>,
+    /// `fallback`
+    pub fallback: Option<CounterStyleName>,
+    /// `speak-as`
+    pub speak_as: Option<SpeakAs>,
+}
+
+impl CounterStyle {
+    /// Parse the content of a `@counter-style` block.
+    ///
+    /// Invalid and unknown descriptors are ignored,
+    /// and a descriptor specified more than once takes its last valid value.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Self {
+        let mut counter_style = CounterStyle::default();
+        for descriptor in DeclarationListParser::new(input, CounterStyleDescriptorParser).flatten()
+        {
+            counter_style.set(descriptor);
+        }
+        counter_style
+    }
+
+    /// Set the value of one descriptor.
+    pub fn set(&mut self, descriptor: CounterStyleDescriptor) {
+        match descriptor {
+            CounterStyleDescriptor::System(value) => self.system = Some(value),
+            CounterStyleDescriptor::Symbols(value) => self.symbols = Some(value),
+            CounterStyleDescriptor::AdditiveSymbols(value) => self.additive_symbols = Some(value),
+            CounterStyleDescriptor::Negative(value) => self.negative = Some(value),
+            CounterStyleDescriptor::Prefix(value) => self.prefix = Some(value),
+            CounterStyleDescriptor::Suffix(value) => self.suffix = Some(value),
+            CounterStyleDescriptor::Range(value) => self.range = Some(value),
+            CounterStyleDescriptor::Pad(value) => self.pad = Some(value),
+            CounterStyleDescriptor::Fallback(value) => self.fallback = Some(value),
+            CounterStyleDescriptor::SpeakAs(value) => self.speak_as = Some(value),
+        }
+    }
+
+    /// Whether the symbols match the requirements of the system.
+    /// A `@counter-style` rule is invalid and must be ignored otherwise.
+    pub fn is_valid(&self) -> bool {
+        let symbols = self.symbols.as_ref().map_or(0, Vec::len);
+        let additive_symbols = self.additive_symbols.as_ref().map_or(0, Vec::len);
+        match self.system {
+            None | Some(System::Symbolic) | Some(System::Cyclic) | Some(System::Fixed(_)) => {
+                symbols >= 1
+            }
+            Some(System::Alphabetic) | Some(System::Numeric) => symbols >= 2,
+            Some(System::Additive) => additive_symbols >= 1,
+            Some(System::Extends(_)) => self.symbols.is_none() && self.additive_symbols.is_none(),
+        }
+    }
+}
+
+impl ToCss for CounterStyle {
+    /// Serialize the descriptors that are set, as a declaration list.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let mut declarations = DeclarationWriter::new(dest);
+        if let Some(ref system) = self.system {
+            declarations.write("system", |dest| system.to_css(dest))?;
+        }
+        if let Some(ref symbols) = self.symbols {
+            declarations.write("symbols", |dest| write_separated(symbols, " ", dest))?;
+        }
+        if let Some(ref symbols) = self.additive_symbols {
+            declarations.write("additive-symbols", |dest| {
+                write_separated(symbols, ", ", dest)
+            })?;
+        }
+        if let Some(ref negative) = self.negative {
+            declarations.write("negative", |dest| negative.to_css(dest))?;
+        }
+        if let Some(ref prefix) = self.prefix {
+            declarations.write("prefix", |dest| prefix.to_css(dest))?;
+        }
+        if let Some(ref suffix) = self.suffix {
+            declarations.write("suffix", |dest| suffix.to_css(dest))?;
+        }
+        if let Some(ref range) = self.range {
+            declarations.write("range", |dest| {
+                if range.is_empty() {
+                    dest.write_str("auto")
+                } else {
+                    write_separated(range, ", ", dest)
+                }
+            })?;
+        }
+        if let Some(ref pad) = self.pad {
+            declarations.write("pad", |dest| pad.to_css(dest))?;
+        }
+        if let Some(ref fallback) = self.fallback {
+            declarations.write("fallback", |dest| fallback.to_css(dest))?;
+        }
+        if let Some(ref speak_as) = self.speak_as {
+            declarations.write("speak-as", |dest| speak_as.to_css(dest))?;
+        }
+        Ok(())
+    }
+}
+
+/// One `@counter-style` descriptor, as returned by `CounterStyleDescriptorParser`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CounterStyleDescriptor {
+    /// `system`
+    System(System),
+    /// `symbols`
+    Symbols(Vec<Symbol>),
+    /// `additive-symbols`
+    AdditiveSymbols(Vec<AdditiveSymbol>),
+    /// `negative`
+    Negative(Negative),
+    /// `prefix`
+    Prefix(Symbol),
+    /// `suffix`
+    Suffix(Symbol),
+    /// `range`
+    Range(Vec<CounterRange>),
+    /// `pad`
+    Pad(Pad),
+    /// `fallback`
+    Fallback(CounterStyleName),
+    /// `speak-as`
+    SpeakAs(SpeakAs),
+}
+
+/// A `DeclarationParser` for the descriptors of `@counter-style` rules,
+/// to be used with `DeclarationListParser`. `CounterStyle::parse` wraps both.
+///
+/// At-rules are rejected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CounterStyleDescriptorParser;
+
+impl<'i> DeclarationParser<'i> for CounterStyleDescriptorParser {
+    type Declaration = CounterStyleDescriptor;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<CounterStyleDescriptor, ParseError<'i, ()>> {
+        Ok(match_ignore_ascii_case! { &name,
+            "system" => CounterStyleDescriptor::System(System::parse(input)?),
+            "symbols" => {
+                let mut symbols = vec![Symbol::parse(input)?];
+                while let Ok(symbol) = input.try_parse(Symbol::parse) {
+                    symbols.push(symbol);
+                }
+                CounterStyleDescriptor::Symbols(symbols)
+            },
+            "additive-symbols" => {
+                let symbols: Vec<AdditiveSymbol> =
+                    input.parse_comma_separated(|input| Ok(AdditiveSymbol::parse(input)?))?;
+                // Weights must be in strictly decreasing order.
+                if symbols.windows(2).any(|pair| pair[0].weight <= pair[1].weight) {
+                    return Err(input.new_custom_error(()));
+                }
+                CounterStyleDescriptor::AdditiveSymbols(symbols)
+            },
+            "negative" => {
+                let prefix = Symbol::parse(input)?;
+                let suffix = input.try_parse(Symbol::parse).ok();
+                CounterStyleDescriptor::Negative(Negative { prefix, suffix })
+            },
+            "prefix" => CounterStyleDescriptor::Prefix(Symbol::parse(input)?),
+            "suffix" => CounterStyleDescriptor::Suffix(Symbol::parse(input)?),
+            "range" => {
+                let ranges = if input.try_parse(|input| input.expect_ident_matching("auto")).is_ok() {
+                    Vec::new()
+                } else {
+                    input.parse_comma_separated(|input| Ok(CounterRange::parse(input)?))?
+                };
+                CounterStyleDescriptor::Range(ranges)
+            },
+            "pad" => {
+                let symbol = input.try_parse(Symbol::parse).ok();
+                let min_length = parse_non_negative_integer(input)?;
+                let symbol = match symbol {
+                    Some(symbol) => symbol,
+                    None => Symbol::parse(input)?,
+                };
+                CounterStyleDescriptor::Pad(Pad { min_length, symbol })
+            },
+            "fallback" => CounterStyleDescriptor::Fallback(CounterStyleName::parse(input)?),
+            "speak-as" => CounterStyleDescriptor::SpeakAs(SpeakAs::parse(input)?),
+            _ => return Err(input.new_custom_error(())),
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for CounterStyleDescriptorParser {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = CounterStyleDescriptor;
+    type Error = ();
+}
+
+fn parse_non_negative_integer<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<i32, BasicParseError<'i>> {
+    let location = input.current_source_location();
+    let value = input.expect_integer()?;
+    if value < 0 {
+        return Err(location.new_basic_unexpected_token_error(Token::Number {
+            has_sign: true,
+            value: value as f32,
+            int_value: Some(value),
+        }));
+    }
+    Ok(value)
+}
+
+/// The simple predefined counter styles, as `@counter-style` rules.
+const PREDEFINED_COUNTER_STYLES: &str = r#"
+@counter-style decimal { system: numeric; symbols: '0' '1' '2' '3' '4' '5' '6' '7' '8' '9' }
+@counter-style decimal-leading-zero { system: extends decimal; pad: 2 '0' }
+@counter-style arabic-indic { system: numeric; symbols: '٠' '١' '٢' '٣' '٤' '٥' '٦' '٧' '٨' '٩' }
+@counter-style persian { system: numeric; symbols: '۰' '۱' '۲' '۳' '۴' '۵' '۶' '۷' '۸' '۹' }
+@counter-style devanagari { system: numeric; symbols: '०' '१' '२' '३' '४' '५' '६' '७' '८' '९' }
+@counter-style bengali { system: numeric; symbols: '০' '১' '২' '৩' '৪' '৫' '৬' '৭' '৮' '৯' }
+@counter-style thai { system: numeric; symbols: '๐' '๑' '๒' '๓' '๔' '๕' '๖' '๗' '๘' '๙' }
+@counter-style cjk-decimal {
+    system: numeric; range: 0 infinite; suffix: '、';
+    symbols: '〇' '一' '二' '三' '四' '五' '六' '七' '八' '九';
+}
+@counter-style lower-roman {
+    system: additive; range: 1 3999;
+    additive-symbols: 1000 m, 900 cm, 500 d, 400 cd, 100 c, 90 xc, 50 l, 40 xl, 10 x, 9 ix, 5 v,
+                      4 iv, 1 i;
+}
+@counter-style upper-roman {
+    system: additive; range: 1 3999;
+    additive-symbols: 1000 M, 900 CM, 500 D, 400 CD, 100 C, 90 XC, 50 L, 40 XL, 10 X, 9 IX, 5 V,
+                      4 IV, 1 I;
+}
+@counter-style lower-alpha {
+    system: alphabetic;
+    symbols: a b c d e f g h i j k l m n o p q r s t u v w x y z;
+}
+@counter-style lower-latin { system: extends lower-alpha }
+@counter-style upper-alpha {
+    system: alphabetic;
+    symbols: A B C D E F G H I J K L M N O P Q R S T U V W X Y Z;
+}
+@counter-style upper-latin { system: extends upper-alpha }
+@counter-style lower-greek {
+    system: alphabetic;
+    symbols: 'α' 'β' 'γ' 'δ' 'ε' 'ζ' 'η' 'θ' 'ι' 'κ' 'λ' 'μ' 'ν' 'ξ' 'ο' 'π' 'ρ' 'σ' 'τ' 'υ' 'φ'
+             'χ' 'ψ' 'ω';
+}
+@counter-style hiragana {
+    system: alphabetic; suffix: '、';
+    symbols: 'あ' 'い' 'う' 'え' 'お' 'か' 'き' 'く' 'け' 'こ' 'さ' 'し' 'す' 'せ' 'そ' 'た' 'ち'
+             'つ' 'て' 'と' 'な' 'に' 'ぬ' 'ね' 'の' 'は' 'ひ' 'ふ' 'へ' 'ほ' 'ま' 'み' 'む' 'め'
+             'も' 'や' 'ゆ' 'よ' 'ら' 'り' 'る' 'れ' 'ろ' 'わ' 'ゐ' 'ゑ' 'を' 'ん';
+}
+@counter-style katakana {
+    system: alphabetic; suffix: '、';
+    symbols: 'ア' 'イ' 'ウ' 'エ' 'オ' 'カ' 'キ' 'ク' 'ケ' 'コ' 'サ' 'シ' 'ス' 'セ' 'ソ' 'タ' 'チ'
+             'ツ' 'テ' 'ト' 'ナ' 'ニ' 'ヌ' 'ネ' 'ノ' 'ハ' 'ヒ' 'フ' 'ヘ' 'ホ' 'マ' 'ミ' 'ム' 'メ'
+             'モ' 'ヤ' 'ユ' 'ヨ' 'ラ' 'リ' 'ル' 'レ' 'ロ' 'ワ' 'ヰ' 'ヱ' 'ヲ' 'ン';
+}
+@counter-style cambodian { system: numeric; symbols: '០' '១' '២' '៣' '៤' '៥' '៦' '៧' '៨' '៩' }
+@counter-style gujarati { system: numeric; symbols: '૦' '૧' '૨' '૩' '૪' '૫' '૬' '૭' '૮' '૯' }
+@counter-style gurmukhi { system: numeric; symbols: '੦' '੧' '੨' '੩' '੪' '੫' '੬' '੭' '੮' '੯' }
+@counter-style kannada { system: numeric; symbols: '೦' '೧' '೨' '೩' '೪' '೫' '೬' '೭' '೮' '೯' }
+@counter-style lao { system: numeric; symbols: '໐' '໑' '໒' '໓' '໔' '໕' '໖' '໗' '໘' '໙' }
+@counter-style malayalam { system: numeric; symbols: '൦' '൧' '൨' '൩' '൪' '൫' '൬' '൭' '൮' '൯' }
+@counter-style mongolian { system: numeric; symbols: '᠐' '᠑' '᠒' '᠓' '᠔' '᠕' '᠖' '᠗' '᠘' '᠙' }
+@counter-style myanmar { system: numeric; symbols: '၀' '၁' '၂' '၃' '၄' '၅' '၆' '၇' '၈' '၉' }
+@counter-style oriya { system: numeric; symbols: '୦' '୧' '୨' '୩' '୪' '୫' '୬' '୭' '୮' '୯' }
+@counter-style tamil { system: numeric; symbols: '௦' '௧' '௨' '௩' '௪' '௫' '௬' '௭' '௮' '௯' }
+@counter-style telugu { system: numeric; symbols: '౦' '౧' '౨' '౩' '౪' '౫' '౬' '౭' '౮' '౯' }
+@counter-style tibetan { system: numeric; symbols: '༠' '༡' '༢' '༣' '༤' '༥' '༦' '༧' '༨' '༩' }
+@counter-style khmer { system: extends cambodian }
+@counter-style armenian { system: extends upper-armenian }
+@counter-style upper-armenian {
+    system: additive; range: 1 9999;
+    additive-symbols: 9000 'Ք', 8000 'Փ', 7000 'Ւ', 6000 'Ց', 5000 'Ր', 4000 'Տ', 3000 'Վ',
+                      2000 'Ս', 1000 'Ռ', 900 'Ջ', 800 'Պ', 700 'Չ', 600 'Ո', 500 'Շ', 400 'Ն',
+                      300 'Յ', 200 'Մ', 100 'Ճ', 90 'Ղ', 80 'Ձ', 70 'Հ', 60 'Կ', 50 'Ծ', 40 'Խ',
+                      30 'Լ', 20 'Ի', 10 'Ժ', 9 'Թ', 8 'Ը', 7 'Է', 6 'Զ', 5 'Ե', 4 'Դ', 3 'Գ',
+                      2 'Բ', 1 'Ա';
+}
+@counter-style lower-armenian {
+    system: additive; range: 1 9999;
+    additive-symbols: 9000 'ք', 8000 'փ', 7000 'ւ', 6000 'ց', 5000 'ր', 4000 'տ', 3000 'վ',
+                      2000 'ս', 1000 'ռ', 900 'ջ', 800 'պ', 700 'չ', 600 'ո', 500 'շ', 400 'ն',
+                      300 'յ', 200 'մ', 100 'ճ', 90 'ղ', 80 'ձ', 70 'հ', 60 'կ', 50 'ծ', 40 'խ',
+                      30 'լ', 20 'ի', 10 'ժ', 9 'թ', 8 'ը', 7 'է', 6 'զ', 5 'ե', 4 'դ', 3 'գ',
+                      2 'բ', 1 'ա';
+}
+@counter-style georgian {
+    system: additive; range: 1 19999;
+    additive-symbols: 10000 'ჵ', 9000 'ჰ', 8000 'ჯ', 7000 'ჴ', 6000 'ხ', 5000 'ჭ', 4000 'წ',
+                      3000 'ძ', 2000 'ც', 1000 'ჩ', 900 'შ', 800 'ყ', 700 'ღ', 600 'ქ', 500 'ფ',
+                      400 'ჳ', 300 'ტ', 200 'ს', 100 'რ', 90 'ჟ', 80 'პ', 70 'ო', 60 'ჲ', 50 'ნ',
+                      40 'მ', 30 'ლ', 20 'კ', 10 'ი', 9 'თ', 8 'ჱ', 7 'ზ', 6 'ვ', 5 'ე', 4 'დ',
+                      3 'გ', 2 'ბ', 1 'ა';
+}
+@counter-style hebrew {
+    system: additive; range: 1 10999;
+    additive-symbols: 10000 'יוד', 9000 'ט', 8000 'ח', 7000 'ז', 6000 'ו', 5000 'ה', 4000 'ד',
+                      3000 'ג', 2000 'ב', 1000 'א', 400 'ת', 300 'ש', 200 'ר', 100 'ק', 90 'צ',
+                      80 'פ', 70 'ע', 60 'ס', 50 'נ', 40 'מ', 30 'ל', 20 'כ', 19 'יט', 18 'יח',
+                      17 'יז', 16 'טז', 15 'טו', 10 'י', 9 'ט', 8 'ח', 7 'ז', 6 'ו', 5 'ה', 4 'ד',
+                      3 'ג', 2 'ב', 1 'א';
+}
+@counter-style hiragana-iroha {
+    system: alphabetic; suffix: '、';
+    symbols: 'い' 'ろ' 'は' 'に' 'ほ' 'へ' 'と' 'ち' 'り' 'ぬ' 'る' 'を' 'わ' 'か' 'よ' 'た' 'れ' 'そ' 'つ' 'ね' 'な'
+             'ら' 'む' 'う' 'ゐ' 'の' 'お' 'く' 'や' 'ま' 'け' 'ふ' 'こ' 'え' 'て' 'あ' 'さ' 'き' 'ゆ' 'め' 'み' 'し'
+             'ゑ' 'ひ' 'も' 'せ' 'す';
+}
+@counter-style katakana-iroha {
+    system: alphabetic; suffix: '、';
+    symbols: 'イ' 'ロ' 'ハ' 'ニ' 'ホ' 'ヘ' 'ト' 'チ' 'リ' 'ヌ' 'ル' 'ヲ' 'ワ' 'カ' 'ヨ' 'タ' 'レ' 'ソ' 'ツ' 'ネ' 'ナ'
+             'ラ' 'ム' 'ウ' 'ヰ' 'ノ' 'オ' 'ク' 'ヤ' 'マ' 'ケ' 'フ' 'コ' 'エ' 'テ' 'ア' 'サ' 'キ' 'ユ' 'メ' 'ミ' 'シ'
+             'ヱ' 'ヒ' 'モ' 'セ' 'ス';
+}
+@counter-style cjk-earthly-branch {
+    system: fixed; suffix: '、';
+    symbols: '子' '丑' '寅' '卯' '辰' '巳' '午' '未' '申' '酉' '戌' '亥';
+}
+@counter-style cjk-heavenly-stem {
+    system: fixed; suffix: '、';
+    symbols: '甲' '乙' '丙' '丁' '戊' '己' '庚' '辛' '壬' '癸';
+}
+@counter-style disc { system: cyclic; symbols: '•'; suffix: ' ' }
+@counter-style circle { system: cyclic; symbols: '◦'; suffix: ' ' }
+@counter-style square { system: cyclic; symbols: '▪'; suffix: ' ' }
+@counter-style disclosure-open { system: cyclic; symbols: '▾'; suffix: ' ' }
+@counter-style disclosure-closed { system: cyclic; symbols: '▸'; suffix: ' ' }
+"#;
+
+fn parse_predefined_rule<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<(CounterStyleName, CounterStyle), ParseError<'i, ()>> {
+    // The `@counter-style` keyword.
+    input.next()?;
+    let name = CounterStyleName::parse(input)?;
+    input.expect_curly_bracket_block()?;
+    let style = input.parse_nested_block(|input| Ok(CounterStyle::parse(input)))?;
+    Ok((name, style))
+}
+
+/// The maximum number of times a symbol is repeated by the `symbolic` and `additive`
+/// algorithms and by `pad`. Values that need more fall back,
+/// to avoid absurdly long representations.
+const MAX_REPETITIONS: i64 = 60;
+
+/// A set of counter styles by name, which renders counter values.
+///
+/// `new` includes the simple predefined counter styles of CSS Counter Styles,
+/// from `decimal` to `disclosure-closed`, but not the complex Chinese, Japanese,
+/// Korean and Ethiopic styles.
+#[derive(Clone, Debug)]
+pub struct CounterStyleRegistry {
+    styles: HashMap<String, CounterStyle>,
+}
+
+impl Default for CounterStyleRegistry {
+    fn default() -> Self {
+        CounterStyleRegistry::new()
+    }
+}
+
+/// A counter style with `extends` and defaults resolved.
+struct ResolvedCounterStyle<'a> {
+    system: &'a System,
+    symbols: &'a [Symbol],
+    additive_symbols: &'a [AdditiveSymbol],
+    negative: Option<&'a Negative>,
+    prefix: Option<&'a Symbol>,
+    suffix: Option<&'a Symbol>,
+    range: Option<&'a [CounterRange]>,
+    pad: Option<&'a Pad>,
+    fallback: Option<&'a CounterStyleName>,
+}
+
+impl CounterStyleRegistry {
+    /// Create a registry with the predefined counter styles.
+    pub fn new() -> Self {
+        let mut registry = CounterStyleRegistry {
+            styles: HashMap::new(),
+        };
+        let mut input = ParserInput::new(PREDEFINED_COUNTER_STYLES);
+        let mut input = Parser::new(&mut input);
+        while !input.is_exhausted() {
+            let (name, style) =
+                parse_predefined_rule(&mut input).expect("invalid predefined counter style");
+            registry.styles.insert(name.0, style);
+        }
+        registry
+    }
+
+    /// Add the counter style of a `@counter-style` rule,
+    /// replacing any existing style with the same name.
+    ///
+    /// Return false, and do nothing, if the rule is invalid:
+    /// its name can not be defined or `CounterStyle::is_valid` is false.
+    pub fn register(&mut self, name: CounterStyleName, style: CounterStyle) -> bool {
+        if !name.can_be_defined() || !style.is_valid() {
+            return false;
+        }
+        self.styles.insert(name.0, style);
+        true
+    }
+
+    /// The counter style with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&CounterStyle> {
+        self.styles.get(name)
+    }
+
+    /// The counter representation of a value: its text without the prefix and suffix,
+    /// with the negative sign and padding.
+    ///
+    /// Unknown counter styles are treated as `decimal`.
+    /// Values out of the range of the style, or that it can not represent,
+    /// use its fallback style.
+    pub fn representation(&self, name: &str, value: i32) -> String {
+        self.generate(name, value, false)
+    }
+
+    /// The text of a list marker: the counter representation with the prefix and suffix.
+    pub fn marker(&self, name: &str, value: i32) -> String {
+        self.generate(name, value, true)
+    }
+
+    fn generate(&self, name: &str, value: i32, with_affixes: bool) -> String {
+        let mut name = name;
+        let mut visited = Vec::new();
+        loop {
+            // Per spec, a style whose fallback chain loops falls back to decimal.
+            if visited.contains(&name) {
+                name = "decimal";
+            }
+            visited.push(name);
+            let style = match self.resolve(name, &mut Vec::new()) {
+                Some(style) => style,
+                None => {
+                    name = "decimal";
+                    continue;
+                }
+            };
+            if let Some(representation) = style.representation(value) {
+                if !with_affixes {
+                    return representation;
+                }
+                let prefix = style.prefix.map_or("", Symbol::as_str);
+                let suffix = style.suffix.map_or(". ", Symbol::as_str);
+                return format!("{}{}{}", prefix, representation, suffix);
+            }
+            name = style.fallback.map_or("decimal", |name| &name.0);
+        }
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+        extending: &mut Vec<&'a str>,
+    ) -> Option<ResolvedCounterStyle<'a>> {
+        let style = self.styles.get(name)?;
+        let mut resolved = match style.system {
+            Some(System::Extends(ref base)) => {
+                extending.push(name);
+                // Extending an unknown style, or a loop of styles, extends decimal.
+                let base = if extending.contains(&&*base.0) || !self.styles.contains_key(&base.0) {
+                    "decimal"
+                } else {
+                    &base.0
+                };
+                self.resolve(base, extending)?
+            }
+            ref system => ResolvedCounterStyle {
+                system: system.as_ref().unwrap_or(&System::Symbolic),
+                symbols: style.symbols.as_ref().map_or(&[], |symbols| &symbols[..]),
+                additive_symbols: style
+                    .additive_symbols
+                    .as_ref()
+                    .map_or(&[], |symbols| &symbols[..]),
+                negative: None,
+                prefix: None,
+                suffix: None,
+                range: None,
+                pad: None,
+                fallback: None,
+            },
+        };
+        resolved.negative = style.negative.as_ref().or(resolved.negative);
+        resolved.prefix = style.prefix.as_ref().or(resolved.prefix);
+        resolved.suffix = style.suffix.as_ref().or(resolved.suffix);
+        resolved.range = style.range.as_ref().map(|r| &r[..]).or(resolved.range);
+        resolved.pad = style.pad.as_ref().or(resolved.pad);
+        resolved.fallback = style.fallback.as_ref().or(resolved.fallback);
+        Some(resolved)
+    }
+}
+
+impl<'a> ResolvedCounterStyle<'a> {
+    fn representation(&self, value: i32) -> Option<String> {
+        let in_range = match self.range {
+            Some(ranges) if !ranges.is_empty() => ranges.iter().any(|range| range.contains(value)),
+            _ => self.system.auto_range().contains(value),
+        };
+        if !in_range {
+            return None;
+        }
+        let negative = value < 0 && self.system.uses_negative();
+        let mut representation = self.algorithm(if negative {
+            -(value as i64)
+        } else {
+            value as i64
+        })?;
+        let (negative_prefix, negative_suffix) = match (negative, self.negative) {
+            (false, _) => ("", ""),
+            (true, Some(symbols)) => (
+                symbols.prefix.as_str(),
+                symbols.suffix.as_ref().map_or("", Symbol::as_str),
+            ),
+            (true, None) => ("-", ""),
+        };
+        if let Some(pad) = self.pad {
+            let length = representation.chars().count()
+                + negative_prefix.chars().count()
+                + negative_suffix.chars().count();
+            let missing = (pad.min_length as i64).saturating_sub(length as i64);
+            if missing > MAX_REPETITIONS {
+                return None;
+            }
+            if missing > 0 {
+                representation = pad.symbol.as_str().repeat(missing as usize) + &representation;
+            }
+        }
+        Some(format!(
+            "{}{}{}",
+            negative_prefix, representation, negative_suffix
+        ))
+    }
+
+    /// Run the algorithm of the system on a non-negative value,
+    /// except for `cyclic` and `fixed` which accept any value.
+    fn algorithm(&self, value: i64) -> Option<String> {
+        let symbols = self.symbols;
+        let count = symbols.len() as i64;
+        let symbol = |index: i64| symbols[index as usize].as_str();
+        match *self.system {
+            System::Cyclic if count >= 1 => Some(symbol((value - 1).rem_euclid(count)).to_owned()),
+            System::Fixed(first) => {
+                let index = value - first as i64;
+                if 0 <= index && index < count {
+                    Some(symbol(index).to_owned())
+                } else {
+                    None
+                }
+            }
+            System::Symbolic if value >= 1 && count >= 1 => {
+                let repetitions = (value - 1) / count + 1;
+                if repetitions > MAX_REPETITIONS {
+                    return None;
+                }
+                Some(symbol((value - 1) % count).repeat(repetitions as usize))
+            }
+            System::Alphabetic if value >= 1 && count >= 2 => {
+                let mut digits = Vec::new();
+                let mut value = value;
+                while value != 0 {
+                    value -= 1;
+                    digits.push(symbol(value % count));
+                    value /= count;
+                }
+                Some(digits.into_iter().rev().collect())
+            }
+            System::Numeric if count >= 2 => {
+                let mut digits = vec![symbol(value % count)];
+                let mut value = value / count;
+                while value != 0 {
+                    digits.push(symbol(value % count));
+                    value /= count;
+                }
+                Some(digits.into_iter().rev().collect())
+            }
+            System::Additive => {
+                if value == 0 {
+                    return self
+                        .additive_symbols
+                        .iter()
+                        .find(|tuple| tuple.weight == 0)
+                        .map(|tuple| tuple.symbol.as_str().to_owned());
+                }
+                let mut value = value;
+                let mut representation = String::new();
+                for tuple in self.additive_symbols {
+                    if tuple.weight == 0 {
+                        continue;
+                    }
+                    let repetitions = value / tuple.weight as i64;
+                    if repetitions > MAX_REPETITIONS {
+                        return None;
+                    }
+                    representation.push_str(&tuple.symbol.as_str().repeat(repetitions as usize));
+                    value -= repetitions * tuple.weight as i64;
+                    if value == 0 {
+                        return Some(representation);
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A simple in-memory document tree implementing `Element`,
+//! as a reference for implementations and for testing selectors.
+
+use crate::matching::{Element, ElementState, MatchingContext};
+use crate::{Direction, SelectorList};
+
+/// A node of a `Document`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// An attribute of an element of a `Document`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    /// The namespace URL, if any.
+    pub namespace: Option<String>,
+    /// The local name.
+    pub local_name: String,
+    /// The value.
+    pub value: String,
+}
+
+#[derive(Clone, Debug)]
+enum NodeData {
+    Element {
+        local_name: String,
+        namespace: Option<String>,
+        attributes: Vec<Attribute>,
+        state: ElementState,
+    },
+    Text(String),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    data: NodeData,
+}
+
+/// A tree of elements and text nodes, in tree order.
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    nodes: Vec<Node>,
+}
+
+impl Document {
+    /// Create an empty document.
+    pub fn new() -> Self {
+        Document::default()
+    }
+
+    /// Append an element without a namespace to `parent`,
+    /// or create the root element if `parent` is `None`.
+    pub fn append_element(&mut self, parent: Option<NodeId>, local_name: &str) -> NodeId {
+        self.append_element_ns(parent, None, local_name)
+    }
+
+    /// Append an element in a namespace to `parent`,
+    /// or create the root element if `parent` is `None`.
+    pub fn append_element_ns(
+        &mut self,
+        parent: Option<NodeId>,
+        namespace: Option<&str>,
+        local_name: &str,
+    ) -> NodeId {
+        self.append(
+            parent,
+            NodeData::Element {
+                local_name: local_name.to_owned(),
+                namespace: namespace.map(str::to_owned),
+                attributes: Vec::new(),
+                state: ElementState::empty(),
+            },
+        )
+    }
+
+    /// Append a text node to `parent`.
+    pub fn append_text(&mut self, parent: NodeId, text: &str) -> NodeId {
+        self.append(Some(parent), NodeData::Text(text.to_owned()))
+    }
+
+    fn append(&mut self, parent: Option<NodeId>, data: NodeData) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            parent,
+            children: Vec::new(),
+            data,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        id
+    }
+
+    /// Set an attribute without a namespace, such as `id` or `class`.
+    pub fn set_attribute(&mut self, element: NodeId, local_name: &str, value: &str) {
+        self.set_attribute_ns(element, None, local_name, value)
+    }
+
+    /// Set an attribute in a namespace.
+    pub fn set_attribute_ns(
+        &mut self,
+        element: NodeId,
+        namespace: Option<&str>,
+        local_name: &str,
+        value: &str,
+    ) {
+        if let NodeData::Element {
+            ref mut attributes, ..
+        } = self.nodes[element.0].data
+        {
+            let namespace = namespace.map(str::to_owned);
+            match attributes
+                .iter_mut()
+                .find(|a| a.namespace == namespace && a.local_name == local_name)
+            {
+                Some(attribute) => attribute.value = value.to_owned(),
+                None => attributes.push(Attribute {
+                    namespace,
+                    local_name: local_name.to_owned(),
+                    value: value.to_owned(),
+                }),
+            }
+        }
+    }
+
+    /// Set the state of an element, for pseudo-classes such as `:hover`.
+    pub fn set_state(&mut self, element: NodeId, new_state: ElementState) {
+        if let NodeData::Element { ref mut state, .. } = self.nodes[element.0].data {
+            *state = new_state
+        }
+    }
+
+    /// The element with this ID, or `None` if it is a text node.
+    pub fn element(&self, id: NodeId) -> Option<DomElement<'_>> {
+        match self.nodes.get(id.0)?.data {
+            NodeData::Element { .. } => Some(DomElement { document: self, id }),
+            NodeData::Text(_) => None,
+        }
+    }
+
+    /// The elements that match the selector list, in tree order.
+    pub fn select(&self, selectors: &SelectorList) -> Vec<NodeId> {
+        let context = MatchingContext::new();
+        (0..self.nodes.len())
+            .filter_map(|i| self.element(NodeId(i)))
+            .filter(|element| selectors.matches(element, &context))
+            .map(|element| element.id)
+            .collect()
+    }
+}
+
+/// An element of a `Document`.
+#[derive(Clone, Copy, Debug)]
+pub struct DomElement<'a> {
+    document: &'a Document,
+    id: NodeId,
+}
+
+impl<'a> PartialEq for DomElement<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.document, other.document) && self.id == other.id
+    }
+}
+
+impl<'a> DomElement<'a> {
+    /// The node ID of the element in its document.
+    pub fn node_id(&self) -> NodeId {
+        self.id
+    }
+
+    fn node(&self) -> &'a Node {
+        &self.document.nodes[self.id.0]
+    }
+
+    fn attributes(&self) -> &'a [Attribute] {
+        match self.node().data {
+            NodeData::Element { ref attributes, .. } => attributes,
+            NodeData::Text(_) => &[],
+        }
+    }
+
+    fn attribute(&self, local_name: &str) -> Option<&'a str> {
+        self.attributes()
+            .iter()
+            .find(|a| a.namespace.is_none() && a.local_name == local_name)
+            .map(|a| &*a.value)
+    }
+
+    /// The closest following or preceding sibling that is an element.
+    fn sibling_element(&self, forward: bool) -> Option<Self> {
+        let siblings = &self.document.nodes[self.node().parent?.0].children;
+        let position = siblings.iter().position(|&id| id == self.id)?;
+        let mut candidates: Box<dyn Iterator<Item = &NodeId>> = if forward {
+            Box::new(siblings[position + 1..].iter())
+        } else {
+            Box::new(siblings[..position].iter().rev())
+        };
+        candidates.find_map(|&id| self.document.element(id))
+    }
+}
+
+impl<'a> Element for DomElement<'a> {
+    fn parent_element(&self) -> Option<Self> {
+        self.document.element(self.node().parent?)
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.sibling_element(false)
+    }
+
+    fn next_sibling_element(&self) -> Option<Self> {
+        self.sibling_element(true)
+    }
+
+    fn first_child_element(&self) -> Option<Self> {
+        self.node()
+            .children
+            .iter()
+            .find_map(|&id| self.document.element(id))
+    }
+
+    fn local_name(&self) -> &str {
+        match self.node().data {
+            NodeData::Element { ref local_name, .. } => local_name,
+            NodeData::Text(_) => "",
+        }
+    }
+
+    fn namespace(&self) -> Option<&str> {
+        match self.node().data {
+            NodeData::Element { ref namespace, .. } => namespace.as_deref(),
+            NodeData::Text(_) => None,
+        }
+    }
+
+    fn id(&self) -> Option<&str> {
+        self.attribute("id")
+    }
+
+    fn has_class(&self, name: &str) -> bool {
+        self.attribute("class")
+            .iter()
+            .flat_map(|classes| classes.split(|c: char| c.is_ascii_whitespace()))
+            .any(|class| class == name)
+    }
+
+    fn any_attribute(
+        &self,
+        local_name: &str,
+        f: &mut dyn FnMut(Option<&str>, &str) -> bool,
+    ) -> bool {
+        self.attributes()
+            .iter()
+            .filter(|a| a.local_name == local_name)
+            .any(|a| f(a.namespace.as_deref(), &a.value))
+    }
+
+    fn state(&self) -> ElementState {
+        match self.node().data {
+            NodeData::Element { state, .. } => state,
+            NodeData::Text(_) => ElementState::empty(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.node()
+            .children
+            .iter()
+            .all(|&id| match self.document.nodes[id.0].data {
+                NodeData::Element { .. } => false,
+                NodeData::Text(ref text) => text.is_empty(),
+            })
+    }
+
+    fn lang(&self) -> Option<&str> {
+        self.attribute("lang")
+    }
+
+    fn direction(&self) -> Direction {
+        let mut element = Some(*self);
+        while let Some(e) = element {
+            match e.attribute("dir") {
+                Some(dir) if dir.eq_ignore_ascii_case("rtl") => return Direction::Rtl,
+                Some(dir) if dir.eq_ignore_ascii_case("ltr") => return Direction::Ltr,
+                _ => element = e.parent_element(),
+            }
+        }
+        Direction::Ltr
+    }
+}
@@ -0,0 +1,739 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-fonts-4/#font-face-rule
+
+use std::fmt;
+
+use super::{
+    AtRuleParser, BasicParseError, CowRcStr, DeclarationListParser, DeclarationParser, ParseError,
+    Parser, ToCss, Token, UnicodeRange,
+};
+use crate::parser::is_css_wide_keyword;
+use crate::serializer::{
+    serialize_identifier, serialize_string, write_separated, DeclarationWriter,
+};
+
+/// The descriptors of a `@font-face` rule.
+///
+/// Descriptors that are not specified, or whose value is invalid, are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FontFace {
+    /// `font-family`
+    pub family: Option<String>,
+    /// `src`
+    pub sources: Option<Vec<FontFaceSource>>,
+    /// `font-weight`
+    pub weight: Option<FontWeightRange>,
+    /// `font-stretch`
+    pub stretch: Option<FontStretchRange>,
+    /// `font-style`
+    pub style: Option<FontStyleRange>,
+    /// `unicode-range`
+    pub unicode_range: Option<Vec<UnicodeRange>>,
+    /// `font-display`
+    pub display: Option<FontDisplay>,
+    /// `font-feature-settings`. `normal` is an empty list.
+    pub feature_settings: Option<Vec<FontFeatureSetting>>,
+    /// `ascent-override`
+    pub ascent_override: Option<MetricOverride>,
+    /// `descent-override`
+    pub descent_override: Option<MetricOverride>,
+    /// `line-gap-override`
+    pub line_gap_override: Option<MetricOverride>,
+    /// `size-adjust`, as a fraction: 1.0 is 100%.
+    pub size_adjust: Option<f32>,
+}
+
+impl FontFace {
+    /// Parse the content of a `@font-face` block.
+    ///
+    /// Invalid and unknown descriptors are ignored,
+    /// and a descriptor specified more than once takes its last valid value.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Self {
+        let mut font_face = FontFace::default();
+        for descriptor in DeclarationListParser::new(input, FontFaceDescriptorParser).flatten() {
+            font_face.set(descriptor);
+        }
+        font_face
+    }
+
+    /// Set the value of one descriptor.
+    pub fn set(&mut self, descriptor: FontFaceDescriptor) {
+        match descriptor {
+            FontFaceDescriptor::Family(value) => self.family = Some(value),
+            FontFaceDescriptor::Sources(value) => self.sources = Some(value),
+            FontFaceDescriptor::Weight(value) => self.weight = Some(value),
+            FontFaceDescriptor::Stretch(value) => self.stretch = Some(value),
+            FontFaceDescriptor::Style(value) => self.style = Some(value),
+            FontFaceDescriptor::UnicodeRange(value) => self.unicode_range = Some(value),
+            FontFaceDescriptor::Display(value) => self.display = Some(value),
+            FontFaceDescriptor::FeatureSettings(value) => self.feature_settings = Some(value),
+            FontFaceDescriptor::AscentOverride(value) => self.ascent_override = Some(value),
+            FontFaceDescriptor::DescentOverride(value) => self.descent_override = Some(value),
+            FontFaceDescriptor::LineGapOverride(value) => self.line_gap_override = Some(value),
+            FontFaceDescriptor::SizeAdjust(value) => self.size_adjust = Some(value),
+        }
+    }
+}
+
+impl ToCss for FontFace {
+    /// Serialize the descriptors that are set, as a declaration list.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let mut declarations = DeclarationWriter::new(dest);
+        if let Some(ref family) = self.family {
+            declarations.write("font-family", |dest| serialize_string(family, dest))?;
+        }
+        if let Some(ref sources) = self.sources {
+            declarations.write("src", |dest| write_separated(sources, ", ", dest))?;
+        }
+        if let Some(ref weight) = self.weight {
+            declarations.write("font-weight", |dest| weight.to_css(dest))?;
+        }
+        if let Some(ref stretch) = self.stretch {
+            declarations.write("font-stretch", |dest| stretch.to_css(dest))?;
+        }
+        if let Some(ref style) = self.style {
+            declarations.write("font-style", |dest| style.to_css(dest))?;
+        }
+        if let Some(ref ranges) = self.unicode_range {
+            declarations.write("unicode-range", |dest| write_separated(ranges, ", ", dest))?;
+        }
+        if let Some(ref display) = self.display {
+            declarations.write("font-display", |dest| display.to_css(dest))?;
+        }
+        if let Some(ref settings) = self.feature_settings {
+            declarations.write("font-feature-settings", |dest| {
+                if settings.is_empty() {
+                    dest.write_str("normal")
+                } else {
+                    write_separated(settings, ", ", dest)
+                }
+            })?;
+        }
+        for &(name, value) in &[
+            ("ascent-override", self.ascent_override),
+            ("descent-override", self.descent_override),
+            ("line-gap-override", self.line_gap_override),
+        ] {
+            if let Some(value) = value {
+                declarations.write(name, |dest| value.to_css(dest))?;
+            }
+        }
+        if let Some(size_adjust) = self.size_adjust {
+            declarations.write("size-adjust", |dest| write_percentage(size_adjust, dest))?;
+        }
+        Ok(())
+    }
+}
+
+/// One `@font-face` descriptor, as returned by `FontFaceDescriptorParser`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontFaceDescriptor {
+    /// `font-family`
+    Family(String),
+    /// `src`
+    Sources(Vec<FontFaceSource>),
+    /// `font-weight`
+    Weight(FontWeightRange),
+    /// `font-stretch`
+    Stretch(FontStretchRange),
+    /// `font-style`
+    Style(FontStyleRange),
+    /// `unicode-range`
+    UnicodeRange(Vec<UnicodeRange>),
+    /// `font-display`
+    Display(FontDisplay),
+    /// `font-feature-settings`
+    FeatureSettings(Vec<FontFeatureSetting>),
+    /// `ascent-override`
+    AscentOverride(MetricOverride),
+    /// `descent-override`
+    DescentOverride(MetricOverride),
+    /// `line-gap-override`
+    LineGapOverride(MetricOverride),
+    /// `size-adjust`
+    SizeAdjust(f32),
+}
+
+/// A `DeclarationParser` for the descriptors of `@font-face` rules,
+/// to be used with `DeclarationListParser`. `FontFace::parse` wraps both.
+///
+/// At-rules are rejected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FontFaceDescriptorParser;
+
+impl<'i> DeclarationParser<'i> for FontFaceDescriptorParser {
+    type Declaration = FontFaceDescriptor;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<FontFaceDescriptor, ParseError<'i, ()>> {
+        Ok(match_ignore_ascii_case! { &name,
+            "font-family" => FontFaceDescriptor::Family(parse_family_name(input)?),
+            "src" => FontFaceDescriptor::Sources(parse_sources(input)?),
+            "font-weight" => FontFaceDescriptor::Weight(FontWeightRange::parse(input)?),
+            "font-stretch" => FontFaceDescriptor::Stretch(FontStretchRange::parse(input)?),
+            "font-style" => FontFaceDescriptor::Style(FontStyleRange::parse(input)?),
+            "unicode-range" => FontFaceDescriptor::UnicodeRange(
+                input.parse_comma_separated(|input| Ok(UnicodeRange::parse(input)?))?
+            ),
+            "font-display" => FontFaceDescriptor::Display(FontDisplay::parse(input)?),
+            "font-feature-settings" => {
+                let settings = if input.try_parse(|input| input.expect_ident_matching("normal")).is_ok() {
+                    Vec::new()
+                } else {
+                    input.parse_comma_separated(|input| Ok(FontFeatureSetting::parse(input)?))?
+                };
+                FontFaceDescriptor::FeatureSettings(settings)
+            },
+            "ascent-override" => FontFaceDescriptor::AscentOverride(MetricOverride::parse(input)?),
+            "descent-override" => FontFaceDescriptor::DescentOverride(MetricOverride::parse(input)?),
+            "line-gap-override" => FontFaceDescriptor::LineGapOverride(MetricOverride::parse(input)?),
+            "size-adjust" => FontFaceDescriptor::SizeAdjust(parse_non_negative_percentage(input)?),
+            _ => return Err(input.new_custom_error(())),
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for FontFaceDescriptorParser {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = FontFaceDescriptor;
+    type Error = ();
+}
+
+/// Parse a `<family-name>`: a string, or a sequence of identifiers joined with spaces.
+fn parse_family_name<'i, 't>(input: &mut Parser<'i, 't>) -> Result<String, BasicParseError<'i>> {
+    if let Ok(name) = input.try_parse(|input| input.expect_string_cloned()) {
+        return Ok(name.as_ref().to_owned());
+    }
+    let location = input.current_source_location();
+    let first = input.expect_ident_cloned()?;
+    if is_css_wide_keyword(&first) {
+        return Err(location.new_basic_unexpected_token_error(Token::Ident(first.clone())));
+    }
+    let mut name = first.as_ref().to_owned();
+    while let Ok(ident) = input.try_parse(|input| input.expect_ident_cloned()) {
+        name.push(' ');
+        name.push_str(&ident);
+    }
+    Ok(name)
+}
+
+/// Parse `src`. Per spec, components that fail to parse are dropped,
+/// and the descriptor is only invalid if none is left.
+fn parse_sources<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Vec<FontFaceSource>, ParseError<'i, ()>> {
+    let mut sources = Vec::new();
+    let result: Result<_, ParseError<()>> = input.parse_comma_separated(|input| {
+        let source = input.try_parse(|input| {
+            input.parse_entirely(|input| {
+                FontFaceSource::parse(input).map_err(ParseError::<()>::from)
+            })
+        });
+        if let Ok(source) = source {
+            sources.push(source);
+        } else {
+            while input.next().is_ok() {}
+        }
+        Ok(())
+    });
+    result?;
+    if sources.is_empty() {
+        return Err(input.new_custom_error(()));
+    }
+    Ok(sources)
+}
+
+fn write_percentage<W: fmt::Write>(unit_value: f32, dest: &mut W) -> fmt::Result {
+    (unit_value * 100.).to_css(dest)?;
+    dest.write_char('%')
+}
+
+fn parse_non_negative_percentage<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<f32, BasicParseError<'i>> {
+    let location = input.current_source_location();
+    let value = input.expect_percentage()?;
+    if value < 0. {
+        return Err(
+            location.new_basic_unexpected_token_error(Token::Percentage {
+                has_sign: true,
+                unit_value: value,
+                int_value: None,
+            }),
+        );
+    }
+    Ok(value)
+}
+
+/// One component of the `src` descriptor.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontFaceSource {
+    /// `<url> [ format(<font-format>) ]? [ tech(<font-tech>#) ]?`
+    Url {
+        /// The URL, as written.
+        url: String,
+        /// The format, such as `woff2`.
+        format: Option<FontFormat>,
+        /// The technology keywords, ASCII-lowercased, such as `color-colrv1`.
+        tech: Vec<String>,
+    },
+    /// `local(<family-name>)`
+    Local(String),
+}
+
+impl FontFaceSource {
+    /// Parse one component of the `src` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if input
+            .try_parse(|input| input.expect_function_matching("local"))
+            .is_ok()
+        {
+            let name: Result<_, ParseError<()>> =
+                input.parse_nested_block(|input| Ok(parse_family_name(input)?));
+            return name.map(FontFaceSource::Local).map_err(ParseError::basic);
+        }
+
+        let url = input.expect_url()?.as_ref().to_owned();
+        let format = input
+            .try_parse(|input| -> Result<_, ParseError<()>> {
+                input.expect_function_matching("format")?;
+                input.parse_nested_block(|input| Ok(FontFormat::parse(input)?))
+            })
+            .ok();
+        let tech = input
+            .try_parse(|input| -> Result<_, ParseError<()>> {
+                input.expect_function_matching("tech")?;
+                input.parse_nested_block(|input| {
+                    input.parse_comma_separated(|input| {
+                        Ok(input.expect_ident()?.to_ascii_lowercase())
+                    })
+                })
+            })
+            .unwrap_or_default();
+        Ok(FontFaceSource::Url { url, format, tech })
+    }
+}
+
+impl ToCss for FontFaceSource {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            FontFaceSource::Url {
+                ref url,
+                ref format,
+                ref tech,
+            } => {
+                dest.write_str("url(")?;
+                serialize_string(url, dest)?;
+                dest.write_char(')')?;
+                if let Some(ref format) = *format {
+                    dest.write_str(" format(")?;
+                    format.to_css(dest)?;
+                    dest.write_char(')')?;
+                }
+                if !tech.is_empty() {
+                    dest.write_str(" tech(")?;
+                    for (i, tech) in tech.iter().enumerate() {
+                        if i > 0 {
+                            dest.write_str(", ")?;
+                        }
+                        serialize_identifier(tech, dest)?;
+                    }
+                    dest.write_char(')')?;
+                }
+                Ok(())
+            }
+            FontFaceSource::Local(ref name) => {
+                dest.write_str("local(")?;
+                serialize_string(name, dest)?;
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+/// A `<font-format>`, in `format()` in `src` or in `font-format()` in `@supports`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontFormat {
+    /// The format keyword, ASCII-lowercased, or string, as written, such as `woff2`.
+    pub format: String,
+    /// Whether the format is a `<string>` rather than a keyword.
+    pub string: bool,
+}
+
+impl FontFormat {
+    /// Parse a `<font-format>` keyword or string.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::Ident(ref format) => Ok(FontFormat {
+                format: format.to_ascii_lowercase(),
+                string: false,
+            }),
+            Token::QuotedString(ref format) => Ok(FontFormat {
+                format: format.as_ref().to_owned(),
+                string: true,
+            }),
+            ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+        }
+    }
+}
+
+impl ToCss for FontFormat {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.string {
+            serialize_string(&self.format, dest)
+        } else {
+            serialize_identifier(&self.format, dest)
+        }
+    }
+}
+
+/// The `font-weight` descriptor: `auto | <font-weight-absolute>{1,2}`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontWeightRange {
+    /// `auto`
+    Auto,
+    /// A range of weights, with `normal` as 400 and `bold` as 700.
+    /// Both ends are equal for a single value.
+    Range(f32, f32),
+}
+
+impl FontWeightRange {
+    /// Parse the `font-weight` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if input
+            .try_parse(|input| input.expect_ident_matching("auto"))
+            .is_ok()
+        {
+            return Ok(FontWeightRange::Auto);
+        }
+        let start = Self::parse_absolute(input)?;
+        let end = input.try_parse(Self::parse_absolute).unwrap_or(start);
+        Ok(FontWeightRange::Range(start, end))
+    }
+
+    fn parse_absolute<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::Number { value, .. } if (1. ..=1000.).contains(&value) => Ok(value),
+            Token::Ident(ref ident) if ident.eq_ignore_ascii_case("normal") => Ok(400.),
+            Token::Ident(ref ident) if ident.eq_ignore_ascii_case("bold") => Ok(700.),
+            ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+        }
+    }
+}
+
+impl ToCss for FontWeightRange {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            FontWeightRange::Auto => dest.write_str("auto"),
+            FontWeightRange::Range(start, end) => {
+                start.to_css(dest)?;
+                if end != start {
+                    dest.write_char(' ')?;
+                    end.to_css(dest)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The `font-stretch` descriptor: `auto | <font-stretch-absolute>{1,2}`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontStretchRange {
+    /// `auto`
+    Auto,
+    /// A range of widths as fractions (1.0 is `normal` or 100%),
+    /// with keywords converted to their percentage.
+    /// Both ends are equal for a single value.
+    Range(f32, f32),
+}
+
+impl FontStretchRange {
+    /// Parse the `font-stretch` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if input
+            .try_parse(|input| input.expect_ident_matching("auto"))
+            .is_ok()
+        {
+            return Ok(FontStretchRange::Auto);
+        }
+        let start = Self::parse_absolute(input)?;
+        let end = input.try_parse(Self::parse_absolute).unwrap_or(start);
+        Ok(FontStretchRange::Range(start, end))
+    }
+
+    fn parse_absolute<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, BasicParseError<'i>> {
+        if let Ok(value) = input.try_parse(parse_non_negative_percentage) {
+            return Ok(value);
+        }
+        let location = input.current_source_location();
+        let ident = input.expect_ident()?;
+        Ok(match_ignore_ascii_case! { ident,
+            "ultra-condensed" => 0.5,
+            "extra-condensed" => 0.625,
+            "condensed" => 0.75,
+            "semi-condensed" => 0.875,
+            "normal" => 1.,
+            "semi-expanded" => 1.125,
+            "expanded" => 1.25,
+            "extra-expanded" => 1.5,
+            "ultra-expanded" => 2.,
+            _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone()))),
+        })
+    }
+}
+
+impl ToCss for FontStretchRange {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            FontStretchRange::Auto => dest.write_str("auto"),
+            FontStretchRange::Range(start, end) => {
+                write_percentage(start, dest)?;
+                if end != start {
+                    dest.write_char(' ')?;
+                    write_percentage(end, dest)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The `font-style` descriptor: `auto | normal | italic | oblique [ <angle [-90deg,90deg]>{1,2} ]?`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontStyleRange {
+    /// `auto`
+    Auto,
+    /// `normal`
+    Normal,
+    /// `italic`
+    Italic,
+    /// `oblique`, with a range of angles in degrees.
+    /// `oblique` alone is 14 degrees. Both ends are equal for a single value.
+    Oblique(f32, f32),
+}
+
+impl FontStyleRange {
+    /// Parse the `font-style` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident_cloned()?;
+        Ok(match_ignore_ascii_case! { &ident,
+            "auto" => FontStyleRange::Auto,
+            "normal" => FontStyleRange::Normal,
+            "italic" => FontStyleRange::Italic,
+            "oblique" => {
+                match input.try_parse(Self::parse_angle) {
+                    Ok(start) => {
+                        let end = input.try_parse(Self::parse_angle).unwrap_or(start);
+                        FontStyleRange::Oblique(start, end)
+                    }
+                    Err(_) => FontStyleRange::Oblique(14., 14.),
+                }
+            },
+            _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone()))),
+        })
+    }
+
+    fn parse_angle<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let token = input.next()?.clone();
+        let degrees = match token {
+            Token::Dimension {
+                value, ref unit, ..
+            } => {
+                match_ignore_ascii_case! { unit,
+                    "deg" => Some(value),
+                    "grad" => Some(value * 360. / 400.),
+                    "rad" => Some(value.to_degrees()),
+                    "turn" => Some(value * 360.),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match degrees {
+            Some(degrees) if (-90. ..=90.).contains(&degrees) => Ok(degrees),
+            _ => Err(location.new_basic_unexpected_token_error(token)),
+        }
+    }
+}
+
+impl ToCss for FontStyleRange {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            FontStyleRange::Auto => dest.write_str("auto"),
+            FontStyleRange::Normal => dest.write_str("normal"),
+            FontStyleRange::Italic => dest.write_str("italic"),
+            FontStyleRange::Oblique(start, end) => {
+                dest.write_str("oblique ")?;
+                start.to_css(dest)?;
+                dest.write_str("deg")?;
+                if end != start {
+                    dest.write_char(' ')?;
+                    end.to_css(dest)?;
+                    dest.write_str("deg")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The `font-display` descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontDisplay {
+    /// `auto`
+    Auto,
+    /// `block`
+    Block,
+    /// `swap`
+    Swap,
+    /// `fallback`
+    Fallback,
+    /// `optional`
+    Optional,
+}
+
+impl FontDisplay {
+    /// Parse the `font-display` descriptor.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident()?;
+        Ok(match_ignore_ascii_case! { ident,
+            "auto" => FontDisplay::Auto,
+            "block" => FontDisplay::Block,
+            "swap" => FontDisplay::Swap,
+            "fallback" => FontDisplay::Fallback,
+            "optional" => FontDisplay::Optional,
+            _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(ident.clone()))),
+        })
+    }
+}
+
+impl ToCss for FontDisplay {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str(match *self {
+            FontDisplay::Auto => "auto",
+            FontDisplay::Block => "block",
+            FontDisplay::Swap => "swap",
+            FontDisplay::Fallback => "fallback",
+            FontDisplay::Optional => "optional",
+        })
+    }
+}
+
+/// A `<feature-tag-value>` in `font-feature-settings`, such as `"liga" 0`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontFeatureSetting {
+    /// The OpenType feature tag: four ASCII characters.
+    pub tag: String,
+    /// The value: 1 for `on` or if omitted, and 0 for `off`.
+    pub value: u32,
+}
+
+impl FontFeatureSetting {
+    /// Parse a `<feature-tag-value>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let tag = input.expect_string()?;
+        if tag.len() != 4 || !tag.bytes().all(|b| (0x20..=0x7E).contains(&b)) {
+            return Err(location.new_basic_unexpected_token_error(Token::QuotedString(tag.clone())));
+        }
+        let tag = tag.as_ref().to_owned();
+        let value = input
+            .try_parse(|input| -> Result<_, BasicParseError<'i>> {
+                let location = input.current_source_location();
+                match *input.next()? {
+                    Token::Number {
+                        int_value: Some(value),
+                        ..
+                    } if value >= 0 => Ok(value as u32),
+                    Token::Ident(ref ident) if ident.eq_ignore_ascii_case("on") => Ok(1),
+                    Token::Ident(ref ident) if ident.eq_ignore_ascii_case("off") => Ok(0),
+                    ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+                }
+            })
+            .unwrap_or(1);
+        Ok(FontFeatureSetting { tag, value })
+    }
+}
+
+impl ToCss for FontFeatureSetting {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        serialize_string(&self.tag, dest)?;
+        if self.value != 1 {
+            dest.write_char(' ')?;
+            self.value.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `ascent-override`, `descent-override` and `line-gap-override` descriptors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricOverride {
+    /// `normal`, which uses the metric from the font file.
+    Normal,
+    /// A non-negative percentage of the used font size, as a fraction: 1.0 is 100%.
+    Percentage(f32),
+}
+
+impl MetricOverride {
+    /// Parse `normal | <percentage [0,∞]>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if input
+            .try_parse(|input| input.expect_ident_matching("normal"))
+            .is_ok()
+        {
+            return Ok(MetricOverride::Normal);
+        }
+        parse_non_negative_percentage(input).map(MetricOverride::Percentage)
+    }
+}
+
+impl ToCss for MetricOverride {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            MetricOverride::Normal => dest.write_str("normal"),
+            MetricOverride::Percentage(value) => write_percentage(value, dest),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-color-4/#gamut-mapping
+
+use super::{AbsoluteColor, Color, ColorSpace, RGBA};
+
+/// How to bring a color into the gamut of a color space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamutMapping {
+    /// The CSS gamut mapping algorithm, which reduces the OKLCH chroma until
+    /// clipping the color makes no noticeable difference.
+    ///
+    /// https://drafts.csswg.org/css-color-4/#binsearch
+    Css,
+    /// Clamp each component to the gamut, which can shift hues and lightness.
+    Clip,
+}
+
+/// The just noticeable difference in deltaEOK.
+const JND: f32 = 0.02;
+const EPSILON: f32 = 0.0001;
+
+impl ColorSpace {
+    /// Whether the colors of this space are bounded by a gamut:
+    /// the RGB spaces of `color()`, and `hsl` and `hwb` for the sRGB gamut.
+    pub fn is_bounded(&self) -> bool {
+        !matches!(
+            *self,
+            ColorSpace::XyzD50
+                | ColorSpace::XyzD65
+                | ColorSpace::Lab
+                | ColorSpace::Lch
+                | ColorSpace::Oklab
+                | ColorSpace::Oklch
+        )
+    }
+}
+
+impl AbsoluteColor {
+    /// Whether the color is in the gamut of its color space.
+    /// Missing components are zero.
+    pub fn in_gamut(&self) -> bool {
+        match self.space {
+            ColorSpace::Hsl | ColorSpace::Hwb => self.to_space(ColorSpace::Srgb).in_gamut(),
+            space if space.is_bounded() => self
+                .components
+                .iter()
+                .all(|c| (0. ..=1.).contains(&c.unwrap_or(0.))),
+            _ => true,
+        }
+    }
+
+    /// Clamp the components to the gamut of the color space.
+    pub fn clip(&self) -> AbsoluteColor {
+        match self.space {
+            ColorSpace::Hsl | ColorSpace::Hwb => {
+                self.to_space(ColorSpace::Srgb).clip().to_space(self.space)
+            }
+            space if space.is_bounded() => {
+                let mut clipped = *self;
+                for component in &mut clipped.components {
+                    *component = component.map(|c| c.clamp(0., 1.));
+                }
+                clipped
+            }
+            _ => *self,
+        }
+    }
+
+    /// The deltaEOK color difference with another color,
+    /// the euclidean distance in OKLab.
+    pub fn delta_e_ok(&self, other: &AbsoluteColor) -> f32 {
+        let [l1, a1, b1] = self.to_space(ColorSpace::Oklab).resolved_components();
+        let [l2, a2, b2] = other.to_space(ColorSpace::Oklab).resolved_components();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    /// Convert the color to a color space and bring it into its gamut.
+    pub fn to_gamut(&self, space: ColorSpace, mapping: GamutMapping) -> AbsoluteColor {
+        let destination = self.to_space(space);
+        if !space.is_bounded() {
+            return destination;
+        }
+        match mapping {
+            GamutMapping::Clip => destination.clip(),
+            GamutMapping::Css => self.to_gamut_css(space),
+        }
+    }
+
+    /// https://drafts.csswg.org/css-color-4/#binsearch
+    fn to_gamut_css(self, space: ColorSpace) -> AbsoluteColor {
+        let mut current = self.to_space(ColorSpace::Oklch);
+        let [lightness, chroma, _] = current.resolved_components();
+        if lightness >= 1. || lightness <= 0. {
+            let white_or_black = AbsoluteColor {
+                space: ColorSpace::Oklab,
+                components: [Some(lightness.clamp(0., 1.)), Some(0.), Some(0.)],
+                alpha: self.alpha,
+            };
+            return white_or_black.to_space(space).clip();
+        }
+        if current.to_space(space).in_gamut() {
+            return self.to_space(space);
+        }
+
+        let mut clipped = current.to_space(space).clip();
+        if clipped.delta_e_ok(&current) < JND {
+            return clipped;
+        }
+        let (mut min, mut max) = (0., chroma);
+        let mut min_in_gamut = true;
+        while max - min > EPSILON {
+            let chroma = (min + max) / 2.;
+            current.components[1] = Some(chroma);
+            let destination = current.to_space(space);
+            if min_in_gamut && destination.in_gamut() {
+                min = chroma;
+                continue;
+            }
+            clipped = destination.clip();
+            let delta_e = clipped.delta_e_ok(&current);
+            if delta_e < JND {
+                if JND - delta_e < EPSILON {
+                    break;
+                }
+                min_in_gamut = false;
+                min = chroma;
+            } else {
+                max = chroma;
+            }
+        }
+        clipped
+    }
+
+    /// Convert the color to sRGB with a gamut mapping.
+    pub fn to_rgba_in_gamut(&self, mapping: GamutMapping) -> RGBA {
+        let [red, green, blue] = self
+            .to_gamut(ColorSpace::Srgb, mapping)
+            .resolved_components();
+        RGBA::from_floats(red, green, blue, self.resolved_alpha())
+    }
+}
+
+impl Color {
+    /// The color in sRGB with a gamut mapping,
+    /// or `None` if it depends on the context, see `Color::to_absolute`.
+    pub fn to_rgba_in_gamut(&self, mapping: GamutMapping) -> Option<RGBA> {
+        match *self {
+            Color::RGBA(rgba) => Some(rgba),
+            _ => Some(self.to_absolute()?.to_rgba_in_gamut(mapping)),
+        }
+    }
+}
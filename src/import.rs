@@ -0,0 +1,354 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-cascade-5/#at-import
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{
+    stylesheet_encoding, BasicParseError, Condition, Delimiter, EncodingSupport, LayerName,
+    MediaQueryList, ParseError, Parser, ParserInput, SupportsCondition, SupportsFeature, ToCss,
+    Token,
+};
+use crate::serializer::serialize_string;
+
+/// The prelude of an `@import` rule:
+///
+/// `[ <url> | <string> ] [ layer | layer(<layer-name>) ]? [ supports( ... ) ]? <media-query-list>?`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportRule {
+    /// The URL of the imported stylesheet, as written.
+    pub url: String,
+    /// The cascade layer the imported stylesheet is put into, if any.
+    pub layer: Option<ImportLayer>,
+    /// The `supports()` condition, if any.
+    pub supports: Option<SupportsCondition>,
+    /// The media queries. An empty list means the import is unconditional.
+    pub media: MediaQueryList,
+}
+
+/// The layer of an `@import` rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportLayer {
+    /// `layer`, a new anonymous layer.
+    Anonymous,
+    /// `layer(<layer-name>)`
+    Named(LayerName),
+}
+
+impl ImportLayer {
+    /// The layer name, if any, as passed to `LayerOrder::enter`.
+    pub fn name(&self) -> Option<&LayerName> {
+        match *self {
+            ImportLayer::Anonymous => None,
+            ImportLayer::Named(ref name) => Some(name),
+        }
+    }
+}
+
+impl ImportRule {
+    /// Parse the prelude of an `@import` rule, after the at-keyword.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let url = input.expect_url_or_string()?.as_ref().to_owned();
+
+        let layer = if input
+            .try_parse(|input| input.expect_ident_matching("layer"))
+            .is_ok()
+        {
+            Some(ImportLayer::Anonymous)
+        } else if input
+            .try_parse(|input| input.expect_function_matching("layer"))
+            .is_ok()
+        {
+            // An invalid layer name makes the whole rule invalid.
+            let name: Result<_, ParseError<()>> =
+                input.parse_nested_block(|input| Ok(LayerName::parse(input)?));
+            Some(ImportLayer::Named(name.map_err(ParseError::basic)?))
+        } else {
+            None
+        };
+
+        let supports = if input
+            .try_parse(|input| input.expect_function_matching("supports"))
+            .is_ok()
+        {
+            // An invalid condition makes the whole rule invalid.
+            let condition: Result<_, ParseError<()>> = input.parse_nested_block(|input| {
+                // A bare declaration is allowed in addition to a condition.
+                if let Ok(condition) = input.try_parse(|input| {
+                    input.parse_entirely(|input| {
+                        SupportsCondition::parse(input).map_err(ParseError::<()>::from)
+                    })
+                }) {
+                    return Ok(condition);
+                }
+                Ok(Condition::Leaf(SupportsFeature::parse_declaration(input)?))
+            });
+            Some(condition.map_err(ParseError::basic)?)
+        } else {
+            None
+        };
+
+        let media = MediaQueryList::parse(input);
+        Ok(ImportRule {
+            url,
+            layer,
+            supports,
+            media,
+        })
+    }
+}
+
+impl ToCss for ImportRule {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("url(")?;
+        serialize_string(&self.url, dest)?;
+        dest.write_char(')')?;
+        match self.layer {
+            Some(ImportLayer::Anonymous) => dest.write_str(" layer")?,
+            Some(ImportLayer::Named(ref name)) => {
+                dest.write_str(" layer(")?;
+                name.to_css(dest)?;
+                dest.write_char(')')?;
+            }
+            None => {}
+        }
+        if let Some(ref supports) = self.supports {
+            dest.write_str(" supports(")?;
+            supports.to_css(dest)?;
+            dest.write_char(')')?;
+        }
+        if !self.media.media_queries.is_empty() {
+            dest.write_char(' ')?;
+            self.media.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// The bytes of a stylesheet, as fetched by a `StylesheetLoader`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadedStylesheet {
+    /// The content of the stylesheet.
+    pub bytes: Vec<u8>,
+    /// The encoding label given by HTTP or equivalent protocol, if any.
+    /// (e.g. via the `charset` parameter of the `Content-Type` header.)
+    pub protocol_encoding_label: Option<String>,
+}
+
+/// Decodes stylesheets for `resolve_imports`,
+/// in the encoding determined with `EncodingSupport`.
+pub trait StylesheetDecoder: EncodingSupport {
+    /// Decode bytes in the given encoding, as returned by `stylesheet_encoding`,
+    /// removing any BOM and replacing invalid sequences with U+FFFD.
+    ///
+    /// https://encoding.spec.whatwg.org/#decode
+    fn decode(bytes: &[u8], encoding: &Self::Encoding) -> String;
+}
+
+/// Fetches stylesheets for `resolve_imports`.
+pub trait StylesheetLoader {
+    /// The error returned when a stylesheet can not be loaded.
+    type Error;
+
+    /// Resolve the URL of an `@import` rule against the URL of the stylesheet containing it.
+    fn resolve_url(&self, base_url: &str, url: &str) -> String;
+
+    /// Fetch the stylesheet at the given resolved URL.
+    fn load(&mut self, url: &str) -> Result<LoadedStylesheet, Self::Error>;
+}
+
+/// A `StylesheetLoader` for the local filesystem, where URLs are file paths.
+///
+/// Relative paths are resolved against the directory of the importing stylesheet,
+/// and a leading `file://` is ignored.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSystemLoader;
+
+impl StylesheetLoader for FileSystemLoader {
+    type Error = io::Error;
+
+    fn resolve_url(&self, base_url: &str, url: &str) -> String {
+        let url = url.trim_start_matches("file://");
+        let path = Path::new(url);
+        if path.is_absolute() {
+            return url.to_owned();
+        }
+        match Path::new(base_url).parent() {
+            Some(directory) => directory.join(path).to_string_lossy().into_owned(),
+            None => url.to_owned(),
+        }
+    }
+
+    fn load(&mut self, url: &str) -> Result<LoadedStylesheet, io::Error> {
+        Ok(LoadedStylesheet {
+            bytes: fs::read(url)?,
+            protocol_encoding_label: None,
+        })
+    }
+}
+
+/// An error from `resolve_imports`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportError<E> {
+    /// A stylesheet could not be loaded.
+    Load {
+        /// The resolved URL of the stylesheet.
+        url: String,
+        /// The error returned by the loader.
+        error: E,
+    },
+    /// A stylesheet imports itself, directly or indirectly.
+    /// This contains the resolved URLs of the cycle, starting and ending with the same one.
+    Cycle(Vec<String>),
+}
+
+/// Load the stylesheet at the given URL and recursively inline its `@import` rules.
+///
+/// Each import is replaced with the content of the imported stylesheet,
+/// wrapped in `@layer`, `@supports` and `@media` blocks equivalent to its prelude.
+/// Invalid `@import` rules, those after other rules, and `@charset` rules are dropped.
+/// Other rules are copied as written; relative URLs in them are not rewritten.
+///
+/// Each stylesheet is decoded with `E::decode`, in the encoding determined by
+/// `stylesheet_encoding`, using the encoding of the importing stylesheet
+/// as the environment encoding.
+pub fn resolve_imports<E, L>(url: &str, loader: &mut L) -> Result<String, ImportError<L::Error>>
+where
+    E: StylesheetDecoder,
+    E::Encoding: Clone,
+    L: StylesheetLoader,
+{
+    let mut stack = Vec::new();
+    resolve_imports_recursive::<E, L>(url.to_owned(), None, loader, &mut stack)
+}
+
+fn resolve_imports_recursive<E, L>(
+    url: String,
+    environment_encoding: Option<E::Encoding>,
+    loader: &mut L,
+    stack: &mut Vec<String>,
+) -> Result<String, ImportError<L::Error>>
+where
+    E: StylesheetDecoder,
+    E::Encoding: Clone,
+    L: StylesheetLoader,
+{
+    if stack.contains(&url) {
+        let start = stack.iter().position(|u| *u == url).unwrap();
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(url);
+        return Err(ImportError::Cycle(cycle));
+    }
+    let sheet = match loader.load(&url) {
+        Ok(sheet) => sheet,
+        Err(error) => return Err(ImportError::Load { url, error }),
+    };
+    let encoding = stylesheet_encoding::<E>(
+        &sheet.bytes,
+        sheet
+            .protocol_encoding_label
+            .as_ref()
+            .map(|label| label.as_bytes()),
+        environment_encoding,
+    );
+    let css = E::decode(&sheet.bytes, &encoding);
+
+    stack.push(url);
+    let mut input = ParserInput::new(&css);
+    let mut input = Parser::new(&mut input);
+    let mut result = String::new();
+    let mut imports_allowed = true;
+    loop {
+        input.skip_whitespace();
+        let start = input.position();
+        let name = match input.next() {
+            Err(_) => break,
+            Ok(Token::CDO) | Ok(Token::CDC) => continue,
+            Ok(Token::AtKeyword(name)) => Some(name.clone()),
+            Ok(_) => None,
+        };
+        let name = match name {
+            Some(name) => name,
+            None => {
+                imports_allowed = false;
+                consume_rule(&mut input);
+                result.push_str(input.slice_from(start));
+                result.push('\n');
+                continue;
+            }
+        };
+        match_ignore_ascii_case! { &name,
+            "import" => {
+                let import: Result<_, ParseError<()>> =
+                    input.parse_until_after(Delimiter::Semicolon, |input| Ok(ImportRule::parse(input)?));
+                if let (true, Ok(import)) = (imports_allowed, import) {
+                    let url = loader.resolve_url(stack.last().unwrap(), &import.url);
+                    let imported =
+                        resolve_imports_recursive::<E, L>(url, Some(encoding.clone()), loader, stack)?;
+                    result.push_str(&wrap_import(&import, imported));
+                }
+            },
+            "charset" => {
+                consume_rule(&mut input);
+            },
+            _ => {
+                let has_block = consume_rule(&mut input);
+                // `@layer` statements are allowed before `@import` rules.
+                if has_block || !name.eq_ignore_ascii_case("layer") {
+                    imports_allowed = false;
+                }
+                result.push_str(input.slice_from(start));
+                result.push('\n');
+            },
+        }
+    }
+    stack.pop();
+    Ok(result)
+}
+
+/// Consume the rest of a rule after its first token, and return whether it had a `{}` block.
+fn consume_rule(input: &mut Parser) -> bool {
+    let _: Result<(), ParseError<()>> = input.parse_until_before(
+        Delimiter::Semicolon | Delimiter::CurlyBracketBlock,
+        |input| {
+            while input.next().is_ok() {}
+            Ok(())
+        },
+    );
+    match input.next() {
+        Ok(&Token::CurlyBracketBlock) => {
+            let _: Result<(), ParseError<()>> = input.parse_nested_block(|input| {
+                while input.next().is_ok() {}
+                Ok(())
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+fn wrap_import(import: &ImportRule, mut css: String) -> String {
+    if let Some(ref layer) = import.layer {
+        css = match *layer {
+            ImportLayer::Anonymous => format!("@layer {{\n{}}}\n", css),
+            ImportLayer::Named(ref name) => {
+                format!("@layer {} {{\n{}}}\n", name.to_css_string(), css)
+            }
+        };
+    }
+    if let Some(ref supports) = import.supports {
+        css = format!("@supports {} {{\n{}}}\n", supports.to_css_string(), css);
+    }
+    if !import.media.media_queries.is_empty() {
+        css = format!("@media {} {{\n{}}}\n", import.media.to_css_string(), css);
+    }
+    css
+}
@@ -0,0 +1,305 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-animations-1/#keyframes
+
+use std::fmt;
+
+use super::{
+    AtRuleParser, BasicParseError, CowRcStr, DeclarationListParser, DeclarationParser, ParseError,
+    Parser, ParserState, QualifiedRuleParser, ToCss, Token,
+};
+use crate::parser::is_css_wide_keyword;
+use crate::serializer::{serialize_identifier, serialize_string};
+
+/// The name of a `@keyframes` or `@-webkit-keyframes` rule.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeyframesName {
+    /// A `<custom-ident>`.
+    Ident(String),
+    /// A `<string>`.
+    String(String),
+}
+
+impl KeyframesName {
+    /// Parse the prelude of a `@keyframes` or `@-webkit-keyframes` rule.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::QuotedString(ref name) => Ok(KeyframesName::String(name.as_ref().to_owned())),
+            // `none` and CSS-wide keywords are not valid keyframes names.
+            Token::Ident(ref name)
+                if name.eq_ignore_ascii_case("none") || is_css_wide_keyword(name) =>
+            {
+                Err(location.new_basic_unexpected_token_error(Token::Ident(name.clone())))
+            }
+            Token::Ident(ref name) => Ok(KeyframesName::Ident(name.as_ref().to_owned())),
+            ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+        }
+    }
+
+    /// The name, whether it was an identifier or a string.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            KeyframesName::Ident(ref name) | KeyframesName::String(ref name) => name,
+        }
+    }
+}
+
+impl ToCss for KeyframesName {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            KeyframesName::Ident(ref name) => serialize_identifier(name, dest),
+            KeyframesName::String(ref name) => serialize_string(name, dest),
+        }
+    }
+}
+
+/// A `<timeline-range-name>`, for keyframes of scroll-driven animations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimelineRangeName {
+    /// `cover`
+    Cover,
+    /// `contain`
+    Contain,
+    /// `entry`
+    Entry,
+    /// `exit`
+    Exit,
+    /// `entry-crossing`
+    EntryCrossing,
+    /// `exit-crossing`
+    ExitCrossing,
+}
+
+impl TimelineRangeName {
+    fn from_ident(ident: &str) -> Option<Self> {
+        Some(match_ignore_ascii_case! { ident,
+            "cover" => TimelineRangeName::Cover,
+            "contain" => TimelineRangeName::Contain,
+            "entry" => TimelineRangeName::Entry,
+            "exit" => TimelineRangeName::Exit,
+            "entry-crossing" => TimelineRangeName::EntryCrossing,
+            "exit-crossing" => TimelineRangeName::ExitCrossing,
+            _ => return None,
+        })
+    }
+}
+
+impl ToCss for TimelineRangeName {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str(match *self {
+            TimelineRangeName::Cover => "cover",
+            TimelineRangeName::Contain => "contain",
+            TimelineRangeName::Entry => "entry",
+            TimelineRangeName::Exit => "exit",
+            TimelineRangeName::EntryCrossing => "entry-crossing",
+            TimelineRangeName::ExitCrossing => "exit-crossing",
+        })
+    }
+}
+
+/// A `<keyframe-selector>`.
+///
+/// Offsets are fractions between 0.0 and 1.0, like `Token::Percentage::unit_value`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyframeSelector {
+    /// `from` (0%), `to` (100%), or a percentage.
+    Percentage(f32),
+    /// A percentage within a named timeline range, such as `entry 20%`.
+    TimelineRange(TimelineRangeName, f32),
+}
+
+impl KeyframeSelector {
+    /// Parse a `<keyframe-selector>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let token = input.next()?.clone();
+        match token {
+            Token::Ident(ref ident) => {
+                if ident.eq_ignore_ascii_case("from") {
+                    return Ok(KeyframeSelector::Percentage(0.));
+                }
+                if ident.eq_ignore_ascii_case("to") {
+                    return Ok(KeyframeSelector::Percentage(1.));
+                }
+                if let Some(range) = TimelineRangeName::from_ident(ident) {
+                    return Ok(KeyframeSelector::TimelineRange(range, parse_offset(input)?));
+                }
+            }
+            Token::Percentage { unit_value, .. } if (0. ..=1.).contains(&unit_value) => {
+                return Ok(KeyframeSelector::Percentage(unit_value));
+            }
+            _ => {}
+        }
+        Err(location.new_basic_unexpected_token_error(token))
+    }
+
+    /// Parse a `<keyframe-selector>#`, the prelude of a keyframe rule.
+    pub fn parse_list<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Vec<Self>, BasicParseError<'i>> {
+        let result: Result<_, ParseError<()>> =
+            input.parse_comma_separated(|input| Ok(Self::parse(input)?));
+        result.map_err(ParseError::basic)
+    }
+}
+
+fn parse_offset<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, BasicParseError<'i>> {
+    let location = input.current_source_location();
+    match *input.next()? {
+        Token::Percentage { unit_value, .. } if (0. ..=1.).contains(&unit_value) => Ok(unit_value),
+        ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+    }
+}
+
+fn write_offset<W: fmt::Write>(offset: f32, dest: &mut W) -> fmt::Result {
+    (offset * 100.).to_css(dest)?;
+    dest.write_char('%')
+}
+
+impl ToCss for KeyframeSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            KeyframeSelector::Percentage(offset) => write_offset(offset, dest),
+            KeyframeSelector::TimelineRange(range, offset) => {
+                range.to_css(dest)?;
+                dest.write_char(' ')?;
+                write_offset(offset, dest)
+            }
+        }
+    }
+}
+
+/// A keyframe rule in a `@keyframes` block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframe<D> {
+    /// The keyframe selectors.
+    pub selectors: Vec<KeyframeSelector>,
+    /// The valid declarations, in order.
+    pub declarations: Vec<D>,
+}
+
+impl<D> Keyframe<D> {
+    /// Sort and merge keyframes the way browsers build the keyframes of an animation.
+    ///
+    /// The result has one keyframe per distinct selector, with all the declarations of
+    /// the keyframes that have this selector, in order: for a given property,
+    /// the last declaration wins. Percentage keyframes come first, sorted by offset,
+    /// then timeline range keyframes in the order they first appear,
+    /// since their offsets depend on the timeline.
+    pub fn sort_and_merge(keyframes: Vec<Keyframe<D>>) -> Vec<Keyframe<D>>
+    where
+        D: Clone,
+    {
+        let mut merged: Vec<Keyframe<D>> = Vec::new();
+        for keyframe in keyframes {
+            for selector in &keyframe.selectors {
+                let declarations = keyframe.declarations.iter().cloned();
+                match merged.iter_mut().find(|k| k.selectors[0] == *selector) {
+                    Some(existing) => existing.declarations.extend(declarations),
+                    None => merged.push(Keyframe {
+                        selectors: vec![*selector],
+                        declarations: declarations.collect(),
+                    }),
+                }
+            }
+        }
+        let sort_key = |keyframe: &Keyframe<D>| match keyframe.selectors[0] {
+            KeyframeSelector::Percentage(offset) => (0, offset),
+            KeyframeSelector::TimelineRange(..) => (1, 0.),
+        };
+        merged.sort_by(|a, b| {
+            let (a, b) = (sort_key(a), sort_key(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        merged
+    }
+}
+
+/// Parses the content of a `@keyframes` block into `Keyframe`s,
+/// to be used with `RuleListParser::new_for_nested_rule`.
+///
+/// Declarations are parsed by the wrapped `DeclarationParser`.
+/// Invalid declarations are dropped, as are keyframe rules with an invalid selector
+/// and at-rules. Per spec, implementations of `parse_value` should reject `!important`.
+pub struct KeyframeListParser<P> {
+    /// The parser for declarations in keyframes.
+    pub declaration_parser: P,
+}
+
+impl<P> KeyframeListParser<P> {
+    /// Create a parser for keyframe rules with the given declaration parser.
+    pub fn new(declaration_parser: P) -> Self {
+        KeyframeListParser { declaration_parser }
+    }
+}
+
+impl<'i, P: DeclarationParser<'i>> QualifiedRuleParser<'i> for KeyframeListParser<P> {
+    type Prelude = Vec<KeyframeSelector>;
+    type QualifiedRule = Keyframe<P::Declaration>;
+    type Error = P::Error;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, P::Error>> {
+        Ok(KeyframeSelector::parse_list(input)?)
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        selectors: Self::Prelude,
+        _: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::QualifiedRule, ParseError<'i, P::Error>> {
+        let parser = DeclarationsOnly(&mut self.declaration_parser);
+        let declarations = DeclarationListParser::new(input, parser)
+            .flatten()
+            .collect();
+        Ok(Keyframe {
+            selectors,
+            declarations,
+        })
+    }
+}
+
+impl<'i, P: DeclarationParser<'i>> AtRuleParser<'i> for KeyframeListParser<P> {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = Keyframe<P::Declaration>;
+    type Error = P::Error;
+}
+
+/// Forwards declarations to a `DeclarationParser`, and rejects at-rules.
+pub(crate) struct DeclarationsOnly<'a, P>(pub(crate) &'a mut P);
+
+impl<'a, 'i, P: DeclarationParser<'i>> DeclarationParser<'i> for DeclarationsOnly<'a, P> {
+    type Declaration = P::Declaration;
+    type Error = P::Error;
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Declaration, ParseError<'i, P::Error>> {
+        self.0.parse_value(name, input)
+    }
+}
+
+impl<'a, 'i, P: DeclarationParser<'i>> AtRuleParser<'i> for DeclarationsOnly<'a, P> {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = P::Declaration;
+    type Error = P::Error;
+}
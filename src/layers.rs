@@ -0,0 +1,296 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-cascade-5/#layering
+
+use std::fmt;
+
+use super::{AtRuleType, BasicParseError, ParseError, Parser, SourceLocation, ToCss, Token};
+use crate::parser::is_css_wide_keyword;
+use crate::serializer::serialize_identifier;
+
+/// A `<layer-name>`: one or more identifiers separated by `.`, such as `framework.base`.
+///
+/// Identifiers are case-sensitive.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LayerName(pub Vec<String>);
+
+impl LayerName {
+    /// Parse a `<layer-name>`. No whitespace is allowed around the dots.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let first = input.expect_ident_cloned()?;
+        let mut names = vec![Self::parse_component(&first, location)?];
+        loop {
+            let state = input.state();
+            match input.next_including_whitespace() {
+                Ok(&Token::Delim('.')) => {}
+                _ => {
+                    input.reset(&state);
+                    break;
+                }
+            }
+            let location = input.current_source_location();
+            let name = match *input.next_including_whitespace()? {
+                Token::Ident(ref name) => name.clone(),
+                ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+            };
+            names.push(Self::parse_component(&name, location)?);
+        }
+        Ok(LayerName(names))
+    }
+
+    fn parse_component<'i>(
+        name: &str,
+        location: SourceLocation,
+    ) -> Result<String, BasicParseError<'i>> {
+        if is_css_wide_keyword(name) {
+            return Err(
+                location.new_basic_unexpected_token_error(Token::Ident(name.to_owned().into()))
+            );
+        }
+        Ok(name.to_owned())
+    }
+}
+
+impl ToCss for LayerName {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        for (i, name) in self.0.iter().enumerate() {
+            if i > 0 {
+                dest.write_char('.')?;
+            }
+            serialize_identifier(name, dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// The prelude of a `@layer` rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayerRule {
+    /// `@layer <layer-name>#;`, which declares layers without adding rules to them.
+    Statement(Vec<LayerName>),
+    /// `@layer <layer-name>? { ... }`, which adds rules to a named or anonymous layer.
+    Block(Option<LayerName>),
+}
+
+impl LayerRule {
+    /// Parse the prelude of a `@layer` rule, after the at-keyword.
+    ///
+    /// The result is meant to be returned from `AtRuleParser::parse_prelude`:
+    /// a `LayerRule::Statement` is returned in `WithoutBlock`,
+    /// and a `LayerRule::Block` in `WithBlock`.
+    /// A single name can be followed by either a block or a semicolon,
+    /// so `AtRuleParser::rule_without_optional_block` should accept
+    /// a named `LayerRule::Block`, see `LayerRule::without_block`.
+    pub fn parse_prelude<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<AtRuleType<LayerRule, LayerRule>, BasicParseError<'i>> {
+        if input.is_exhausted() {
+            return Ok(AtRuleType::WithBlock(LayerRule::Block(None)));
+        }
+        let mut names: Vec<LayerName> = input
+            .parse_comma_separated(|input| LayerName::parse(input).map_err(ParseError::<()>::from))
+            .map_err(ParseError::basic)?;
+        if names.len() == 1 {
+            Ok(AtRuleType::WithBlock(LayerRule::Block(names.pop())))
+        } else {
+            Ok(AtRuleType::WithoutBlock(LayerRule::Statement(names)))
+        }
+    }
+
+    /// The statement equivalent to a `LayerRule::Block` without a block,
+    /// for `AtRuleParser::rule_without_optional_block`,
+    /// or `None` for an anonymous layer, which requires a block.
+    pub fn without_block(self) -> Option<Self> {
+        match self {
+            LayerRule::Block(Some(name)) => Some(LayerRule::Statement(vec![name])),
+            LayerRule::Block(None) => None,
+            statement => Some(statement),
+        }
+    }
+}
+
+impl ToCss for LayerRule {
+    /// Serialize the prelude, without the at-keyword.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            LayerRule::Statement(ref names) => {
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    name.to_css(dest)?;
+                }
+                Ok(())
+            }
+            LayerRule::Block(Some(ref name)) => name.to_css(dest),
+            LayerRule::Block(None) => Ok(()),
+        }
+    }
+}
+
+/// Identifies a cascade layer in a `LayerOrder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(pub usize);
+
+impl LayerId {
+    /// The implicit outer layer, for rules that are not in any layer.
+    pub const UNLAYERED: LayerId = LayerId(0);
+}
+
+#[derive(Clone, Debug)]
+struct LayerNode {
+    /// `None` for anonymous layers and the implicit outer layer.
+    name: Option<String>,
+    parent: usize,
+    children: Vec<usize>,
+}
+
+/// Computes the order of cascade layers while walking the rules of a stylesheet,
+/// including layers introduced by `@import ... layer(...)`.
+///
+/// Call `declare` for `@layer` statements, and `enter` and `exit` around the rules of
+/// `@layer` blocks and layered imports; `current` is then the layer of any other rule.
+/// Once the whole stylesheet has been walked, `ranks` gives the order of the layers.
+#[derive(Clone, Debug)]
+pub struct LayerOrder {
+    nodes: Vec<LayerNode>,
+    stack: Vec<usize>,
+}
+
+impl Default for LayerOrder {
+    fn default() -> Self {
+        LayerOrder::new()
+    }
+}
+
+impl LayerOrder {
+    /// Create an order with only the implicit outer layer.
+    pub fn new() -> Self {
+        LayerOrder {
+            nodes: vec![LayerNode {
+                name: None,
+                parent: 0,
+                children: Vec::new(),
+            }],
+            stack: vec![0],
+        }
+    }
+
+    /// Declare the given layers, nested in the current one, as in `@layer a, b.c;`.
+    pub fn declare(&mut self, names: &[LayerName]) {
+        for name in names {
+            self.find_or_insert(name);
+        }
+    }
+
+    /// Enter a named or anonymous layer nested in the current one,
+    /// as at the start of `@layer a { ... }` or an import with `layer(a)`,
+    /// and return its identifier.
+    pub fn enter(&mut self, name: Option<&LayerName>) -> LayerId {
+        let id = match name {
+            Some(name) => self.find_or_insert(name),
+            None => {
+                let parent = self.current().0;
+                self.insert(parent, None)
+            }
+        };
+        self.stack.push(id);
+        LayerId(id)
+    }
+
+    /// Leave the layer entered by the last call to `enter`.
+    ///
+    /// Panics if there is no such layer.
+    pub fn exit(&mut self) {
+        assert!(
+            self.stack.len() > 1,
+            "LayerOrder::exit called without enter"
+        );
+        self.stack.pop();
+    }
+
+    /// The layer that rules at the current position belong to.
+    pub fn current(&self) -> LayerId {
+        LayerId(*self.stack.last().unwrap())
+    }
+
+    /// The full name of a layer, or `None` for anonymous layers, layers nested in one,
+    /// and the implicit outer layer.
+    pub fn name(&self, id: LayerId) -> Option<LayerName> {
+        let mut names = Vec::new();
+        let mut index = id.0;
+        while index != 0 {
+            let node = &self.nodes[index];
+            names.push(node.name.clone()?);
+            index = node.parent;
+        }
+        if names.is_empty() {
+            return None;
+        }
+        names.reverse();
+        Some(LayerName(names))
+    }
+
+    /// The rank of each layer, indexed by `LayerId`.
+    ///
+    /// For normal declarations, those in a layer with a higher rank win.
+    /// (This is reversed for `!important` declarations.)
+    /// Sibling layers are ranked in the order they were first declared,
+    /// and a layer ranks above its sub-layers, so that the implicit outer layer ranks highest.
+    ///
+    /// Layers declared later can change the ranks of existing layers,
+    /// so this should be called after walking the whole stylesheet.
+    pub fn ranks(&self) -> Vec<usize> {
+        fn visit(nodes: &[LayerNode], index: usize, ranks: &mut [usize], next_rank: &mut usize) {
+            for &child in &nodes[index].children {
+                visit(nodes, child, ranks, next_rank);
+            }
+            ranks[index] = *next_rank;
+            *next_rank += 1;
+        }
+        let mut ranks = vec![0; self.nodes.len()];
+        visit(&self.nodes, 0, &mut ranks, &mut 0);
+        ranks
+    }
+
+    /// The rank of one layer. See `ranks`.
+    pub fn rank(&self, id: LayerId) -> usize {
+        self.ranks()[id.0]
+    }
+
+    fn find_or_insert(&mut self, name: &LayerName) -> usize {
+        let mut index = self.current().0;
+        for component in &name.0 {
+            let existing = self.nodes[index]
+                .children
+                .iter()
+                .cloned()
+                .find(|&child| self.nodes[child].name.as_ref() == Some(component));
+            index = match existing {
+                Some(child) => child,
+                None => self.insert(index, Some(component.clone())),
+            };
+        }
+        index
+    }
+
+    fn insert(&mut self, parent: usize, name: Option<String>) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(LayerNode {
+            name,
+            parent,
+            children: Vec::new(),
+        });
+        self.nodes[parent].children.push(index);
+        index
+    }
+}
@@ -68,21 +68,60 @@ fn parse_border_spacing(_context: &ParserContext, input: &mut Parser)
 #![recursion_limit = "200"] // For color::parse_color_keyword
 
 pub use crate::color::{
-    parse_color_keyword, AngleOrNumber, Color, ColorComponentParser, NumberOrPercentage, RGBA,
+    parse_color_keyword, AngleOrNumber, ChannelCalc, ChannelKeywords, Color, ColorComponentParser,
+    ColorMix, ColorScheme, LightDark, NumberOrPercentage, RelativeColor, RelativeComponent, RGBA,
 };
+pub use crate::color_space::{
+    AbsoluteColor, ColorInterpolationMethod, ColorSpace, HueInterpolationMethod,
+};
+pub use crate::condition::Condition;
+pub use crate::container_queries::{ContainerCondition, ContainerFeature, ContainerQuery};
+pub use crate::container_queries::{QueryContainer, ScrollStateQuery, StyleFeature, StyleQuery};
+pub use crate::counter_style::{
+    AdditiveSymbol, CounterRange, CounterStyle, CounterStyleDescriptor,
+};
+pub use crate::counter_style::{
+    CounterStyleDescriptorParser, CounterStyleName, CounterStyleRegistry,
+};
+pub use crate::counter_style::{Negative, Pad, SpeakAs, Symbol, System};
 pub use crate::cow_rc_str::CowRcStr;
+pub use crate::font_face::{FontDisplay, FontFace, FontFaceDescriptor, FontFaceDescriptorParser};
+pub use crate::font_face::{FontFaceSource, FontFeatureSetting, FontFormat, FontStretchRange};
+pub use crate::font_face::{FontStyleRange, FontWeightRange, MetricOverride};
 pub use crate::from_bytes::{stylesheet_encoding, EncodingSupport};
+pub use crate::gamut_mapping::GamutMapping;
+pub use crate::import::{resolve_imports, FileSystemLoader, ImportError, ImportLayer, ImportRule};
+pub use crate::import::{LoadedStylesheet, StylesheetDecoder, StylesheetLoader};
+pub use crate::keyframes::TimelineRangeName;
+pub use crate::keyframes::{Keyframe, KeyframeListParser, KeyframeSelector, KeyframesName};
+pub use crate::layers::{LayerId, LayerName, LayerOrder, LayerRule};
 #[doc(hidden)]
 pub use crate::macros::_cssparser_internal_to_lowercase;
-pub use crate::nth::parse_nth;
+pub use crate::media_queries::MediaType;
+pub use crate::media_queries::{Comparison, MediaCondition, MediaEnvironment, MediaFeature};
+pub use crate::media_queries::{MediaFeatureValue, MediaQualifier, MediaQuery, MediaQueryList};
+pub use crate::namespaces::{NamespaceMap, NamespaceRule, TopLevelRuleKind, TopLevelRuleOrder};
+pub use crate::nth::{parse_nth, AnPlusB};
+pub use crate::page::{PageMarginBox, PageMarginRule, PagePseudoClass, PageRule, PageSelector};
 pub use crate::parser::{BasicParseError, BasicParseErrorKind, ParseError, ParseErrorKind};
 pub use crate::parser::{Delimiter, Delimiters, Parser, ParserInput, ParserState};
+pub use crate::property_rule::{DataType, Multiplier, PropertyRule, PropertyRuleError};
+pub use crate::property_rule::{SyntaxComponent, SyntaxComponentName, SyntaxDefinition};
 pub use crate::rules_and_declarations::{parse_important, parse_one_declaration};
 pub use crate::rules_and_declarations::{parse_one_rule, RuleListParser};
 pub use crate::rules_and_declarations::{AtRuleParser, AtRuleType, QualifiedRuleParser};
 pub use crate::rules_and_declarations::{DeclarationListParser, DeclarationParser};
+pub use crate::scope::{ScopeBodyParser, ScopeRule, ScopedRuleParser};
+pub use crate::selectors::{AttributeCase, AttributeOperator, AttributeSelector, Combinator};
+pub use crate::selectors::{
+    CompoundSelector, Direction, NamespaceConstraint, NthKind, NthSelector,
+};
+pub use crate::selectors::{PseudoClass, PseudoElement, RelativeSelector, Selector, SelectorList};
+pub use crate::selectors::{SelectorParser, SimpleSelector, Specificity, StatePseudoClass};
 pub use crate::serializer::{serialize_identifier, serialize_name, serialize_string};
 pub use crate::serializer::{CssStringWriter, ToCss, TokenSerializationType};
+pub use crate::supports::{SupportsCondition, SupportsFeature, SupportsParser};
+pub use crate::system_color::{SystemColor, ThemeProvider};
 pub use crate::tokenizer::{SourceLocation, SourcePosition, Token};
 pub use crate::unicode_range::UnicodeRange;
 pub use cssparser_macros::*;
@@ -102,11 +141,31 @@ mod tokenizer {
     include!(concat!(env!("OUT_DIR"), "/tokenizer.rs"));
 }
 mod color;
+mod color_space;
+mod condition;
+mod container_queries;
+mod contrast;
+mod counter_style;
 mod cow_rc_str;
+pub mod dom;
+mod font_face;
 mod from_bytes;
+mod gamut_mapping;
+mod import;
+mod keyframes;
+mod layers;
+pub mod matching;
+mod media_queries;
+mod namespaces;
 mod nth;
+mod page;
 mod parser;
+mod property_rule;
+mod scope;
+mod selectors;
 mod serializer;
+mod supports;
+mod system_color;
 mod unicode_range;
 
 #[cfg(test)]
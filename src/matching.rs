@@ -0,0 +1,487 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/selectors-4/#match-a-selector-against-an-element
+
+use super::{
+    AttributeCase, AttributeOperator, AttributeSelector, Combinator, CompoundSelector, Direction,
+    NamespaceConstraint, NthKind, NthSelector, PseudoClass, PseudoElement, RelativeSelector,
+    Selector, SelectorList, SimpleSelector, StatePseudoClass,
+};
+
+/// A set of `StatePseudoClass`, the state of an element as known by the host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ElementState(u32);
+
+impl ElementState {
+    /// The state of an element that matches none of the state pseudo-classes.
+    pub fn empty() -> Self {
+        ElementState(0)
+    }
+
+    /// Add a pseudo-class to the set.
+    pub fn insert(&mut self, pseudo_class: StatePseudoClass) {
+        self.0 |= 1 << pseudo_class as u32
+    }
+
+    /// Remove a pseudo-class from the set.
+    pub fn remove(&mut self, pseudo_class: StatePseudoClass) {
+        self.0 &= !(1 << pseudo_class as u32)
+    }
+
+    /// Whether the element matches this pseudo-class.
+    pub fn contains(&self, pseudo_class: StatePseudoClass) -> bool {
+        self.0 & (1 << pseudo_class as u32) != 0
+    }
+}
+
+/// An element in a tree that selectors can be matched against.
+///
+/// Values of this type are handles that are cheap to clone,
+/// and equal when they refer to the same element.
+pub trait Element: Sized + Clone + PartialEq {
+    /// The parent element, or `None` for the root element
+    /// (and for elements whose parent is not an element).
+    fn parent_element(&self) -> Option<Self>;
+
+    /// The previous sibling that is an element.
+    fn prev_sibling_element(&self) -> Option<Self>;
+
+    /// The next sibling that is an element.
+    fn next_sibling_element(&self) -> Option<Self>;
+
+    /// The first child that is an element.
+    fn first_child_element(&self) -> Option<Self>;
+
+    /// The local name of the element.
+    fn local_name(&self) -> &str;
+
+    /// The namespace URL of the element, if any.
+    fn namespace(&self) -> Option<&str>;
+
+    /// The ID of the element, if any.
+    fn id(&self) -> Option<&str>;
+
+    /// Whether the element has this class.
+    fn has_class(&self, name: &str) -> bool;
+
+    /// Whether `f` returns `true` for the namespace and value of an attribute
+    /// of the element with this local name.
+    fn any_attribute(
+        &self,
+        local_name: &str,
+        f: &mut dyn FnMut(Option<&str>, &str) -> bool,
+    ) -> bool;
+
+    /// The state of the element, for pseudo-classes such as `:hover` or `:checked`.
+    fn state(&self) -> ElementState;
+
+    /// Whether the element has no children, other than comments and processing instructions.
+    fn is_empty(&self) -> bool;
+
+    /// The language declared on the element itself, such as its `lang` attribute.
+    /// The language of an element is inherited from its ancestors.
+    fn lang(&self) -> Option<&str> {
+        None
+    }
+
+    /// The directionality of the element, for `:dir()`.
+    fn direction(&self) -> Direction {
+        Direction::Ltr
+    }
+
+    /// Whether the element matches a pseudo-class accepted by the `SelectorParser`.
+    fn matches_custom_pseudo_class(&self, name: &str, arguments: Option<&str>) -> bool {
+        let _ = (name, arguments);
+        false
+    }
+}
+
+/// What an element is matched for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchingContext<E> {
+    /// The element that `:scope` matches, or `None` for the root element.
+    pub scope: Option<E>,
+    /// The pseudo-element that is matched, whose originating element is the element
+    /// that the selector is matched against. When `None`, selectors with
+    /// a pseudo-element do not match.
+    pub pseudo_element: Option<PseudoElement>,
+}
+
+impl<E> Default for MatchingContext<E> {
+    fn default() -> Self {
+        MatchingContext::new()
+    }
+}
+
+impl<E> MatchingContext<E> {
+    /// A context for matching elements, without any scope.
+    pub fn new() -> Self {
+        MatchingContext {
+            scope: None,
+            pseudo_element: None,
+        }
+    }
+}
+
+impl SelectorList {
+    /// Whether any selector of the list matches the element.
+    pub fn matches<E: Element>(&self, element: &E, context: &MatchingContext<E>) -> bool {
+        self.0
+            .iter()
+            .any(|selector| selector.matches(element, context))
+    }
+}
+
+impl Selector {
+    /// Whether the selector matches the element.
+    ///
+    /// The `||` combinator never matches, as elements do not know about table columns.
+    /// Pseudo-classes after a pseudo-element are matched against the originating element.
+    pub fn matches<E: Element>(&self, element: &E, context: &MatchingContext<E>) -> bool {
+        if self.pseudo_element() != context.pseudo_element.as_ref() {
+            return false;
+        }
+        matches_complex(&self.compounds, &self.combinators, element, context, None)
+    }
+}
+
+/// Match the selector from right to left, `anchor` being the element
+/// that the leftmost compound selector must be related to, for relative selectors.
+fn matches_complex<E: Element>(
+    compounds: &[CompoundSelector],
+    combinators: &[Combinator],
+    element: &E,
+    context: &MatchingContext<E>,
+    anchor: Option<(&E, Combinator)>,
+) -> bool {
+    let (last, compounds) = match compounds.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    if !matches_compound(last, element, context) {
+        return false;
+    }
+    let (combinator, combinators) = match combinators.split_last() {
+        Some((&combinator, combinators)) => (combinator, combinators),
+        None => {
+            return match anchor {
+                Some((anchor, combinator)) => {
+                    any_related(element, combinator, |related| related == anchor)
+                }
+                None => true,
+            }
+        }
+    };
+    any_related(element, combinator, |related| {
+        matches_complex(compounds, combinators, related, context, anchor)
+    })
+}
+
+/// Whether `f` returns `true` for an element on the left side of `combinator`
+/// when `element` is on its right side.
+fn any_related<E: Element>(
+    element: &E,
+    combinator: Combinator,
+    mut f: impl FnMut(&E) -> bool,
+) -> bool {
+    match combinator {
+        Combinator::Child => matches!(element.parent_element(), Some(parent) if f(&parent)),
+        Combinator::Descendant => {
+            let mut ancestor = element.parent_element();
+            while let Some(element) = ancestor {
+                if f(&element) {
+                    return true;
+                }
+                ancestor = element.parent_element();
+            }
+            false
+        }
+        Combinator::NextSibling => {
+            matches!(element.prev_sibling_element(), Some(sibling) if f(&sibling))
+        }
+        Combinator::SubsequentSibling => {
+            let mut sibling = element.prev_sibling_element();
+            while let Some(element) = sibling {
+                if f(&element) {
+                    return true;
+                }
+                sibling = element.prev_sibling_element();
+            }
+            false
+        }
+        Combinator::Column => false,
+    }
+}
+
+fn matches_compound<E: Element>(
+    compound: &CompoundSelector,
+    element: &E,
+    context: &MatchingContext<E>,
+) -> bool {
+    compound
+        .0
+        .iter()
+        .all(|simple| matches_simple(simple, element, context))
+}
+
+fn matches_simple<E: Element>(
+    simple: &SimpleSelector,
+    element: &E,
+    context: &MatchingContext<E>,
+) -> bool {
+    match *simple {
+        SimpleSelector::Type {
+            ref namespace,
+            ref local_name,
+        } => {
+            element.local_name() == local_name
+                && matches_namespace(namespace, element.namespace(), false)
+        }
+        SimpleSelector::Universal { ref namespace } => {
+            matches_namespace(namespace, element.namespace(), false)
+        }
+        SimpleSelector::Id(ref id) => element.id() == Some(&**id),
+        SimpleSelector::Class(ref class) => element.has_class(class),
+        SimpleSelector::Attribute(ref attribute) => matches_attribute(attribute, element),
+        SimpleSelector::PseudoClass(ref pseudo_class) => {
+            matches_pseudo_class(pseudo_class, element, context)
+        }
+        // Checked by `Selector::matches`.
+        SimpleSelector::PseudoElement(_) => true,
+    }
+}
+
+fn matches_namespace(
+    constraint: &NamespaceConstraint,
+    namespace: Option<&str>,
+    in_attribute: bool,
+) -> bool {
+    match *constraint {
+        NamespaceConstraint::Implicit if in_attribute => namespace.is_none(),
+        NamespaceConstraint::Implicit | NamespaceConstraint::Any => true,
+        NamespaceConstraint::None => namespace.is_none(),
+        NamespaceConstraint::Default(ref url) | NamespaceConstraint::Prefixed { ref url, .. } => {
+            namespace == Some(&**url)
+        }
+    }
+}
+
+fn matches_attribute<E: Element>(selector: &AttributeSelector, element: &E) -> bool {
+    element.any_attribute(&selector.local_name, &mut |namespace, value| {
+        if !matches_namespace(&selector.namespace, namespace, true) {
+            return false;
+        }
+        let (operator, expected) = match selector.operation {
+            Some((operator, ref expected)) => (operator, &**expected),
+            None => return true,
+        };
+        let eq = |a: &str, b: &str| match selector.case {
+            AttributeCase::Insensitive => a.eq_ignore_ascii_case(b),
+            AttributeCase::Default | AttributeCase::Sensitive => a == b,
+        };
+        match operator {
+            AttributeOperator::Equal => eq(value, expected),
+            AttributeOperator::Includes => {
+                !expected.is_empty()
+                    && !expected.contains(is_html_whitespace)
+                    && value
+                        .split(is_html_whitespace)
+                        .any(|word| eq(word, expected))
+            }
+            AttributeOperator::DashMatch => {
+                eq(value, expected)
+                    || matches!(
+                        value.get(..expected.len() + 1),
+                        Some(prefix) if prefix.ends_with('-')
+                            && eq(&prefix[..expected.len()], expected)
+                    )
+            }
+            AttributeOperator::Prefix => {
+                !expected.is_empty()
+                    && matches!(value.get(..expected.len()), Some(prefix) if eq(prefix, expected))
+            }
+            AttributeOperator::Suffix => {
+                let start = value.len().wrapping_sub(expected.len());
+                !expected.is_empty()
+                    && matches!(value.get(start..), Some(suffix) if eq(suffix, expected))
+            }
+            AttributeOperator::Substring => {
+                !expected.is_empty()
+                    && match selector.case {
+                        AttributeCase::Insensitive => value
+                            .to_ascii_lowercase()
+                            .contains(&*expected.to_ascii_lowercase()),
+                        AttributeCase::Default | AttributeCase::Sensitive => {
+                            value.contains(expected)
+                        }
+                    }
+            }
+        }
+    })
+}
+
+fn is_html_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0C' | '\r')
+}
+
+fn matches_pseudo_class<E: Element>(
+    pseudo_class: &PseudoClass,
+    element: &E,
+    context: &MatchingContext<E>,
+) -> bool {
+    // Pseudo-elements are not allowed in the arguments of pseudo-classes.
+    let nested = MatchingContext {
+        scope: context.scope.clone(),
+        pseudo_element: None,
+    };
+    match *pseudo_class {
+        PseudoClass::Is(ref list) | PseudoClass::Where(ref list) => list.matches(element, &nested),
+        PseudoClass::Not(ref list) => !list.matches(element, &nested),
+        PseudoClass::Has(ref list) => list
+            .iter()
+            .any(|relative| matches_relative(relative, element, &nested)),
+        PseudoClass::Nth(ref nth) => matches_nth(nth, element, &nested),
+        PseudoClass::FirstChild => element.prev_sibling_element().is_none(),
+        PseudoClass::LastChild => element.next_sibling_element().is_none(),
+        PseudoClass::OnlyChild => {
+            element.prev_sibling_element().is_none() && element.next_sibling_element().is_none()
+        }
+        PseudoClass::FirstOfType => nth_index(element, NthKind::OfType, None, &nested) == 1,
+        PseudoClass::LastOfType => nth_index(element, NthKind::LastOfType, None, &nested) == 1,
+        PseudoClass::OnlyOfType => {
+            nth_index(element, NthKind::OfType, None, &nested) == 1
+                && nth_index(element, NthKind::LastOfType, None, &nested) == 1
+        }
+        PseudoClass::Root => element.parent_element().is_none(),
+        PseudoClass::Empty => element.is_empty(),
+        PseudoClass::Scope => match context.scope {
+            Some(ref scope) => element == scope,
+            None => element.parent_element().is_none(),
+        },
+        PseudoClass::Lang(ref ranges) => {
+            let mut ancestor = Some(element.clone());
+            while let Some(element) = ancestor {
+                if let Some(lang) = element.lang() {
+                    return ranges.iter().any(|range| matches_lang(lang, range));
+                }
+                ancestor = element.parent_element();
+            }
+            false
+        }
+        PseudoClass::Dir(direction) => element.direction() == direction,
+        PseudoClass::State(state) => element.state().contains(state),
+        PseudoClass::Custom {
+            ref name,
+            ref arguments,
+        } => element.matches_custom_pseudo_class(name, arguments.as_deref()),
+    }
+}
+
+/// Whether an element related to `anchor` as required by the relative selector matches it.
+fn matches_relative<E: Element>(
+    relative: &RelativeSelector,
+    anchor: &E,
+    context: &MatchingContext<E>,
+) -> bool {
+    let selector = &relative.selector;
+    let mut matches = |element: &E| {
+        matches_complex(
+            &selector.compounds,
+            &selector.combinators,
+            element,
+            context,
+            Some((anchor, relative.combinator)),
+        )
+    };
+    match relative.combinator {
+        Combinator::Descendant | Combinator::Child => any_descendant(anchor, &mut matches),
+        Combinator::NextSibling | Combinator::SubsequentSibling | Combinator::Column => {
+            let mut sibling = anchor.next_sibling_element();
+            while let Some(element) = sibling {
+                if matches(&element) || any_descendant(&element, &mut matches) {
+                    return true;
+                }
+                sibling = element.next_sibling_element();
+            }
+            false
+        }
+    }
+}
+
+fn any_descendant<E: Element>(element: &E, f: &mut impl FnMut(&E) -> bool) -> bool {
+    let mut child = element.first_child_element();
+    while let Some(element) = child {
+        if f(&element) || any_descendant(&element, f) {
+            return true;
+        }
+        child = element.next_sibling_element();
+    }
+    false
+}
+
+fn matches_nth<E: Element>(nth: &NthSelector, element: &E, context: &MatchingContext<E>) -> bool {
+    if let Some(ref of) = nth.of {
+        if !of.matches(element, context) {
+            return false;
+        }
+    }
+    let index = nth_index(element, nth.kind, nth.of.as_ref(), context);
+    nth.an_plus_b.matches(index)
+}
+
+/// The 1-based index of the element among its siblings that are counted by `kind`,
+/// or that match `of`.
+fn nth_index<E: Element>(
+    element: &E,
+    kind: NthKind,
+    of: Option<&SelectorList>,
+    context: &MatchingContext<E>,
+) -> i32 {
+    let counts = |sibling: &E| match kind {
+        NthKind::Child | NthKind::LastChild => of.iter().all(|of| of.matches(sibling, context)),
+        NthKind::OfType | NthKind::LastOfType => {
+            sibling.local_name() == element.local_name()
+                && sibling.namespace() == element.namespace()
+        }
+    };
+    let next = |sibling: &E| match kind {
+        NthKind::Child | NthKind::OfType => sibling.prev_sibling_element(),
+        NthKind::LastChild | NthKind::LastOfType => sibling.next_sibling_element(),
+    };
+    let mut index = 1i32;
+    let mut sibling = next(element);
+    while let Some(element) = sibling {
+        if counts(&element) {
+            index = index.saturating_add(1);
+        }
+        sibling = next(&element);
+    }
+    index
+}
+
+/// https://www.rfc-editor.org/rfc/rfc4647#section-3.3.2 (extended filtering)
+fn matches_lang(lang: &str, range: &str) -> bool {
+    let mut subtags = lang.split('-');
+    let mut range = range.split('-');
+    let first_matches = match (range.next(), subtags.next()) {
+        (Some("*"), Some(tag)) => !tag.is_empty(),
+        (Some(first), Some(tag)) => first.eq_ignore_ascii_case(tag),
+        _ => false,
+    };
+    first_matches
+        && range.all(|range| {
+            if range == "*" {
+                return true;
+            }
+            loop {
+                match subtags.next() {
+                    Some(tag) if tag.eq_ignore_ascii_case(range) => return true,
+                    Some(tag) if tag.len() == 1 => return false,
+                    Some(_) => continue,
+                    None => return false,
+                }
+            }
+        })
+}
@@ -0,0 +1,822 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/mediaqueries-4/
+
+use std::fmt;
+
+use super::{BasicParseError, Condition, Delimiter, ParseError, Parser, ToCss, Token};
+use crate::serializer::serialize_identifier;
+
+/// A `<media-condition>`.
+pub type MediaCondition = Condition<MediaFeature>;
+
+/// A comma-separated `<media-query-list>`, as found in the prelude of `@media` rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaQueryList {
+    /// The media queries. The list matches if any of them does.
+    pub media_queries: Vec<MediaQuery>,
+}
+
+impl MediaQueryList {
+    /// Parse a media query list.
+    ///
+    /// This never fails: per spec, a media query that fails to parse
+    /// is replaced with `not all`, and other queries in the list are unaffected.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Self {
+        let mut media_queries = Vec::new();
+        // An empty list is valid and matches everything.
+        if input.is_exhausted() {
+            return MediaQueryList { media_queries };
+        }
+        loop {
+            let result: Result<_, ParseError<()>> = input
+                .parse_until_before(Delimiter::Comma, |input| {
+                    MediaQuery::parse(input).map_err(ParseError::from)
+                });
+            media_queries.push(result.unwrap_or_else(|_| MediaQuery::never_matching()));
+            match input.next() {
+                Ok(&Token::Comma) => {}
+                _ => break,
+            }
+        }
+        MediaQueryList { media_queries }
+    }
+
+    /// Whether the list matches the given environment.
+    /// An empty list always matches.
+    pub fn matches<M: MediaEnvironment + ?Sized>(&self, environment: &M) -> bool {
+        self.media_queries.is_empty()
+            || self
+                .media_queries
+                .iter()
+                .any(|query| query.matches(environment))
+    }
+}
+
+impl ToCss for MediaQueryList {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        for (i, query) in self.media_queries.iter().enumerate() {
+            if i > 0 {
+                dest.write_str(", ")?;
+            }
+            query.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `not` or `only` keyword before a media type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaQualifier {
+    /// `only`, which has no effect other than hiding the query from legacy user agents.
+    Only,
+    /// `not`, which negates the whole query.
+    Not,
+}
+
+/// A `<media-type>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MediaType {
+    /// `all`, which is also implied when a query has no media type.
+    All,
+    /// `screen`
+    Screen,
+    /// `print`
+    Print,
+    /// Any other identifier, ASCII-lowercased.
+    /// This includes deprecated media types like `tv`, which never match.
+    Other(String),
+}
+
+impl MediaType {
+    fn from_ident(ident: &str) -> Result<Self, ()> {
+        Ok(match_ignore_ascii_case! { ident,
+            "all" => MediaType::All,
+            "screen" => MediaType::Screen,
+            "print" => MediaType::Print,
+            // https://drafts.csswg.org/mediaqueries-4/#mq-syntax
+            "only" | "not" | "and" | "or" | "layer" => return Err(()),
+            _ => MediaType::Other(ident.to_ascii_lowercase()),
+        })
+    }
+
+    fn name(&self) -> &str {
+        match *self {
+            MediaType::All => "all",
+            MediaType::Screen => "screen",
+            MediaType::Print => "print",
+            MediaType::Other(ref name) => name,
+        }
+    }
+}
+
+impl ToCss for MediaType {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        serialize_identifier(self.name(), dest)
+    }
+}
+
+/// A single `<media-query>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaQuery {
+    /// The `not` or `only` keyword, if any.
+    pub qualifier: Option<MediaQualifier>,
+    /// The media type, `all` if omitted.
+    pub media_type: MediaType,
+    /// The media condition, if any.
+    pub condition: Option<MediaCondition>,
+}
+
+impl MediaQuery {
+    /// The `not all` query that replaces invalid queries in a list.
+    pub fn never_matching() -> Self {
+        MediaQuery {
+            qualifier: Some(MediaQualifier::Not),
+            media_type: MediaType::All,
+            condition: None,
+        }
+    }
+
+    /// Parse a media query:
+    ///
+    /// `<media-condition> | [ not | only ]? <media-type> [ and <media-condition-without-or> ]?`
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let condition: Result<_, ParseError<()>> = input.try_parse(|input| {
+            MediaCondition::parse_with(input, true, &mut |input| {
+                MediaFeature::parse(input).map_err(ParseError::from)
+            })
+        });
+        if let Ok(condition) = condition {
+            return Ok(MediaQuery {
+                qualifier: None,
+                media_type: MediaType::All,
+                condition: Some(condition),
+            });
+        }
+
+        let location = input.current_source_location();
+        let mut ident = input.expect_ident_cloned()?;
+        let qualifier = match_ignore_ascii_case! { &ident,
+            "only" => Some(MediaQualifier::Only),
+            "not" => Some(MediaQualifier::Not),
+            _ => None,
+        };
+        if qualifier.is_some() {
+            ident = input.expect_ident_cloned()?;
+        }
+        let media_type = MediaType::from_ident(&ident)
+            .map_err(|()| location.new_basic_unexpected_token_error(Token::Ident(ident.clone())))?;
+
+        let condition = if input
+            .try_parse(|input| input.expect_ident_matching("and"))
+            .is_ok()
+        {
+            let condition: Result<_, ParseError<()>> =
+                MediaCondition::parse_with(input, false, &mut |input| {
+                    MediaFeature::parse(input).map_err(ParseError::from)
+                });
+            Some(condition.map_err(ParseError::basic)?)
+        } else {
+            None
+        };
+
+        Ok(MediaQuery {
+            qualifier,
+            media_type,
+            condition,
+        })
+    }
+
+    /// Whether this query matches the given environment.
+    ///
+    /// A condition that evaluates to “unknown” (because of `<general-enclosed>`
+    /// or unknown media features) makes the query not match.
+    pub fn matches<M: MediaEnvironment + ?Sized>(&self, environment: &M) -> bool {
+        let type_matches = match self.media_type {
+            MediaType::All => true,
+            ref media_type => media_type
+                .name()
+                .eq_ignore_ascii_case(environment.media_type()),
+        };
+        let result = type_matches
+            && match self.condition {
+                Some(ref condition) => condition
+                    .evaluate(&mut |feature| feature.evaluate(environment))
+                    .unwrap_or(false),
+                None => true,
+            };
+        if self.qualifier == Some(MediaQualifier::Not) {
+            !result
+        } else {
+            result
+        }
+    }
+}
+
+impl ToCss for MediaQuery {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.qualifier {
+            Some(MediaQualifier::Only) => dest.write_str("only ")?,
+            Some(MediaQualifier::Not) => dest.write_str("not ")?,
+            None => {}
+        }
+        let condition = match self.condition {
+            Some(ref condition) => condition,
+            None => return self.media_type.to_css(dest),
+        };
+        // https://drafts.csswg.org/cssom/#serialize-a-media-query
+        if self.qualifier.is_some() || self.media_type != MediaType::All {
+            self.media_type.to_css(dest)?;
+            dest.write_str(" and ")?;
+        }
+        condition.to_css(dest)
+    }
+}
+
+/// A comparison operator in a media feature range context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// `<`
+    LessThan,
+    /// `<=`
+    LessThanOrEqual,
+    /// `>`
+    GreaterThan,
+    /// `>=`
+    GreaterThanOrEqual,
+    /// `=`
+    Equal,
+}
+
+impl Comparison {
+    fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let first = match *input.next()? {
+            Token::Delim(c @ '<') | Token::Delim(c @ '>') => c,
+            Token::Delim('=') => return Ok(Comparison::Equal),
+            ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+        };
+        // `<=` and `>=` are two tokens, without whitespace in between.
+        let or_equal = input
+            .try_parse(|input| match *input.next_including_whitespace()? {
+                Token::Delim('=') => Ok(()),
+                ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+            })
+            .is_ok();
+        Ok(match (first, or_equal) {
+            ('<', false) => Comparison::LessThan,
+            ('<', true) => Comparison::LessThanOrEqual,
+            ('>', false) => Comparison::GreaterThan,
+            _ => Comparison::GreaterThanOrEqual,
+        })
+    }
+
+    fn is_less(self) -> bool {
+        matches!(self, Comparison::LessThan | Comparison::LessThanOrEqual)
+    }
+
+    fn is_greater(self) -> bool {
+        matches!(
+            self,
+            Comparison::GreaterThan | Comparison::GreaterThanOrEqual
+        )
+    }
+
+    /// Swap the operands: `a < b` is the same as `b > a`.
+    fn reversed(self) -> Self {
+        match self {
+            Comparison::LessThan => Comparison::GreaterThan,
+            Comparison::LessThanOrEqual => Comparison::GreaterThanOrEqual,
+            Comparison::GreaterThan => Comparison::LessThan,
+            Comparison::GreaterThanOrEqual => Comparison::LessThanOrEqual,
+            Comparison::Equal => Comparison::Equal,
+        }
+    }
+
+    fn compare(self, left: f32, right: f32) -> bool {
+        match self {
+            Comparison::LessThan => left < right,
+            Comparison::LessThanOrEqual => left <= right,
+            Comparison::GreaterThan => left > right,
+            Comparison::GreaterThanOrEqual => left >= right,
+            Comparison::Equal => left == right,
+        }
+    }
+}
+
+impl ToCss for Comparison {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str(match *self {
+            Comparison::LessThan => "<",
+            Comparison::LessThanOrEqual => "<=",
+            Comparison::GreaterThan => ">",
+            Comparison::GreaterThanOrEqual => ">=",
+            Comparison::Equal => "=",
+        })
+    }
+}
+
+/// An `<mf-value>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MediaFeatureValue {
+    /// A `<number>` or `<integer>`.
+    Number(f32),
+    /// A `<dimension>`, such as a `<length>` or a `<resolution>`.
+    Dimension {
+        /// The numeric value.
+        value: f32,
+        /// The unit, ASCII-lowercased.
+        unit: String,
+    },
+    /// A `<ratio>`, such as `16 / 9`.
+    Ratio(f32, f32),
+    /// An `<ident>`, ASCII-lowercased.
+    Ident(String),
+}
+
+impl MediaFeatureValue {
+    /// Parse an `<mf-value>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let value = match *input.next()? {
+            Token::Number { value, .. } => value,
+            Token::Dimension {
+                value, ref unit, ..
+            } => {
+                return Ok(MediaFeatureValue::Dimension {
+                    value,
+                    unit: unit.to_ascii_lowercase(),
+                })
+            }
+            Token::Ident(ref ident) => {
+                return Ok(MediaFeatureValue::Ident(ident.to_ascii_lowercase()))
+            }
+            ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+        };
+        let denominator = input.try_parse(|input| {
+            input.expect_delim('/')?;
+            input.expect_number()
+        });
+        Ok(match denominator {
+            Ok(denominator) if value >= 0. && denominator >= 0. => {
+                MediaFeatureValue::Ratio(value, denominator)
+            }
+            Ok(_) => return Err(location.new_basic_unexpected_token_error(Token::Delim('/'))),
+            Err(_) => MediaFeatureValue::Number(value),
+        })
+    }
+}
+
+impl ToCss for MediaFeatureValue {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            MediaFeatureValue::Number(value) => value.to_css(dest),
+            MediaFeatureValue::Dimension { value, ref unit } => {
+                value.to_css(dest)?;
+                serialize_identifier(unit, dest)
+            }
+            MediaFeatureValue::Ratio(numerator, denominator) => {
+                numerator.to_css(dest)?;
+                dest.write_str(" / ")?;
+                denominator.to_css(dest)
+            }
+            MediaFeatureValue::Ident(ref ident) => serialize_identifier(ident, dest),
+        }
+    }
+}
+
+/// A `<media-feature>`, including its parentheses.
+///
+/// Feature names are ASCII-lowercased, and may have a `min-` or `max-` prefix
+/// in the plain form. Unknown feature names are accepted by the parser
+/// and evaluate to “unknown”.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MediaFeature {
+    /// `(<mf-name>)`, which tests whether the feature’s value is not zero or `none`.
+    Boolean(String),
+    /// `(<mf-name>: <mf-value>)`
+    Plain {
+        /// The feature name, possibly with a `min-` or `max-` prefix.
+        name: String,
+        /// The value.
+        value: MediaFeatureValue,
+    },
+    /// `(<mf-value> <op> <mf-name> <op> <mf-value>)`,
+    /// or a single comparison with one of `start` and `end` omitted.
+    Range {
+        /// The feature name.
+        name: String,
+        /// A value and operator before the name, if any.
+        start: Option<(MediaFeatureValue, Comparison)>,
+        /// An operator and value after the name, if any.
+        end: Option<(Comparison, MediaFeatureValue)>,
+    },
+}
+
+impl MediaFeature {
+    /// Parse a `<media-feature>`, including its parentheses.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        input.expect_parenthesis_block()?;
+        let result: Result<_, ParseError<()>> =
+            input.parse_nested_block(|input| Ok(Self::parse_inside_parens(input)?));
+        result.map_err(ParseError::basic)
+    }
+
+    /// Parse the contents of a `<mf-plain>`, `<mf-boolean>` or `<mf-range>`,
+    /// within the parentheses.
+    pub fn parse_inside_parens<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, BasicParseError<'i>> {
+        let name_first = input.try_parse(|input| -> Result<_, BasicParseError<'i>> {
+            let name = input.expect_ident()?.to_ascii_lowercase();
+            let feature = if input.is_exhausted() {
+                MediaFeature::Boolean(name)
+            } else if input.try_parse(|input| input.expect_colon()).is_ok() {
+                let value = MediaFeatureValue::parse(input)?;
+                MediaFeature::Plain { name, value }
+            } else {
+                let comparison = Comparison::parse(input)?;
+                let value = MediaFeatureValue::parse(input)?;
+                MediaFeature::Range {
+                    name,
+                    start: None,
+                    end: Some((comparison, value)),
+                }
+            };
+            // Otherwise, try again from the start with a value first.
+            input.expect_exhausted()?;
+            Ok(feature)
+        });
+        if let Ok(feature) = name_first {
+            return Ok(feature);
+        }
+
+        let start_value = MediaFeatureValue::parse(input)?;
+        let start_comparison = Comparison::parse(input)?;
+        let name = input.expect_ident()?.to_ascii_lowercase();
+        let location = input.current_source_location();
+        let end = if input.is_exhausted() {
+            None
+        } else {
+            let end_comparison = Comparison::parse(input)?;
+            // Both comparisons must go in the same direction.
+            let consistent = (start_comparison.is_less() && end_comparison.is_less())
+                || (start_comparison.is_greater() && end_comparison.is_greater());
+            if !consistent {
+                return Err(location.new_basic_unexpected_token_error(Token::Ident(name.into())));
+            }
+            Some((end_comparison, MediaFeatureValue::parse(input)?))
+        };
+        Ok(MediaFeature::Range {
+            name,
+            start: Some((start_value, start_comparison)),
+            end,
+        })
+    }
+
+    /// The feature name, including any `min-` or `max-` prefix.
+    pub fn name(&self) -> &str {
+        match *self {
+            MediaFeature::Boolean(ref name)
+            | MediaFeature::Plain { ref name, .. }
+            | MediaFeature::Range { ref name, .. } => name,
+        }
+    }
+
+    /// Serialize this feature without the surrounding parentheses.
+    pub fn to_css_inside_parens<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            MediaFeature::Boolean(ref name) => serialize_identifier(name, dest)?,
+            MediaFeature::Plain {
+                ref name,
+                ref value,
+            } => {
+                serialize_identifier(name, dest)?;
+                dest.write_str(": ")?;
+                value.to_css(dest)?;
+            }
+            MediaFeature::Range {
+                ref name,
+                ref start,
+                ref end,
+            } => {
+                if let Some((ref value, comparison)) = *start {
+                    value.to_css(dest)?;
+                    dest.write_char(' ')?;
+                    comparison.to_css(dest)?;
+                    dest.write_char(' ')?;
+                }
+                serialize_identifier(name, dest)?;
+                if let Some((comparison, ref value)) = *end {
+                    dest.write_char(' ')?;
+                    comparison.to_css(dest)?;
+                    dest.write_char(' ')?;
+                    value.to_css(dest)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate this feature in the given environment.
+    ///
+    /// Returns `None` (“unknown”) for unknown features,
+    /// and for values of the wrong type.
+    pub fn evaluate<M: MediaEnvironment + ?Sized>(&self, environment: &M) -> Option<bool> {
+        self.evaluate_with(environment.font_size(), &mut |name| {
+            environment_value(environment, name)
+        })
+    }
+
+    /// Evaluate this feature given a function that returns the value of a feature by name
+    /// (without `min-` or `max-` prefix), and the font size used for font-relative lengths.
+    ///
+    /// This is shared with container queries, which have different features.
+    pub(crate) fn evaluate_with<F>(&self, font_size: f32, feature_value: &mut F) -> Option<bool>
+    where
+        F: FnMut(&str) -> Option<FeatureValue>,
+    {
+        match *self {
+            MediaFeature::Boolean(ref name) => Some(match feature_value(name)? {
+                FeatureValue::Length(value)
+                | FeatureValue::Resolution(value)
+                | FeatureValue::Integer(value) => value != 0.,
+                FeatureValue::Ratio(numerator, _) => numerator != 0.,
+                FeatureValue::Ident(ref ident) => ident != "none" && ident != "no-preference",
+            }),
+            MediaFeature::Plain {
+                ref name,
+                ref value,
+            } => {
+                let (name, comparison) = if let Some(name) = name.strip_prefix("min-") {
+                    (name, Comparison::GreaterThanOrEqual)
+                } else if let Some(name) = name.strip_prefix("max-") {
+                    (name, Comparison::LessThanOrEqual)
+                } else {
+                    (&**name, Comparison::Equal)
+                };
+                let actual = feature_value(name)?;
+                if let FeatureValue::Ident(ref actual) = actual {
+                    return match *value {
+                        MediaFeatureValue::Ident(ref expected)
+                            if comparison == Comparison::Equal =>
+                        {
+                            Some(actual == expected)
+                        }
+                        _ => None,
+                    };
+                }
+                let (actual, expected) = actual.comparable_with(value, font_size)?;
+                Some(comparison.compare(actual, expected))
+            }
+            MediaFeature::Range {
+                ref name,
+                ref start,
+                ref end,
+            } => {
+                let actual = feature_value(name)?;
+                if let Some((ref value, comparison)) = *start {
+                    let (actual, expected) = actual.comparable_with(value, font_size)?;
+                    if !comparison.reversed().compare(actual, expected) {
+                        return Some(false);
+                    }
+                }
+                if let Some((comparison, ref value)) = *end {
+                    let (actual, expected) = actual.comparable_with(value, font_size)?;
+                    if !comparison.compare(actual, expected) {
+                        return Some(false);
+                    }
+                }
+                Some(true)
+            }
+        }
+    }
+}
+
+impl ToCss for MediaFeature {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_char('(')?;
+        self.to_css_inside_parens(dest)?;
+        dest.write_char(')')
+    }
+}
+
+/// The actual value of a feature in an environment, used for evaluation.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum FeatureValue {
+    /// A length in CSS pixels.
+    Length(f32),
+    /// A resolution in dots per CSS pixel.
+    Resolution(f32),
+    /// A ratio.
+    Ratio(f32, f32),
+    /// An integer.
+    Integer(f32),
+    /// A keyword, ASCII-lowercased.
+    Ident(String),
+}
+
+impl FeatureValue {
+    /// Convert `value` to the type of `self`, and return both as numbers that can be compared.
+    fn comparable_with(&self, value: &MediaFeatureValue, font_size: f32) -> Option<(f32, f32)> {
+        Some(match (self, value) {
+            (FeatureValue::Length(actual), MediaFeatureValue::Number(value)) => {
+                // Only unitless zero is a valid `<length>`.
+                if *value != 0. {
+                    return None;
+                }
+                (*actual, 0.)
+            }
+            (FeatureValue::Length(actual), MediaFeatureValue::Dimension { value, unit }) => {
+                (*actual, value * length_unit_in_px(unit, font_size)?)
+            }
+            (FeatureValue::Resolution(actual), MediaFeatureValue::Dimension { value, unit }) => {
+                let dppx = match_ignore_ascii_case! { unit,
+                    "dppx" | "x" => 1.,
+                    "dpi" => 1. / 96.,
+                    "dpcm" => 2.54 / 96.,
+                    _ => return None,
+                };
+                (*actual, value * dppx)
+            }
+            (FeatureValue::Resolution(actual), MediaFeatureValue::Ident(ident))
+                if ident == "infinite" =>
+            {
+                (*actual, f32::INFINITY)
+            }
+            (FeatureValue::Ratio(n, d), MediaFeatureValue::Ratio(value_n, value_d)) => {
+                (ratio_value(*n, *d), ratio_value(*value_n, *value_d))
+            }
+            (FeatureValue::Ratio(n, d), MediaFeatureValue::Number(value)) => {
+                (ratio_value(*n, *d), *value)
+            }
+            (FeatureValue::Integer(actual), MediaFeatureValue::Number(value)) => (*actual, *value),
+            _ => return None,
+        })
+    }
+}
+
+/// A ratio as a single number, where `0 / 0` is treated as zero
+/// and `n / 0` as infinity.
+fn ratio_value(numerator: f32, denominator: f32) -> f32 {
+    if numerator == 0. {
+        0.
+    } else if denominator == 0. {
+        f32::INFINITY
+    } else {
+        numerator / denominator
+    }
+}
+
+/// The number of CSS pixels in one of the given length unit,
+/// for units that don’t depend on a viewport or container.
+pub(crate) fn length_unit_in_px(unit: &str, font_size: f32) -> Option<f32> {
+    Some(match_ignore_ascii_case! { unit,
+        "px" => 1.,
+        "in" => 96.,
+        "cm" => 96. / 2.54,
+        "mm" => 96. / 25.4,
+        "q" => 96. / 101.6,
+        "pt" => 96. / 72.,
+        "pc" => 16.,
+        "em" | "rem" => font_size,
+        "ex" | "ch" => font_size / 2.,
+        _ => return None,
+    })
+}
+
+fn environment_value<M: MediaEnvironment + ?Sized>(
+    environment: &M,
+    name: &str,
+) -> Option<FeatureValue> {
+    let (width, height) = (environment.viewport_width(), environment.viewport_height());
+    Some(match_ignore_ascii_case! { name,
+        "width" => FeatureValue::Length(width),
+        "height" => FeatureValue::Length(height),
+        "device-width" => FeatureValue::Length(environment.device_width()),
+        "device-height" => FeatureValue::Length(environment.device_height()),
+        "aspect-ratio" => FeatureValue::Ratio(width, height),
+        "device-aspect-ratio" => {
+            FeatureValue::Ratio(environment.device_width(), environment.device_height())
+        },
+        "orientation" => FeatureValue::Ident(
+            if height >= width { "portrait" } else { "landscape" }.to_owned()
+        ),
+        "resolution" => FeatureValue::Resolution(environment.resolution()),
+        "color" => FeatureValue::Integer(environment.color_bits() as f32),
+        "color-index" => FeatureValue::Integer(environment.color_index() as f32),
+        "monochrome" => FeatureValue::Integer(environment.monochrome_bits() as f32),
+        "grid" => FeatureValue::Integer(if environment.grid() { 1. } else { 0. }),
+        "prefers-color-scheme" => {
+            FeatureValue::Ident(environment.prefers_color_scheme().to_ascii_lowercase())
+        },
+        _ => FeatureValue::Ident(environment.discrete_feature(name)?.to_ascii_lowercase()),
+    })
+}
+
+/// The environment that media queries are evaluated against.
+///
+/// Lengths are in CSS pixels.
+/// Default implementations are provided for everything but the viewport size,
+/// and describe a typical desktop screen.
+pub trait MediaEnvironment {
+    /// The media type, such as `screen` or `print`.
+    fn media_type(&self) -> &str {
+        "screen"
+    }
+
+    /// The width of the viewport.
+    fn viewport_width(&self) -> f32;
+
+    /// The height of the viewport.
+    fn viewport_height(&self) -> f32;
+
+    /// The width of the output device, for the deprecated `device-width` feature.
+    fn device_width(&self) -> f32 {
+        self.viewport_width()
+    }
+
+    /// The height of the output device, for the deprecated `device-height` feature.
+    fn device_height(&self) -> f32 {
+        self.viewport_height()
+    }
+
+    /// The resolution, in dots per CSS pixel.
+    fn resolution(&self) -> f32 {
+        1.
+    }
+
+    /// The initial font size, used for `em` and other font-relative units.
+    fn font_size(&self) -> f32 {
+        16.
+    }
+
+    /// The number of bits per color component.
+    fn color_bits(&self) -> u32 {
+        8
+    }
+
+    /// The number of entries in the color lookup table.
+    fn color_index(&self) -> u32 {
+        0
+    }
+
+    /// The number of bits per pixel of a monochrome device, or zero.
+    fn monochrome_bits(&self) -> u32 {
+        0
+    }
+
+    /// Whether the device is grid-based, like a terminal.
+    fn grid(&self) -> bool {
+        false
+    }
+
+    /// The value of `prefers-color-scheme`: `light` or `dark`.
+    fn prefers_color_scheme(&self) -> &str {
+        "light"
+    }
+
+    /// The keyword value of other discrete features,
+    /// such as `hover`, `pointer` or `prefers-reduced-motion`,
+    /// or `None` for unsupported features.
+    fn discrete_feature(&self, name: &str) -> Option<&str> {
+        Some(match_ignore_ascii_case! { name,
+            "hover" | "any-hover" => "hover",
+            "pointer" | "any-pointer" => "fine",
+            "scan" => "progressive",
+            "update" => "fast",
+            "overflow-block" | "overflow-inline" => "scroll",
+            "color-gamut" => "srgb",
+            "dynamic-range" | "video-dynamic-range" => "standard",
+            "forced-colors" | "inverted-colors" => "none",
+            "prefers-reduced-motion" | "prefers-reduced-transparency" => "no-preference",
+            "prefers-reduced-data" | "prefers-contrast" => "no-preference",
+            "scripting" => "enabled",
+            "display-mode" => "browser",
+            _ => return None,
+        })
+    }
+}
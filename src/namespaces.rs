@@ -0,0 +1,162 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-namespaces-3/
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{BasicParseError, Parser, ToCss};
+use crate::serializer::{serialize_identifier, serialize_string};
+
+/// The prelude of a `@namespace` rule: `<namespace-prefix>? [ <string> | <url> ]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamespaceRule {
+    /// The prefix, which is case-sensitive, or `None` for the default namespace.
+    pub prefix: Option<String>,
+    /// The namespace URL, as written. It is not resolved against the stylesheet URL.
+    pub url: String,
+}
+
+impl NamespaceRule {
+    /// Parse the prelude of a `@namespace` rule, after the at-keyword.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let prefix = input
+            .try_parse(|input| input.expect_ident_cloned())
+            .ok()
+            .map(|prefix| prefix.as_ref().to_owned());
+        let url = input.expect_url_or_string()?.as_ref().to_owned();
+        input.expect_exhausted()?;
+        Ok(NamespaceRule { prefix, url })
+    }
+}
+
+impl ToCss for NamespaceRule {
+    /// Serialize the prelude, without the at-keyword.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if let Some(ref prefix) = self.prefix {
+            serialize_identifier(prefix, dest)?;
+            dest.write_char(' ')?;
+        }
+        dest.write_str("url(")?;
+        serialize_string(&self.url, dest)?;
+        dest.write_char(')')
+    }
+}
+
+/// The namespaces declared by the `@namespace` rules of a stylesheet,
+/// for resolving namespace prefixes in selectors.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceMap {
+    default: Option<String>,
+    prefixes: HashMap<String, String>,
+}
+
+impl NamespaceMap {
+    /// Create a map without any namespace.
+    pub fn new() -> Self {
+        NamespaceMap::default()
+    }
+
+    /// Add the namespace of a valid `@namespace` rule.
+    /// A later rule with the same prefix, or for the default namespace, replaces earlier ones.
+    pub fn insert(&mut self, rule: NamespaceRule) {
+        match rule.prefix {
+            Some(prefix) => {
+                self.prefixes.insert(prefix, rule.url);
+            }
+            None => self.default = Some(rule.url),
+        }
+    }
+
+    /// The default namespace, which applies to type selectors without a prefix.
+    pub fn default_namespace(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// The namespace URL of a prefix, or `None` if it is not declared,
+    /// which makes selectors that use it invalid.
+    pub fn prefix(&self, prefix: &str) -> Option<&str> {
+        self.prefixes.get(prefix).map(String::as_str)
+    }
+}
+
+/// The kinds of top-level rules that matter for `TopLevelRuleOrder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopLevelRuleKind {
+    /// `@charset`
+    Charset,
+    /// `@import`
+    Import,
+    /// `@layer` without a block.
+    LayerStatement,
+    /// `@namespace`
+    Namespace,
+    /// Any other rule, including style rules.
+    Other,
+}
+
+/// Enforces the order of the top-level rules of a stylesheet:
+/// `@charset`, then `@import` rules, then `@namespace` rules, then other rules,
+/// with `@layer` statements also allowed before `@import` rules.
+///
+/// Call `accept` for each top-level rule, in order. Rules it rejects must be ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TopLevelRuleOrder {
+    /// No rule has been accepted yet, or only `@charset`.
+    Start,
+    /// `@import` rules and `@layer` statements are accepted.
+    Imports,
+    /// `@namespace` rules are accepted.
+    Namespaces,
+    /// Only other rules are accepted.
+    Body,
+}
+
+impl Default for TopLevelRuleOrder {
+    fn default() -> Self {
+        TopLevelRuleOrder::new()
+    }
+}
+
+impl TopLevelRuleOrder {
+    /// The position before the first rule of a stylesheet.
+    pub fn new() -> Self {
+        TopLevelRuleOrder::Start
+    }
+
+    /// Return whether a rule of the given kind is valid at this position,
+    /// and move past it if it is.
+    pub fn accept(&mut self, kind: TopLevelRuleKind) -> bool {
+        let (valid, next) = match kind {
+            TopLevelRuleKind::Charset => (*self == TopLevelRuleOrder::Start, *self),
+            TopLevelRuleKind::Import => (
+                *self <= TopLevelRuleOrder::Imports,
+                TopLevelRuleOrder::Imports,
+            ),
+            TopLevelRuleKind::Namespace => (
+                *self <= TopLevelRuleOrder::Namespaces,
+                TopLevelRuleOrder::Namespaces,
+            ),
+            TopLevelRuleKind::LayerStatement if *self <= TopLevelRuleOrder::Imports => {
+                (true, TopLevelRuleOrder::Imports)
+            }
+            TopLevelRuleKind::LayerStatement | TopLevelRuleKind::Other => {
+                (true, TopLevelRuleOrder::Body)
+            }
+        };
+        if valid {
+            *self = next;
+        }
+        valid
+    }
+
+    /// Whether a `@namespace` rule would be accepted at this position.
+    pub fn allows_namespace_rules(&self) -> bool {
+        *self <= TopLevelRuleOrder::Namespaces
+    }
+}
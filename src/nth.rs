@@ -2,9 +2,91 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use super::{BasicParseError, Parser, ParserInput, Token};
+use std::fmt;
+
+use super::{BasicParseError, Parser, ParserInput, ToCss, Token};
 use matches::matches;
 
+/// The *An+B* notation: https://drafts.csswg.org/css-syntax/#anb-microsyntax
+///
+/// Values out of the `i32` range saturate, like the integers of numeric tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AnPlusB {
+    /// The step, *A*.
+    pub a: i32,
+    /// The offset, *B*.
+    pub b: i32,
+}
+
+impl AnPlusB {
+    /// Create an *An+B* value.
+    pub fn new(a: i32, b: i32) -> Self {
+        AnPlusB { a, b }
+    }
+
+    /// Parse the *An+B* notation. See `parse_nth`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let (a, b) = parse_nth(input)?;
+        Ok(AnPlusB { a, b })
+    }
+
+    /// Parse `<an+b> [ of <rest> ]?`, as in `:nth-child(2n+1 of .important)`,
+    /// calling `parse_rest` for what follows `of`.
+    /// As with `parse_nth`, the caller needs to check if the parser is exhausted.
+    pub fn parse_with_of<'i, 't, F, T>(
+        input: &mut Parser<'i, 't>,
+        parse_rest: F,
+    ) -> Result<(Self, Option<T>), BasicParseError<'i>>
+    where
+        F: FnOnce(&mut Parser<'i, 't>) -> Result<T, BasicParseError<'i>>,
+    {
+        let an_plus_b = AnPlusB::parse(input)?;
+        if input
+            .try_parse(|input| input.expect_ident_matching("of"))
+            .is_err()
+        {
+            return Ok((an_plus_b, None));
+        }
+        Ok((an_plus_b, Some(parse_rest(input)?)))
+    }
+
+    /// Whether the 1-based `index` is `A*n + B` for some integer `n >= 0`.
+    pub fn matches(&self, index: i32) -> bool {
+        // Computed in i64 so that no intermediate value overflows.
+        let a = i64::from(self.a);
+        let offset = i64::from(index) - i64::from(self.b);
+        if a == 0 {
+            offset == 0
+        } else {
+            offset % a == 0 && offset / a >= 0
+        }
+    }
+}
+
+impl ToCss for AnPlusB {
+    /// https://drafts.csswg.org/css-syntax/#serializing-anb
+    ///
+    /// This is the shortest form with an explicit sign: `odd` serializes as `2n+1`.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.a {
+            0 => return write!(dest, "{}", self.b),
+            1 => dest.write_char('n')?,
+            -1 => dest.write_str("-n")?,
+            a => write!(dest, "{}n", a)?,
+        }
+        if self.b > 0 {
+            dest.write_char('+')?;
+        }
+        if self.b != 0 {
+            write!(dest, "{}", self.b)?;
+        }
+        Ok(())
+    }
+}
+
 /// Parse the *An+B* notation, as found in the `:nth-child()` selector.
 /// The input is typically the arguments of a function,
 /// in which case the caller needs to check if the arguments’ parser is exhausted.
@@ -104,13 +186,23 @@ fn parse_signless_b<'i, 't>(
     a: i32,
     b_sign: i32,
 ) -> Result<(i32, i32), BasicParseError<'i>> {
+    input.skip_whitespace();
+    let start = input.position();
     // FIXME: remove .clone() when lifetimes are non-lexical.
     match input.next()?.clone() {
         Token::Number {
             has_sign: false,
             int_value: Some(b),
             ..
-        } => Ok((a, b_sign * b)),
+        } => {
+            if b_sign < 0 {
+                // Saturate the signed value, like `n-3000000000` does.
+                let digits = input.slice_from(start);
+                Ok((a, parse_number_saturate(&format!("-{}", digits)).unwrap()))
+            } else {
+                Ok((a, b))
+            }
+        }
         token => Err(input.new_basic_unexpected_token_error(token)),
     }
 }
@@ -0,0 +1,366 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-page-3/#at-page-rule
+
+use std::fmt;
+
+use super::{
+    AtRuleParser, AtRuleType, BasicParseError, BasicParseErrorKind, CowRcStr,
+    DeclarationListParser, DeclarationParser, ParseError, Parser, ParserState, ToCss, Token,
+};
+use crate::keyframes::DeclarationsOnly;
+use crate::serializer::serialize_identifier;
+
+/// A page pseudo-class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PagePseudoClass {
+    /// `:first`
+    First,
+    /// `:left`
+    Left,
+    /// `:right`
+    Right,
+    /// `:blank`
+    Blank,
+}
+
+impl ToCss for PagePseudoClass {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str(match *self {
+            PagePseudoClass::First => ":first",
+            PagePseudoClass::Left => ":left",
+            PagePseudoClass::Right => ":right",
+            PagePseudoClass::Blank => ":blank",
+        })
+    }
+}
+
+/// A `<page-selector>`, such as `chapter:first:left`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PageSelector {
+    /// The page type name, which is case-sensitive.
+    pub name: Option<String>,
+    /// The pseudo-classes, in order.
+    pub pseudo_classes: Vec<PagePseudoClass>,
+}
+
+impl PageSelector {
+    /// Parse a `<page-selector>`. No whitespace is allowed between its components.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let mut selector = PageSelector::default();
+        let location = input.current_source_location();
+        match *input.next()? {
+            Token::Ident(ref name) => selector.name = Some(name.as_ref().to_owned()),
+            Token::Colon => selector.pseudo_classes.push(parse_pseudo_class(input)?),
+            ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+        }
+        loop {
+            let state = input.state();
+            match input.next_including_whitespace() {
+                Ok(&Token::Colon) => selector.pseudo_classes.push(parse_pseudo_class(input)?),
+                _ => {
+                    input.reset(&state);
+                    return Ok(selector);
+                }
+            }
+        }
+    }
+
+    /// Parse a `<page-selector-list>`, the prelude of a `@page` rule after the at-keyword.
+    ///
+    /// The list is empty if the input is.
+    pub fn parse_list<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Vec<Self>, BasicParseError<'i>> {
+        if input.is_exhausted() {
+            return Ok(Vec::new());
+        }
+        let result: Result<_, ParseError<()>> =
+            input.parse_comma_separated(|input| Ok(Self::parse(input)?));
+        result.map_err(ParseError::basic)
+    }
+
+    /// The specificity of this selector, as (page type names, `:first` and `:blank`,
+    /// `:left` and `:right`), to be compared lexicographically.
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        let mut specificity = (self.name.is_some() as u32, 0, 0);
+        for pseudo_class in &self.pseudo_classes {
+            match *pseudo_class {
+                PagePseudoClass::First | PagePseudoClass::Blank => specificity.1 += 1,
+                PagePseudoClass::Left | PagePseudoClass::Right => specificity.2 += 1,
+            }
+        }
+        specificity
+    }
+}
+
+fn parse_pseudo_class<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<PagePseudoClass, BasicParseError<'i>> {
+    let location = input.current_source_location();
+    match *input.next_including_whitespace()? {
+        Token::Ident(ref name) => Ok(match_ignore_ascii_case! { name,
+            "first" => PagePseudoClass::First,
+            "left" => PagePseudoClass::Left,
+            "right" => PagePseudoClass::Right,
+            "blank" => PagePseudoClass::Blank,
+            _ => return Err(location.new_basic_unexpected_token_error(Token::Ident(name.clone()))),
+        }),
+        ref token => Err(location.new_basic_unexpected_token_error(token.clone())),
+    }
+}
+
+impl ToCss for PageSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if let Some(ref name) = self.name {
+            serialize_identifier(name, dest)?;
+        }
+        for pseudo_class in &self.pseudo_classes {
+            pseudo_class.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// One of the sixteen page-margin boxes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PageMarginBox {
+    /// `@top-left-corner`
+    TopLeftCorner,
+    /// `@top-left`
+    TopLeft,
+    /// `@top-center`
+    TopCenter,
+    /// `@top-right`
+    TopRight,
+    /// `@top-right-corner`
+    TopRightCorner,
+    /// `@bottom-left-corner`
+    BottomLeftCorner,
+    /// `@bottom-left`
+    BottomLeft,
+    /// `@bottom-center`
+    BottomCenter,
+    /// `@bottom-right`
+    BottomRight,
+    /// `@bottom-right-corner`
+    BottomRightCorner,
+    /// `@left-top`
+    LeftTop,
+    /// `@left-middle`
+    LeftMiddle,
+    /// `@left-bottom`
+    LeftBottom,
+    /// `@right-top`
+    RightTop,
+    /// `@right-middle`
+    RightMiddle,
+    /// `@right-bottom`
+    RightBottom,
+}
+
+impl PageMarginBox {
+    /// The margin box for an at-keyword name (without `@`), ASCII case-insensitively.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match_ignore_ascii_case! { name,
+            "top-left-corner" => PageMarginBox::TopLeftCorner,
+            "top-left" => PageMarginBox::TopLeft,
+            "top-center" => PageMarginBox::TopCenter,
+            "top-right" => PageMarginBox::TopRight,
+            "top-right-corner" => PageMarginBox::TopRightCorner,
+            "bottom-left-corner" => PageMarginBox::BottomLeftCorner,
+            "bottom-left" => PageMarginBox::BottomLeft,
+            "bottom-center" => PageMarginBox::BottomCenter,
+            "bottom-right" => PageMarginBox::BottomRight,
+            "bottom-right-corner" => PageMarginBox::BottomRightCorner,
+            "left-top" => PageMarginBox::LeftTop,
+            "left-middle" => PageMarginBox::LeftMiddle,
+            "left-bottom" => PageMarginBox::LeftBottom,
+            "right-top" => PageMarginBox::RightTop,
+            "right-middle" => PageMarginBox::RightMiddle,
+            "right-bottom" => PageMarginBox::RightBottom,
+            _ => return None,
+        })
+    }
+
+    /// The at-keyword name, without `@`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PageMarginBox::TopLeftCorner => "top-left-corner",
+            PageMarginBox::TopLeft => "top-left",
+            PageMarginBox::TopCenter => "top-center",
+            PageMarginBox::TopRight => "top-right",
+            PageMarginBox::TopRightCorner => "top-right-corner",
+            PageMarginBox::BottomLeftCorner => "bottom-left-corner",
+            PageMarginBox::BottomLeft => "bottom-left",
+            PageMarginBox::BottomCenter => "bottom-center",
+            PageMarginBox::BottomRight => "bottom-right",
+            PageMarginBox::BottomRightCorner => "bottom-right-corner",
+            PageMarginBox::LeftTop => "left-top",
+            PageMarginBox::LeftMiddle => "left-middle",
+            PageMarginBox::LeftBottom => "left-bottom",
+            PageMarginBox::RightTop => "right-top",
+            PageMarginBox::RightMiddle => "right-middle",
+            PageMarginBox::RightBottom => "right-bottom",
+        }
+    }
+}
+
+/// A page-margin rule such as `@top-center { content: "Title" }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageMarginRule<D> {
+    /// The margin box this rule applies to.
+    pub margin_box: PageMarginBox,
+    /// The valid declarations, in order.
+    pub declarations: Vec<D>,
+}
+
+/// A `@page` rule.
+///
+/// `ToCss` serializes the whole rule, and requires the declarations to serialize
+/// as `name: value`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageRule<D> {
+    /// The page selectors. The rule applies to all pages if this is empty.
+    pub selectors: Vec<PageSelector>,
+    /// The valid declarations, in order.
+    pub declarations: Vec<D>,
+    /// The page-margin rules, in order.
+    pub margin_rules: Vec<PageMarginRule<D>>,
+}
+
+impl<D> PageRule<D> {
+    /// Parse the block of a `@page` rule with the given declaration parser,
+    /// which is used for both the page context and the page-margin rules.
+    ///
+    /// Invalid declarations, unknown at-rules and page-margin rules with a prelude
+    /// are dropped.
+    pub fn parse_block<'i, 't, P>(
+        selectors: Vec<PageSelector>,
+        input: &mut Parser<'i, 't>,
+        declaration_parser: &mut P,
+    ) -> Self
+    where
+        P: DeclarationParser<'i, Declaration = D>,
+    {
+        let mut rule = PageRule {
+            selectors,
+            declarations: Vec::new(),
+            margin_rules: Vec::new(),
+        };
+        for item in DeclarationListParser::new(input, PageBodyParser(declaration_parser)).flatten()
+        {
+            match item {
+                PageBodyItem::Declaration(declaration) => rule.declarations.push(declaration),
+                PageBodyItem::MarginRule(margin_rule) => rule.margin_rules.push(margin_rule),
+            }
+        }
+        rule
+    }
+}
+
+fn write_declaration_block<D: ToCss, W: fmt::Write>(
+    declarations: &[D],
+    dest: &mut W,
+) -> fmt::Result {
+    for declaration in declarations {
+        dest.write_char(' ')?;
+        declaration.to_css(dest)?;
+        dest.write_char(';')?;
+    }
+    Ok(())
+}
+
+impl<D: ToCss> ToCss for PageRule<D> {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("@page ")?;
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                dest.write_str(", ")?;
+            }
+            selector.to_css(dest)?;
+        }
+        if !self.selectors.is_empty() {
+            dest.write_char(' ')?;
+        }
+        dest.write_char('{')?;
+        write_declaration_block(&self.declarations, dest)?;
+        for margin_rule in &self.margin_rules {
+            dest.write_str(" @")?;
+            dest.write_str(margin_rule.margin_box.name())?;
+            dest.write_str(" {")?;
+            write_declaration_block(&margin_rule.declarations, dest)?;
+            dest.write_str(" }")?;
+        }
+        dest.write_str(" }")
+    }
+}
+
+enum PageBodyItem<D> {
+    Declaration(D),
+    MarginRule(PageMarginRule<D>),
+}
+
+struct PageBodyParser<'a, P>(&'a mut P);
+
+impl<'a, 'i, P: DeclarationParser<'i>> DeclarationParser<'i> for PageBodyParser<'a, P> {
+    type Declaration = PageBodyItem<P::Declaration>;
+    type Error = P::Error;
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Declaration, ParseError<'i, P::Error>> {
+        self.0
+            .parse_value(name, input)
+            .map(PageBodyItem::Declaration)
+    }
+}
+
+impl<'a, 'i, P: DeclarationParser<'i>> AtRuleParser<'i> for PageBodyParser<'a, P> {
+    type PreludeNoBlock = ();
+    type PreludeBlock = PageMarginBox;
+    type AtRule = PageBodyItem<P::Declaration>;
+    type Error = P::Error;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<AtRuleType<(), PageMarginBox>, ParseError<'i, P::Error>> {
+        match PageMarginBox::from_name(&name) {
+            Some(margin_box) => {
+                input.expect_exhausted()?;
+                Ok(AtRuleType::WithBlock(margin_box))
+            }
+            None => Err(input.new_error(BasicParseErrorKind::AtRuleInvalid(name))),
+        }
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        margin_box: PageMarginBox,
+        _: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, P::Error>> {
+        let declarations = DeclarationListParser::new(input, DeclarationsOnly(self.0))
+            .flatten()
+            .collect();
+        Ok(PageBodyItem::MarginRule(PageMarginRule {
+            margin_box,
+            declarations,
+        }))
+    }
+}
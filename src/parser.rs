@@ -156,10 +156,30 @@ impl<'i, T> ParseError<'i, T> {
     }
 }
 
+/// The number of tokens other than whitespace and comments kept by `ParserInput`
+/// after they’ve been tokenized, so that backtracking (with `Parser::reset`
+/// or `Parser::peek_nth`) does not need to tokenize them again.
+const CACHED_TOKENS: usize = 3;
+
+/// The slot of `ParserInput::cached_tokens` for the last whitespace or comment token,
+/// which are cheap to tokenize again and must not evict the tokens `Parser::next` returns.
+const WHITESPACE_SLOT: usize = CACHED_TOKENS;
+
+#[cfg(test)]
+thread_local! {
+    /// The number of tokens tokenized by parsers, rather than taken from their cache.
+    pub(crate) static TOKENIZED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 /// The owned input for a parser.
 pub struct ParserInput<'i> {
     tokenizer: Tokenizer<'i>,
-    cached_token: Option<CachedToken<'i>>,
+    /// A ring buffer of recently tokenized tokens, followed by `WHITESPACE_SLOT`.
+    cached_tokens: [Option<CachedToken<'i>>; CACHED_TOKENS + 1],
+    /// The index in `cached_tokens` of the token last returned by the parser.
+    current_cached_token: usize,
+    /// The index in `cached_tokens` of the slot to overwrite with the next new token.
+    next_cache_slot: usize,
 }
 
 struct CachedToken<'i> {
@@ -173,7 +193,9 @@ impl<'i> ParserInput<'i> {
     pub fn new(input: &'i str) -> ParserInput<'i> {
         ParserInput {
             tokenizer: Tokenizer::new(input),
-            cached_token: None,
+            cached_tokens: Default::default(),
+            current_cached_token: 0,
+            next_cache_slot: 0,
         }
     }
 
@@ -182,13 +204,28 @@ impl<'i> ParserInput<'i> {
     pub fn new_with_line_number_offset(input: &'i str, first_line_number: u32) -> ParserInput<'i> {
         ParserInput {
             tokenizer: Tokenizer::with_first_line_number(input, first_line_number),
-            cached_token: None,
+            cached_tokens: Default::default(),
+            current_cached_token: 0,
+            next_cache_slot: 0,
         }
     }
 
     #[inline]
     fn cached_token_ref(&self) -> &Token<'i> {
-        &self.cached_token.as_ref().unwrap().token
+        &self.cached_tokens[self.current_cached_token]
+            .as_ref()
+            .unwrap()
+            .token
+    }
+
+    /// Return the index in `cached_tokens` of the token starting at the given position, if any.
+    #[inline]
+    fn find_cached_token(&self, start_position: SourcePosition) -> Option<usize> {
+        self.cached_tokens.iter().position(|cached_token| {
+            cached_token.as_ref().map_or(false, |cached_token| {
+                cached_token.start_position == start_position
+            })
+        })
     }
 }
 
@@ -237,15 +274,20 @@ impl BlockType {
 /// ```{rust,ignore}
 /// input.parse_until_before(Delimiter::CurlyBracketBlock | Delimiter::Semicolon)
 /// ```
+///
+/// In addition to the constants in the `Delimiter` module,
+/// a set can contain `<delim-token>`s for ASCII characters (see `Delimiters::delim`).
+/// To stop at identifiers such as `and` in media queries,
+/// see `Parser::parse_until_before_ident`.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Delimiters {
-    bits: u8,
+    bits: u32,
 }
 
 /// `Delimiters` constants.
 #[allow(non_upper_case_globals, non_snake_case)]
 pub mod Delimiter {
-    use super::Delimiters;
+    use super::{delim_char_bit, Delimiters};
 
     /// The empty delimiter set
     pub const None: Delimiters = Delimiters { bits: 0 };
@@ -257,6 +299,14 @@ pub mod Delimiter {
     pub const Bang: Delimiters = Delimiters { bits: 1 << 3 };
     /// The delimiter set with only the `,` comma
     pub const Comma: Delimiters = Delimiters { bits: 1 << 4 };
+    /// The delimiter set with only the `/` solidus
+    pub const Slash: Delimiters = Delimiters {
+        bits: delim_char_bit(b'/'),
+    };
+    /// The delimiter set with only the `|` vertical line
+    pub const Bar: Delimiters = Delimiters {
+        bits: delim_char_bit(b'|'),
+    };
 }
 
 #[allow(non_upper_case_globals, non_snake_case)]
@@ -268,6 +318,38 @@ mod ClosingDelimiter {
     pub const CloseParenthesis: Delimiters = Delimiters { bits: 1 << 7 };
 }
 
+/// The bit of a `Delimiters` set for `<delim-token>`s with the given ASCII value,
+/// or zero if the character never is a `<delim-token>` on its own
+/// (and `!`, whose `Delimiter::Bang` bit is found without tokenizing).
+///
+/// Unlike the other delimiters, these bytes can start other tokens (`/*`, `|=`, `-foo`…),
+/// so they are only delimiters after tokenizing.
+const fn delim_char_bit(byte: u8) -> u32 {
+    let index = match byte {
+        b'#' => 0,
+        b'$' => 1,
+        b'%' => 2,
+        b'&' => 3,
+        b'*' => 4,
+        b'+' => 5,
+        b'-' => 6,
+        b'.' => 7,
+        b'/' => 8,
+        b'<' => 9,
+        b'=' => 10,
+        b'>' => 11,
+        b'?' => 12,
+        b'@' => 13,
+        b'\\' => 14,
+        b'^' => 15,
+        b'`' => 16,
+        b'|' => 17,
+        b'~' => 18,
+        _ => return 0,
+    };
+    1 << (8 + index)
+}
+
 impl BitOr<Delimiters> for Delimiters {
     type Output = Delimiters;
 
@@ -280,11 +362,60 @@ impl BitOr<Delimiters> for Delimiters {
 }
 
 impl Delimiters {
+    /// The delimiter set with only `<delim-token>`s with the given value.
+    ///
+    /// Panics if `value` is not an ASCII character that can be a `<delim-token>`,
+    /// such as a letter or a bracket.
+    #[inline]
+    pub fn delim(value: char) -> Delimiters {
+        if value == '!' {
+            return Delimiter::Bang;
+        }
+        let bits = if value.is_ascii() {
+            delim_char_bit(value as u8)
+        } else {
+            0
+        };
+        assert!(bits != 0, "{:?} can not be a delimiter", value);
+        Delimiters { bits }
+    }
+
     #[inline]
     fn contains(self, other: Delimiters) -> bool {
         (self.bits & other.bits) != 0
     }
 
+    /// Whether `byte` starts a `<delim-token>` in this set if it is tokenized on its own.
+    #[inline]
+    fn may_contain_delim(self, byte: Option<u8>) -> bool {
+        match byte {
+            Some(byte) => self.bits & delim_char_bit(byte) != 0,
+            None => false,
+        }
+    }
+
+    /// Whether the given token is a `<delim-token>` in this set.
+    #[inline]
+    fn contains_delim_token(self, token: &Token) -> bool {
+        match *token {
+            Token::Delim(value) if value.is_ascii() => self.may_contain_delim(Some(value as u8)),
+            _ => false,
+        }
+    }
+
+    /// Whether the next token of the given tokenizer is in this set,
+    /// without advancing it.
+    #[inline]
+    fn starts_next_token(self, tokenizer: &Tokenizer) -> bool {
+        let byte = tokenizer.next_byte();
+        if self.contains(Delimiters::from_byte(byte)) {
+            return true;
+        }
+        // Only tokenize when the next byte could be one of our `<delim-token>`s.
+        self.may_contain_delim(byte)
+            && matches!(tokenizer.clone().next(), Ok(ref token) if self.contains_delim_token(token))
+    }
+
     #[inline]
     fn from_byte(byte: Option<u8>) -> Delimiters {
         match byte {
@@ -471,7 +602,7 @@ impl<'i: 't, 't> Parser<'i, 't> {
     #[inline]
     pub(crate) fn next_byte(&self) -> Option<u8> {
         let byte = self.input.tokenizer.next_byte();
-        if self.stop_before.contains(Delimiters::from_byte(byte)) {
+        if self.stop_before.starts_next_token(&self.input.tokenizer) {
             return None;
         }
         byte
@@ -586,35 +717,53 @@ impl<'i: 't, 't> Parser<'i, 't> {
             return Err(self.new_basic_error(BasicParseErrorKind::EndOfInput));
         }
 
+        let token_start_state = self.input.tokenizer.state();
         let token_start_position = self.input.tokenizer.position();
-        let using_cached_token = self
-            .input
-            .cached_token
-            .as_ref()
-            .map_or(false, |cached_token| {
-                cached_token.start_position == token_start_position
-            });
-        let token = if using_cached_token {
-            let cached_token = self.input.cached_token.as_ref().unwrap();
-            self.input.tokenizer.reset(&cached_token.end_state);
-            match cached_token.token {
-                Token::Function(ref name) => self.input.tokenizer.see_function(name),
-                _ => {}
+        match self.input.find_cached_token(token_start_position) {
+            Some(index) => {
+                let cached_token = self.input.cached_tokens[index].as_ref().unwrap();
+                self.input.tokenizer.reset(&cached_token.end_state);
+                match cached_token.token {
+                    Token::Function(ref name) => self.input.tokenizer.see_function(name),
+                    _ => {}
+                }
+                self.input.current_cached_token = index;
             }
-            &cached_token.token
-        } else {
-            let new_token = self
-                .input
-                .tokenizer
-                .next()
-                .map_err(|()| self.new_basic_error(BasicParseErrorKind::EndOfInput))?;
-            self.input.cached_token = Some(CachedToken {
-                token: new_token,
-                start_position: token_start_position,
-                end_state: self.input.tokenizer.state(),
-            });
-            self.input.cached_token_ref()
-        };
+            None => {
+                let new_token = self
+                    .input
+                    .tokenizer
+                    .next()
+                    .map_err(|()| self.new_basic_error(BasicParseErrorKind::EndOfInput))?;
+                #[cfg(test)]
+                TOKENIZED.with(|tokenized| tokenized.set(tokenized.get() + 1));
+                let index = match new_token {
+                    Token::WhiteSpace(_) | Token::Comment(_) => WHITESPACE_SLOT,
+                    _ => {
+                        let index = self.input.next_cache_slot;
+                        self.input.next_cache_slot = (index + 1) % CACHED_TOKENS;
+                        index
+                    }
+                };
+                self.input.cached_tokens[index] = Some(CachedToken {
+                    token: new_token,
+                    start_position: token_start_position,
+                    end_state: self.input.tokenizer.state(),
+                });
+                self.input.current_cached_token = index;
+            }
+        }
+
+        if self.stop_before.may_contain_delim(byte)
+            && self
+                .stop_before
+                .contains_delim_token(self.input.cached_token_ref())
+        {
+            self.input.tokenizer.reset(&token_start_state);
+            return Err(self.new_basic_error(BasicParseErrorKind::EndOfInput));
+        }
+
+        let token = self.input.cached_token_ref();
 
         if let Some(block_type) = BlockType::opening(token) {
             self.at_start_of = Some(block_type);
@@ -622,6 +771,37 @@ impl<'i: 't, 't> Parser<'i, 't> {
         Ok(token)
     }
 
+    /// Return the next token in the input that is neither whitespace or a comment,
+    /// without consuming it.
+    ///
+    /// This is equivalent to `Parser::peek_nth(0)`.
+    #[inline]
+    pub fn peek(&mut self) -> Result<&Token<'i>, BasicParseError<'i>> {
+        self.peek_nth(0)
+    }
+
+    /// Return the token that the `n + 1`-th call to `Parser::next` would return
+    /// (so `n` is zero-based), without consuming any input.
+    ///
+    /// Like with `Parser::next`, a `Function` or block token counts as a single token
+    /// that includes its contents, and the end of a delimited or nested parser
+    /// is reported as `EndOfInput`.
+    ///
+    /// Tokens that are looked at are kept in a small cache in the `ParserInput`,
+    /// so that consuming them afterwards (for `n` up to 2) does not tokenize them again.
+    pub fn peek_nth(&mut self, n: usize) -> Result<&Token<'i>, BasicParseError<'i>> {
+        let start = self.state();
+        for _ in 0..=n {
+            if let Err(e) = self.next() {
+                self.reset(&start);
+                return Err(e);
+            }
+        }
+        let index = self.input.current_cached_token;
+        self.reset(&start);
+        Ok(&self.input.cached_tokens[index].as_ref().unwrap().token)
+    }
+
     /// Have the given closure parse something, then check the the input is exhausted.
     /// The result is overridden to `Err(())` if some input remains.
     ///
@@ -670,6 +850,141 @@ impl<'i: 't, 't> Parser<'i, 't> {
         }
     }
 
+    /// Parse a list of values separated by `/` delimiters, all with the same syntax,
+    /// such as `12px/1.5` in the `font` shorthand or `a / b` in `grid-area`.
+    ///
+    /// The given closure is called once for each item.
+    /// Unlike with `parse_comma_separated`, a trailing `/` is not consumed
+    /// and a missing `/` simply ends the list,
+    /// so that callers can check the number of items they got.
+    ///
+    /// This method returns `Err(())` the first time that a closure call does.
+    #[inline]
+    pub fn parse_slash_separated<F, T, E>(
+        &mut self,
+        mut parse_one: F,
+    ) -> Result<Vec<T>, ParseError<'i, E>>
+    where
+        F: FnMut(&mut Parser<'i, 't>) -> Result<T, ParseError<'i, E>>,
+    {
+        let mut values = Vec::with_capacity(1);
+        loop {
+            values.push(parse_one(self)?);
+            let before_slash = self.state();
+            if self.expect_delim('/').is_err() {
+                self.reset(&before_slash);
+                return Ok(values);
+            }
+        }
+    }
+
+    /// Parse a space-separated repetition of values with the same syntax,
+    /// such as `<length>{1,4}` or `<image>+`.
+    ///
+    /// The given closure is called (through `Parser::try_parse`) until it fails,
+    /// until `max` values have been parsed,
+    /// or until it succeeds without consuming any input.
+    ///
+    /// This method returns the error of the last closure call
+    /// if fewer than `min` values could be parsed.
+    #[inline]
+    pub fn parse_repeated<F, T, E>(
+        &mut self,
+        min: usize,
+        max: Option<usize>,
+        mut parse_one: F,
+    ) -> Result<Vec<T>, ParseError<'i, E>>
+    where
+        F: FnMut(&mut Parser<'i, 't>) -> Result<T, ParseError<'i, E>>,
+    {
+        let mut values = Vec::new();
+        loop {
+            if let Some(max) = max {
+                if values.len() >= max {
+                    break;
+                }
+            }
+            let start = self.position();
+            match self.try_parse(&mut parse_one) {
+                Ok(value) => values.push(value),
+                Err(e) => {
+                    if values.len() < min {
+                        return Err(e);
+                    }
+                    break;
+                }
+            }
+            if self.position() == start {
+                break;
+            }
+        }
+        if values.len() < min {
+            return Err(self.new_error_for_next_token());
+        }
+        Ok(values)
+    }
+
+    /// Parse components that can appear in any order, each at most once,
+    /// such as the `a || b || c` combinator in CSS value definitions.
+    ///
+    /// The given closure is called with the index (in `0..count`) of the component to parse,
+    /// through `Parser::try_parse`, for each component that was not found yet,
+    /// until no remaining component matches.
+    ///
+    /// Returns a vector of length `count` with the parsed value of each component, if any.
+    /// At least one component must be present:
+    /// otherwise the error of the first closure call is returned.
+    #[inline]
+    pub fn parse_any_order<F, T, E>(
+        &mut self,
+        count: usize,
+        mut parse_one: F,
+    ) -> Result<Vec<Option<T>>, ParseError<'i, E>>
+    where
+        F: FnMut(usize, &mut Parser<'i, 't>) -> Result<T, ParseError<'i, E>>,
+    {
+        let mut values: Vec<Option<T>> = (0..count).map(|_| None).collect();
+        let mut first_error = None;
+        'outer: loop {
+            for (index, slot) in values.iter_mut().enumerate() {
+                if slot.is_some() {
+                    continue;
+                }
+                match self.try_parse(|input| parse_one(index, input)) {
+                    Ok(value) => {
+                        *slot = Some(value);
+                        continue 'outer;
+                    }
+                    Err(e) => {
+                        if first_error.is_none() {
+                            first_error = Some(e)
+                        }
+                    }
+                }
+            }
+            break;
+        }
+        if values.iter().all(Option::is_none) {
+            return Err(match first_error {
+                Some(e) => e,
+                None => self.new_error_for_next_token(),
+            });
+        }
+        Ok(values)
+    }
+
+    /// Parse an optional component, such as a trailing `<number>` in `[ <length> <number>? ]`.
+    ///
+    /// Like `Parser::try_parse`, but converts a failure into `None`
+    /// after restoring the position within the input.
+    #[inline]
+    pub fn parse_optional<F, T, E>(&mut self, parse: F) -> Option<T>
+    where
+        F: FnOnce(&mut Parser<'i, 't>) -> Result<T, E>,
+    {
+        self.try_parse(parse).ok()
+    }
+
     /// Parse the content of a block or function.
     ///
     /// This method panics if the last token yielded by this parser
@@ -726,6 +1041,41 @@ impl<'i: 't, 't> Parser<'i, 't> {
         parse_until_after(self, delimiters, parse)
     }
 
+    /// Like `parse_until_before`, but stop before the first `<ident-token>`
+    /// at this block/function nesting level that is an ASCII case-insensitive match
+    /// for one of `idents`, such as `and` or `or` in media queries.
+    ///
+    /// The delimiters of this parser still apply. The input before the identifier
+    /// is scanned once more than with `parse_until_before`.
+    pub fn parse_until_before_ident<F, T, E>(
+        &mut self,
+        idents: &[&str],
+        parse: F,
+    ) -> Result<T, ParseError<'i, E>>
+    where
+        F: for<'tt> FnOnce(&mut Parser<'i, 'tt>) -> Result<T, ParseError<'i, E>>,
+    {
+        parse_until_before_ident(self, idents, parse)
+    }
+
+    /// Like `parse_until_before_ident`, but also consume the identifier, if any.
+    pub fn parse_until_after_ident<F, T, E>(
+        &mut self,
+        idents: &[&str],
+        parse: F,
+    ) -> Result<T, ParseError<'i, E>>
+    where
+        F: for<'tt> FnOnce(&mut Parser<'i, 'tt>) -> Result<T, ParseError<'i, E>>,
+    {
+        let result = parse_until_before_ident(self, idents, parse);
+        let state = self.state();
+        match self.next_including_whitespace_and_comments() {
+            Ok(Token::Ident(name)) if idents.iter().any(|i| name.eq_ignore_ascii_case(i)) => {}
+            _ => self.reset(&state),
+        }
+        result
+    }
+
     /// Parse a <whitespace-token> and return its value.
     #[inline]
     pub fn expect_whitespace(&mut self) -> Result<&'i str, BasicParseError<'i>> {
@@ -950,6 +1300,17 @@ impl<'i: 't, 't> Parser<'i, 't> {
     }
 }
 
+/// Whether an identifier is a CSS-wide keyword, or `default`,
+/// which are not valid `<custom-ident>`s.
+///
+/// https://drafts.csswg.org/css-values-4/#custom-idents
+pub(crate) fn is_css_wide_keyword(ident: &str) -> bool {
+    match_ignore_ascii_case! { ident,
+        "initial" | "inherit" | "unset" | "revert" | "revert-layer" | "default" => true,
+        _ => false,
+    }
+}
+
 pub fn parse_until_before<'i: 't, 't, F, T, E>(
     parser: &mut Parser<'i, 't>,
     delimiters: Delimiters,
@@ -977,7 +1338,12 @@ where
         if delimiters.contains(Delimiters::from_byte(parser.input.tokenizer.next_byte())) {
             break;
         }
+        let before_token = parser.input.tokenizer.state();
         if let Ok(token) = parser.input.tokenizer.next() {
+            if delimiters.contains_delim_token(&token) {
+                parser.input.tokenizer.reset(&before_token);
+                break;
+            }
             if let Some(block_type) = BlockType::opening(&token) {
                 consume_until_end_of_block(block_type, &mut parser.input.tokenizer);
             }
@@ -1003,13 +1369,61 @@ where
             .stop_before
             .contains(Delimiters::from_byte(next_byte))
     {
-        debug_assert!(delimiters.contains(Delimiters::from_byte(next_byte)));
-        // We know this byte is ASCII.
-        parser.input.tokenizer.advance(1);
-        if next_byte == Some(b'{') {
-            consume_until_end_of_block(BlockType::CurlyBracket, &mut parser.input.tokenizer);
+        if delimiters.contains(Delimiters::from_byte(next_byte)) {
+            // We know this byte is ASCII.
+            parser.input.tokenizer.advance(1);
+            if next_byte == Some(b'{') {
+                consume_until_end_of_block(BlockType::CurlyBracket, &mut parser.input.tokenizer);
+            }
+        } else if delimiters.starts_next_token(&parser.input.tokenizer)
+            && !parser
+                .stop_before
+                .starts_next_token(&parser.input.tokenizer)
+        {
+            // A `<delim-token>` for an ASCII character is a single byte.
+            parser.input.tokenizer.advance(1);
+        }
+    }
+    result
+}
+
+fn parse_until_before_ident<'i: 't, 't, F, T, E>(
+    parser: &mut Parser<'i, 't>,
+    idents: &[&str],
+    parse: F,
+) -> Result<T, ParseError<'i, E>>
+where
+    F: for<'tt> FnOnce(&mut Parser<'i, 'tt>) -> Result<T, ParseError<'i, E>>,
+{
+    let start = parser.state();
+    let end = loop {
+        if let Some(block_type) = parser.at_start_of.take() {
+            consume_until_end_of_block(block_type, &mut parser.input.tokenizer);
         }
+        let before_token = parser.state();
+        match parser.next_including_whitespace_and_comments() {
+            Ok(Token::Ident(name)) if idents.iter().any(|i| name.eq_ignore_ascii_case(i)) => {
+                break before_token;
+            }
+            Ok(_) => {}
+            Err(_) => break parser.state(),
+        }
+    };
+    // Parse the input up to the identifier with a separate `ParserInput`, so that
+    // `Delimiters` (and every `Parser`) does not need to store identifiers.
+    let mut delimited_input = ParserInput::new(
+        parser
+            .input
+            .tokenizer
+            .input_until(SourcePosition(end.position)),
+    );
+    let result;
+    {
+        let mut delimited_parser = Parser::new(&mut delimited_input);
+        delimited_parser.reset(&start);
+        result = delimited_parser.parse_entirely(parse);
     }
+    parser.reset(&end);
     result
 }
 
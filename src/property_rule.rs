@@ -0,0 +1,650 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.css-houdini.org/css-properties-values-api-1/#at-property-rule
+
+use std::fmt;
+
+use super::{
+    AtRuleParser, BasicParseError, Color, CowRcStr, DeclarationListParser, DeclarationParser,
+    ParseError, Parser, ParserInput, ToCss, Token,
+};
+use crate::parser::is_css_wide_keyword;
+use crate::serializer::{serialize_identifier, serialize_name, serialize_string};
+
+/// A data type name in a syntax definition, such as `<length>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataType {
+    /// `<length>`
+    Length,
+    /// `<number>`
+    Number,
+    /// `<percentage>`
+    Percentage,
+    /// `<length-percentage>`
+    LengthPercentage,
+    /// `<color>`
+    Color,
+    /// `<image>`
+    Image,
+    /// `<url>`
+    Url,
+    /// `<integer>`
+    Integer,
+    /// `<angle>`
+    Angle,
+    /// `<time>`
+    Time,
+    /// `<resolution>`
+    Resolution,
+    /// `<transform-function>`
+    TransformFunction,
+    /// `<transform-list>`, which can not have a multiplier.
+    TransformList,
+    /// `<custom-ident>`
+    CustomIdent,
+    /// `<string>`
+    String,
+}
+
+impl DataType {
+    /// The data type with the given name, without `<` and `>`. Names are case-sensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "length" => DataType::Length,
+            "number" => DataType::Number,
+            "percentage" => DataType::Percentage,
+            "length-percentage" => DataType::LengthPercentage,
+            "color" => DataType::Color,
+            "image" => DataType::Image,
+            "url" => DataType::Url,
+            "integer" => DataType::Integer,
+            "angle" => DataType::Angle,
+            "time" => DataType::Time,
+            "resolution" => DataType::Resolution,
+            "transform-function" => DataType::TransformFunction,
+            "transform-list" => DataType::TransformList,
+            "custom-ident" => DataType::CustomIdent,
+            "string" => DataType::String,
+            _ => return None,
+        })
+    }
+
+    /// The name of this data type, without `<` and `>`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DataType::Length => "length",
+            DataType::Number => "number",
+            DataType::Percentage => "percentage",
+            DataType::LengthPercentage => "length-percentage",
+            DataType::Color => "color",
+            DataType::Image => "image",
+            DataType::Url => "url",
+            DataType::Integer => "integer",
+            DataType::Angle => "angle",
+            DataType::Time => "time",
+            DataType::Resolution => "resolution",
+            DataType::TransformFunction => "transform-function",
+            DataType::TransformList => "transform-list",
+            DataType::CustomIdent => "custom-ident",
+            DataType::String => "string",
+        }
+    }
+}
+
+/// The name of a syntax component.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SyntaxComponentName {
+    /// A data type, such as `<length>`.
+    DataType(DataType),
+    /// An identifier, which only matches itself, case-sensitively.
+    Ident(String),
+}
+
+/// A syntax component multiplier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Multiplier {
+    /// `+`: a space-separated list of one or more values.
+    Space,
+    /// `#`: a comma-separated list of one or more values.
+    Comma,
+}
+
+/// A syntax component, such as `<length>+` or `auto`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyntaxComponent {
+    /// What each value matches.
+    pub name: SyntaxComponentName,
+    /// The multiplier, if any.
+    pub multiplier: Option<Multiplier>,
+}
+
+impl ToCss for SyntaxComponent {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match self.name {
+            SyntaxComponentName::DataType(data_type) => {
+                dest.write_char('<')?;
+                dest.write_str(data_type.name())?;
+                dest.write_char('>')?;
+            }
+            SyntaxComponentName::Ident(ref ident) => serialize_identifier(ident, dest)?,
+        }
+        match self.multiplier {
+            Some(Multiplier::Space) => dest.write_char('+'),
+            Some(Multiplier::Comma) => dest.write_char('#'),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The value of the `syntax` descriptor.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SyntaxDefinition {
+    /// `*`, which matches any sequence of tokens.
+    Universal,
+    /// One or more components separated by `|`, which match the first alternative that does.
+    Components(Vec<SyntaxComponent>),
+}
+
+impl SyntaxDefinition {
+    /// Parse a syntax definition, from the content of the `syntax` string.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        if input.try_parse(|input| input.expect_delim('*')).is_ok() {
+            input.expect_exhausted()?;
+            return Ok(SyntaxDefinition::Universal);
+        }
+        let mut components = Vec::new();
+        loop {
+            components.push(Self::parse_component(input)?);
+            if input.is_exhausted() {
+                return Ok(SyntaxDefinition::Components(components));
+            }
+            input.expect_delim('|')?;
+        }
+    }
+
+    fn parse_component<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<SyntaxComponent, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let name = match *input.next()? {
+            Token::Delim('<') => {
+                let location = input.current_source_location();
+                let data_type = match *input.next_including_whitespace()? {
+                    Token::Ident(ref name) => DataType::from_name(name).ok_or_else(|| {
+                        location.new_basic_unexpected_token_error(Token::Ident(name.clone()))
+                    })?,
+                    ref token => {
+                        return Err(location.new_basic_unexpected_token_error(token.clone()))
+                    }
+                };
+                let location = input.current_source_location();
+                match *input.next_including_whitespace()? {
+                    Token::Delim('>') => {}
+                    ref token => {
+                        return Err(location.new_basic_unexpected_token_error(token.clone()))
+                    }
+                }
+                SyntaxComponentName::DataType(data_type)
+            }
+            Token::Ident(ref ident) if !is_css_wide_keyword(ident) => {
+                SyntaxComponentName::Ident(ident.as_ref().to_owned())
+            }
+            ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+        };
+        let state = input.state();
+        let multiplier = match input.next_including_whitespace() {
+            Ok(&Token::Delim('+')) => Some(Multiplier::Space),
+            Ok(&Token::Delim('#')) => Some(Multiplier::Comma),
+            _ => {
+                input.reset(&state);
+                None
+            }
+        };
+        if multiplier.is_some() && name == SyntaxComponentName::DataType(DataType::TransformList) {
+            return Err(location.new_basic_unexpected_token_error(Token::Delim('<')));
+        }
+        Ok(SyntaxComponent { name, multiplier })
+    }
+
+    /// Whether the whole input matches this syntax definition.
+    ///
+    /// Math functions such as `calc()` match numeric data types
+    /// if all their dimensions have units of the expected type,
+    /// and the arguments of other functions are not checked.
+    pub fn matches<'i, 't>(&self, input: &mut Parser<'i, 't>) -> bool {
+        let components = match *self {
+            SyntaxDefinition::Universal => {
+                while input.next().is_ok() {}
+                return true;
+            }
+            SyntaxDefinition::Components(ref components) => components,
+        };
+        let start = input.state();
+        for component in components {
+            let result: Result<(), ParseError<()>> = input.parse_entirely(|input| match component
+                .multiplier
+            {
+                None => parse_component_value(&component.name, input),
+                Some(Multiplier::Space) => {
+                    parse_component_value(&component.name, input)?;
+                    while input
+                        .try_parse(|input| parse_component_value(&component.name, input))
+                        .is_ok()
+                    {}
+                    Ok(())
+                }
+                Some(Multiplier::Comma) => input
+                    .parse_comma_separated(|input| parse_component_value(&component.name, input))
+                    .map(|_| ()),
+            });
+            if result.is_ok() {
+                return true;
+            }
+            input.reset(&start);
+        }
+        while input.next().is_ok() {}
+        false
+    }
+}
+
+impl ToCss for SyntaxDefinition {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            SyntaxDefinition::Universal => dest.write_char('*'),
+            SyntaxDefinition::Components(ref components) => {
+                for (i, component) in components.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(" | ")?;
+                    }
+                    component.to_css(dest)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+const ABSOLUTE_LENGTH_UNITS: &[&str] = &["px", "cm", "mm", "q", "in", "pt", "pc"];
+
+const RELATIVE_LENGTH_UNITS: &[&str] = &[
+    "em", "rem", "ex", "rex", "cap", "rcap", "ch", "rch", "ic", "ric", "lh", "rlh", "vw", "vh",
+    "vi", "vb", "vmin", "vmax", "svw", "svh", "svi", "svb", "svmin", "svmax", "lvw", "lvh", "lvi",
+    "lvb", "lvmin", "lvmax", "dvw", "dvh", "dvi", "dvb", "dvmin", "dvmax", "cqw", "cqh", "cqi",
+    "cqb", "cqmin", "cqmax",
+];
+
+fn has_unit(units: &[&str], unit: &str) -> bool {
+    units.iter().any(|u| u.eq_ignore_ascii_case(unit))
+}
+
+/// The numeric data type of a dimension unit.
+fn unit_type(unit: &str) -> Option<DataType> {
+    if has_unit(ABSOLUTE_LENGTH_UNITS, unit) || has_unit(RELATIVE_LENGTH_UNITS, unit) {
+        return Some(DataType::Length);
+    }
+    Some(match_ignore_ascii_case! { unit,
+        "deg" | "grad" | "rad" | "turn" => DataType::Angle,
+        "s" | "ms" => DataType::Time,
+        "dpi" | "dpcm" | "dppx" | "x" => DataType::Resolution,
+        _ => return None,
+    })
+}
+
+fn is_math_function(name: &str) -> bool {
+    match_ignore_ascii_case! { name,
+        "calc" | "min" | "max" | "clamp" => true,
+        _ => false,
+    }
+}
+
+fn is_transform_function(name: &str) -> bool {
+    match_ignore_ascii_case! { name,
+        "matrix" | "matrix3d" | "translate" | "translate3d" | "translatex" | "translatey"
+        | "translatez" | "scale" | "scale3d" | "scalex" | "scaley" | "scalez" | "rotate"
+        | "rotate3d" | "rotatex" | "rotatey" | "rotatez" | "skew" | "skewx" | "skewy"
+        | "perspective" => true,
+        _ => false,
+    }
+}
+
+fn is_image_function(name: &str) -> bool {
+    match_ignore_ascii_case! { name,
+        "url" | "linear-gradient" | "radial-gradient" | "conic-gradient"
+        | "repeating-linear-gradient" | "repeating-radial-gradient"
+        | "repeating-conic-gradient" | "image" | "image-set" | "cross-fade" | "element" => true,
+        _ => false,
+    }
+}
+
+/// Consume the arguments of a function whose content is not checked.
+fn skip_arguments<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(), ParseError<'i, ()>> {
+    input.parse_nested_block(|input| {
+        while input.next().is_ok() {}
+        Ok(())
+    })
+}
+
+/// Check that the numeric tokens in a math function have one of the allowed types,
+/// where `DataType::Number` is always allowed.
+fn parse_math_arguments<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    allowed: &[DataType],
+) -> Result<(), ParseError<'i, ()>> {
+    input.parse_nested_block(|input| {
+        while let Ok(token) = input.next() {
+            let token = token.clone();
+            let ok = match token {
+                Token::Dimension { ref unit, .. } => {
+                    matches!(unit_type(unit), Some(data_type) if allowed.contains(&data_type))
+                }
+                Token::Percentage { .. } => allowed.contains(&DataType::Percentage),
+                Token::Function(ref name) => is_math_function(name),
+                _ => true,
+            };
+            if !ok {
+                return Err(input.new_unexpected_token_error(token));
+            }
+            if let Token::Function(_) = token {
+                parse_math_arguments(input, allowed)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn parse_component_value<'i, 't>(
+    name: &SyntaxComponentName,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, ()>> {
+    let data_type = match *name {
+        SyntaxComponentName::Ident(ref expected) => {
+            let location = input.current_source_location();
+            let ident = input.expect_ident()?;
+            if **ident != **expected {
+                return Err(location.new_unexpected_token_error(Token::Ident(ident.clone())));
+            }
+            return Ok(());
+        }
+        SyntaxComponentName::DataType(data_type) => data_type,
+    };
+    match data_type {
+        DataType::Color => return Ok(Color::parse(input).map(|_| ())?),
+        DataType::TransformList => {
+            parse_component_value(
+                &SyntaxComponentName::DataType(DataType::TransformFunction),
+                input,
+            )?;
+            while input
+                .try_parse(|input| {
+                    parse_component_value(
+                        &SyntaxComponentName::DataType(DataType::TransformFunction),
+                        input,
+                    )
+                })
+                .is_ok()
+            {}
+            return Ok(());
+        }
+        _ => {}
+    }
+    let location = input.current_source_location();
+    let token = input.next()?.clone();
+    let matches = match token {
+        Token::Number {
+            int_value, value, ..
+        } => match data_type {
+            DataType::Number => true,
+            DataType::Integer => int_value.is_some(),
+            DataType::Length | DataType::LengthPercentage => value == 0.,
+            _ => false,
+        },
+        Token::Percentage { .. } => {
+            data_type == DataType::Percentage || data_type == DataType::LengthPercentage
+        }
+        Token::Dimension { ref unit, .. } => match (unit_type(unit), data_type) {
+            (Some(DataType::Length), DataType::LengthPercentage) => true,
+            (Some(unit_type), data_type) => unit_type == data_type,
+            (None, _) => false,
+        },
+        Token::Ident(ref ident) => {
+            data_type == DataType::CustomIdent && !is_css_wide_keyword(ident)
+        }
+        Token::QuotedString(_) => data_type == DataType::String,
+        Token::UnquotedUrl(_) => data_type == DataType::Url || data_type == DataType::Image,
+        Token::Function(ref name) => {
+            if is_math_function(name) {
+                let allowed: &[DataType] = match data_type {
+                    DataType::Number | DataType::Integer => &[],
+                    DataType::Length => &[DataType::Length],
+                    DataType::Percentage => &[DataType::Percentage],
+                    DataType::LengthPercentage => &[DataType::Length, DataType::Percentage],
+                    DataType::Angle => &[DataType::Angle],
+                    DataType::Time => &[DataType::Time],
+                    DataType::Resolution => &[DataType::Resolution],
+                    _ => return Err(location.new_unexpected_token_error(token.clone())),
+                };
+                parse_math_arguments(input, allowed)?;
+                return Ok(());
+            }
+            let matches = match data_type {
+                DataType::Url => name.eq_ignore_ascii_case("url"),
+                DataType::Image => is_image_function(name),
+                DataType::TransformFunction => is_transform_function(name),
+                _ => false,
+            };
+            if matches {
+                skip_arguments(input)?;
+                return Ok(());
+            }
+            false
+        }
+        _ => false,
+    };
+    if matches {
+        Ok(())
+    } else {
+        Err(location.new_unexpected_token_error(token))
+    }
+}
+
+/// Whether a value has no relative lengths and no `var()` or `env()` references,
+/// so that it can be computed without knowing other property values.
+fn is_computationally_independent<'i, 't>(input: &mut Parser<'i, 't>) -> bool {
+    while let Ok(token) = input.next() {
+        match *token {
+            Token::Dimension { ref unit, .. } if has_unit(RELATIVE_LENGTH_UNITS, unit) => {
+                return false
+            }
+            Token::Function(ref name)
+                if name.eq_ignore_ascii_case("var") || name.eq_ignore_ascii_case("env") =>
+            {
+                return false
+            }
+            Token::Function(_)
+            | Token::ParenthesisBlock
+            | Token::SquareBracketBlock
+            | Token::CurlyBracketBlock => {
+                let result: Result<_, ParseError<()>> =
+                    input.parse_nested_block(|input| Ok(is_computationally_independent(input)));
+                if result != Ok(true) {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Why a `@property` rule is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropertyRuleError {
+    /// The `syntax` descriptor is missing or invalid.
+    MissingSyntax,
+    /// The `inherits` descriptor is missing or invalid.
+    MissingInherits,
+    /// The `initial-value` descriptor is missing, and the syntax is not universal.
+    MissingInitialValue,
+    /// The `initial-value` descriptor does not match the syntax.
+    InitialValueMismatch,
+    /// The `initial-value` descriptor is not computationally independent,
+    /// for example `3em`.
+    InitialValueNotComputationallyIndependent,
+}
+
+/// A valid `@property` rule, which registers a custom property.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyRule {
+    /// The name of the custom property, including the leading `--`.
+    pub name: String,
+    /// The `syntax` descriptor.
+    pub syntax: SyntaxDefinition,
+    /// The `inherits` descriptor.
+    pub inherits: bool,
+    /// The `initial-value` descriptor, as written but without surrounding whitespace.
+    /// It is only optional for the universal syntax.
+    pub initial_value: Option<String>,
+}
+
+impl PropertyRule {
+    /// Parse the prelude of a `@property` rule, after the at-keyword: a custom property name.
+    pub fn parse_name<'i, 't>(input: &mut Parser<'i, 't>) -> Result<String, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let name = input.expect_ident()?;
+        if !name.starts_with("--") || name.len() == 2 {
+            return Err(location.new_basic_unexpected_token_error(Token::Ident(name.clone())));
+        }
+        Ok(name.as_ref().to_owned())
+    }
+
+    /// Parse the block of a `@property` rule, and check that the rule is valid.
+    ///
+    /// Invalid and unknown descriptors are ignored,
+    /// and a descriptor specified more than once takes its last valid value.
+    pub fn parse_block<'i, 't>(
+        name: String,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, PropertyRuleError> {
+        let mut syntax = None;
+        let mut inherits = None;
+        let mut initial_value = None;
+        for descriptor in DeclarationListParser::new(input, PropertyDescriptorParser).flatten() {
+            match descriptor {
+                PropertyDescriptor::Syntax(value) => syntax = Some(value),
+                PropertyDescriptor::Inherits(value) => inherits = Some(value),
+                PropertyDescriptor::InitialValue(value) => initial_value = Some(value),
+            }
+        }
+        let syntax = syntax.ok_or(PropertyRuleError::MissingSyntax)?;
+        let inherits = inherits.ok_or(PropertyRuleError::MissingInherits)?;
+        if syntax != SyntaxDefinition::Universal {
+            let value = initial_value
+                .as_ref()
+                .ok_or(PropertyRuleError::MissingInitialValue)?;
+            let mut input = ParserInput::new(value);
+            if !syntax.matches(&mut Parser::new(&mut input)) {
+                return Err(PropertyRuleError::InitialValueMismatch);
+            }
+            let mut input = ParserInput::new(value);
+            if !is_computationally_independent(&mut Parser::new(&mut input)) {
+                return Err(PropertyRuleError::InitialValueNotComputationallyIndependent);
+            }
+        }
+        Ok(PropertyRule {
+            name,
+            syntax,
+            inherits,
+            initial_value,
+        })
+    }
+}
+
+impl ToCss for PropertyRule {
+    /// Serialize the whole rule.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("@property ")?;
+        match self.name.strip_prefix("--") {
+            Some(name) => {
+                dest.write_str("--")?;
+                serialize_name(name, dest)?;
+            }
+            None => serialize_identifier(&self.name, dest)?,
+        }
+        dest.write_str(" { syntax: ")?;
+        serialize_string(&self.syntax.to_css_string(), dest)?;
+        dest.write_str("; inherits: ")?;
+        dest.write_str(if self.inherits { "true" } else { "false" })?;
+        dest.write_char(';')?;
+        if let Some(ref initial_value) = self.initial_value {
+            dest.write_str(" initial-value: ")?;
+            dest.write_str(initial_value)?;
+            dest.write_char(';')?;
+        }
+        dest.write_str(" }")
+    }
+}
+
+/// One `@property` descriptor.
+enum PropertyDescriptor {
+    Syntax(SyntaxDefinition),
+    Inherits(bool),
+    InitialValue(String),
+}
+
+struct PropertyDescriptorParser;
+
+impl<'i> DeclarationParser<'i> for PropertyDescriptorParser {
+    type Declaration = PropertyDescriptor;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<PropertyDescriptor, ParseError<'i, ()>> {
+        Ok(match_ignore_ascii_case! { &name,
+            "syntax" => {
+                let location = input.current_source_location();
+                let syntax = input.expect_string()?.clone();
+                let mut syntax_input = ParserInput::new(&syntax);
+                let syntax = Parser::new(&mut syntax_input)
+                    .parse_entirely(|input| SyntaxDefinition::parse(input).map_err(ParseError::<()>::from))
+                    .map_err(|_| location.new_custom_error(()))?;
+                PropertyDescriptor::Syntax(syntax)
+            },
+            "inherits" => {
+                let location = input.current_source_location();
+                let ident = input.expect_ident()?;
+                PropertyDescriptor::Inherits(match_ignore_ascii_case! { ident,
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(location.new_unexpected_token_error(Token::Ident(ident.clone()))),
+                })
+            },
+            "initial-value" => {
+                let start = input.position();
+                while input.next().is_ok() {}
+                PropertyDescriptor::InitialValue(input.slice_from(start).trim().to_owned())
+            },
+            _ => return Err(input.new_custom_error(())),
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for PropertyDescriptorParser {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = PropertyDescriptor;
+    type Error = ();
+}
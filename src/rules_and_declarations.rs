@@ -148,8 +148,8 @@ pub trait AtRuleParser<'i> {
     /// as returned by `RuleListParser::next` or `DeclarationListParser::next`,
     /// or `Err(())` to ignore the entire at-rule as invalid.
     ///
-    /// This is only called when `parse_prelude` returned `WithBlock`, and a block
-    /// was indeed found following the prelude.
+    /// This is only called when `parse_prelude` returned `WithBlock`,
+    /// and a block was indeed found following the prelude.
     fn parse_block<'t>(
         &mut self,
         prelude: Self::PreludeBlock,
@@ -161,6 +161,26 @@ pub trait AtRuleParser<'i> {
         let _ = input;
         Err(input.new_error(BasicParseErrorKind::AtRuleBodyInvalid))
     }
+
+    /// End an at-rule whose block is optional but missing, such as `@layer foo;`.
+    /// Return the finished representation of the at-rule,
+    /// or `None` to ignore the entire at-rule as invalid.
+    ///
+    /// The location passed in is source location of the start of the prelude.
+    ///
+    /// This is only called when `parse_prelude` returned `WithBlock`, and
+    /// either a `;` semicolon follows the prelude, or parser is at
+    /// the end of the input.
+    /// The default implementation returns `None`: blocks are required.
+    fn rule_without_optional_block(
+        &mut self,
+        prelude: Self::PreludeBlock,
+        start: &ParserState,
+    ) -> Option<Self::AtRule> {
+        let _ = prelude;
+        let _ = start;
+        None
+    }
 }
 
 /// A trait to provide various parsing of qualified rules.
@@ -454,7 +474,7 @@ where
     })
 }
 
-fn parse_at_rule<'i, 't, P, E>(
+pub(crate) fn parse_at_rule<'i, 't, P, E>(
     start: &ParserState,
     name: CowRcStr<'i>,
     input: &mut Parser<'i, 't>,
@@ -485,11 +505,17 @@ where
                     parse_nested_block(input, callback)
                         .map_err(|e| (e, input.slice_from(start.position())))
                 }
-                Ok(&Token::Semicolon) => Err((
-                    input.new_unexpected_token_error(Token::Semicolon),
-                    input.slice_from(start.position()),
-                )),
-                Err(e) => Err((e.into(), input.slice_from(start.position()))),
+                Ok(&Token::Semicolon) => match parser.rule_without_optional_block(prelude, start) {
+                    Some(rule) => Ok(rule),
+                    None => Err((
+                        input.new_unexpected_token_error(Token::Semicolon),
+                        input.slice_from(start.position()),
+                    )),
+                },
+                Err(e) => match parser.rule_without_optional_block(prelude, start) {
+                    Some(rule) => Ok(rule),
+                    None => Err((e.into(), input.slice_from(start.position()))),
+                },
                 Ok(_) => unreachable!(),
             }
         }
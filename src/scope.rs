@@ -0,0 +1,223 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-cascade-6/#scoped-styles
+
+use std::fmt;
+
+use super::{
+    AtRuleParser, BasicParseError, DeclarationParser, Delimiter, ParseError, Parser,
+    QualifiedRuleParser, ToCss, Token,
+};
+use crate::parser::{parse_nested_block, parse_until_after, parse_until_before};
+use crate::rules_and_declarations::parse_at_rule;
+
+/// The prelude of a `@scope` rule: `[ ( <scope-start> ) ]? [ to ( <scope-end> ) ]?`.
+///
+/// The selector lists are kept as written, without surrounding whitespace,
+/// to be parsed by the caller's selector parser.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScopeRule {
+    /// The scoping roots, or `None` for the parent rule's elements
+    /// (or the owner node of the stylesheet at the top-level).
+    pub start: Option<String>,
+    /// The scoping limits, if any.
+    pub end: Option<String>,
+}
+
+impl ScopeRule {
+    /// Parse the prelude of a `@scope` rule, after the at-keyword.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let mut rule = ScopeRule::default();
+        if input
+            .try_parse(|input| input.expect_parenthesis_block())
+            .is_ok()
+        {
+            rule.start = Some(parse_selector_text(input)?);
+        }
+        if input
+            .try_parse(|input| input.expect_ident_matching("to"))
+            .is_ok()
+        {
+            input.expect_parenthesis_block()?;
+            rule.end = Some(parse_selector_text(input)?);
+        }
+        input.expect_exhausted()?;
+        Ok(rule)
+    }
+}
+
+/// Return the non-empty content of the parenthesis block that was just consumed.
+fn parse_selector_text<'i, 't>(input: &mut Parser<'i, 't>) -> Result<String, BasicParseError<'i>> {
+    let result: Result<_, ParseError<()>> = input.parse_nested_block(|input| {
+        input.skip_whitespace();
+        let start = input.position();
+        input.next()?;
+        while input.next().is_ok() {}
+        Ok(input.slice_from(start).trim_end().to_owned())
+    });
+    result.map_err(ParseError::basic)
+}
+
+impl ToCss for ScopeRule {
+    /// Serialize the prelude, without the at-keyword.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if let Some(ref start) = self.start {
+            dest.write_char('(')?;
+            dest.write_str(start)?;
+            dest.write_char(')')?;
+            if self.end.is_some() {
+                dest.write_char(' ')?;
+            }
+        }
+        if let Some(ref end) = self.end {
+            dest.write_str("to (")?;
+            dest.write_str(end)?;
+            dest.write_char(')')?;
+        }
+        Ok(())
+    }
+}
+
+/// A `QualifiedRuleParser` for the style rules nested in a `@scope` rule,
+/// which can depend on the scope. For example, selectors that do not start
+/// with `:scope` or `&` are relative to `:scope`.
+pub trait ScopedRuleParser<'i>: QualifiedRuleParser<'i> {
+    /// Parse the prelude of a style rule inside the given `@scope` rule.
+    ///
+    /// The default implementation ignores the scope and calls `parse_prelude`.
+    fn parse_scoped_prelude<'t>(
+        &mut self,
+        scope: &ScopeRule,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        let _ = scope;
+        self.parse_prelude(input)
+    }
+}
+
+/// Provides an iterator for the body of a `@scope` rule,
+/// which can contain declarations as well as style rules and at-rules.
+///
+/// Declarations directly in the body apply to the scoping roots,
+/// as if in a `:where(:scope) { ... }` rule.
+pub struct ScopeBodyParser<'i, 't, 'a, P> {
+    /// The input given to `ScopeBodyParser::new`
+    pub input: &'a mut Parser<'i, 't>,
+
+    /// The prelude of the `@scope` rule given to `ScopeBodyParser::new`
+    pub scope: ScopeRule,
+
+    /// The parser given to `ScopeBodyParser::new`
+    pub parser: P,
+}
+
+impl<'i, 't, 'a, I, P, E: 'i> ScopeBodyParser<'i, 't, 'a, P>
+where
+    P: DeclarationParser<'i, Declaration = I, Error = E>
+        + ScopedRuleParser<'i, QualifiedRule = I, Error = E>
+        + AtRuleParser<'i, AtRule = I, Error = E>,
+{
+    /// Create a new `ScopeBodyParser` for the block of the given `@scope` rule.
+    ///
+    /// As with `DeclarationListParser` and `RuleListParser`, the return type for
+    /// declarations, qualified rules and at-rules needs to be the same.
+    pub fn new(input: &'a mut Parser<'i, 't>, scope: ScopeRule, parser: P) -> Self {
+        ScopeBodyParser {
+            input,
+            scope,
+            parser,
+        }
+    }
+}
+
+/// `ScopeBodyParser` is an iterator that yields `Ok(_)` for a valid declaration or rule
+/// or `Err(())` for an invalid one.
+impl<'i, 't, 'a, I, P, E: 'i> Iterator for ScopeBodyParser<'i, 't, 'a, P>
+where
+    P: DeclarationParser<'i, Declaration = I, Error = E>
+        + ScopedRuleParser<'i, QualifiedRule = I, Error = E>
+        + AtRuleParser<'i, AtRule = I, Error = E>,
+{
+    type Item = Result<I, (ParseError<'i, E>, &'i str)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.input.state();
+            match self.input.next_including_whitespace_and_comments() {
+                Ok(Token::WhiteSpace(_)) | Ok(Token::Comment(_)) | Ok(Token::Semicolon) => continue,
+                Ok(Token::AtKeyword(name)) => {
+                    let name = name.clone();
+                    return Some(parse_at_rule(&start, name, self.input, &mut self.parser));
+                }
+                Ok(Token::Ident(name)) => {
+                    let name = name.clone();
+                    if looks_like_declaration(self.input, name.starts_with("--")) {
+                        let parser = &mut self.parser;
+                        let result = parse_until_after(self.input, Delimiter::Semicolon, |input| {
+                            input.expect_colon()?;
+                            parser.parse_value(name, input)
+                        });
+                        return Some(
+                            result.map_err(|e| (e, self.input.slice_from(start.position()))),
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(..) => return None,
+            }
+            self.input.reset(&start);
+            let result = parse_scoped_qualified_rule(self.input, &mut self.parser, &self.scope);
+            return Some(result.map_err(|e| (e, self.input.slice_from(start.position()))));
+        }
+    }
+}
+
+/// After an identifier, whether what follows is a declaration rather than a style rule
+/// such as `a:hover { ... }`: a colon, then a value without a `{}` block
+/// unless this is a custom property.
+fn looks_like_declaration<'i, 't>(input: &mut Parser<'i, 't>, is_custom_property: bool) -> bool {
+    let state = input.state();
+    let result: Result<bool, ParseError<()>> =
+        input.parse_until_before(Delimiter::Semicolon, |input| {
+            input.expect_colon()?;
+            let mut has_block = false;
+            while let Ok(token) = input.next() {
+                has_block |= *token == Token::CurlyBracketBlock;
+            }
+            Ok(is_custom_property || !has_block)
+        });
+    input.reset(&state);
+    result == Ok(true)
+}
+
+fn parse_scoped_qualified_rule<'i, 't, P, E>(
+    input: &mut Parser<'i, 't>,
+    parser: &mut P,
+    scope: &ScopeRule,
+) -> Result<<P as QualifiedRuleParser<'i>>::QualifiedRule, ParseError<'i, E>>
+where
+    P: ScopedRuleParser<'i, Error = E>,
+{
+    let start = input.state();
+    // FIXME: https://github.com/servo/rust-cssparser/issues/254
+    let callback = |input: &mut Parser<'i, '_>| parser.parse_scoped_prelude(scope, input);
+    let delimiters = Delimiter::Semicolon | Delimiter::CurlyBracketBlock;
+    let prelude = parse_until_before(input, delimiters, callback);
+    match *input.next()? {
+        Token::CurlyBracketBlock => {
+            // Do this here so that we consume the `{` even if the prelude is `Err`.
+            let prelude = prelude?;
+            // FIXME: https://github.com/servo/rust-cssparser/issues/254
+            let callback = |input: &mut Parser<'i, '_>| parser.parse_block(prelude, &start, input);
+            parse_nested_block(input, callback)
+        }
+        // Nested style rules end at semicolons.
+        Token::Semicolon => Err(input.new_unexpected_token_error(Token::Semicolon)),
+        _ => unreachable!(),
+    }
+}
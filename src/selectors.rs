@@ -0,0 +1,1374 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/selectors-4/
+
+use std::fmt;
+
+use super::{
+    AnPlusB, BasicParseError, BasicParseErrorKind, CowRcStr, Delimiter, NamespaceMap, ParseError,
+    Parser, ToCss, Token,
+};
+use crate::serializer::{serialize_identifier, serialize_string};
+
+/// The specificity of a selector: the number of ID selectors, of class-like selectors
+/// (classes, attributes and pseudo-classes) and of type-like selectors
+/// (types and pseudo-elements), in that order.
+///
+/// Tuples compare lexicographically, which is how specificities are compared.
+pub type Specificity = (u32, u32, u32);
+
+fn add_specificity(a: Specificity, b: Specificity) -> Specificity {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+/// Hooks for the parts of selector parsing that depend on the stylesheet or the host language.
+///
+/// Every method has a default implementation that declares no namespace
+/// and rejects any non-standard pseudo-class or pseudo-element.
+pub trait SelectorParser {
+    /// The namespace URL of a prefix declared with `@namespace`, or `None`
+    /// if it is not declared, which makes the selector invalid.
+    fn namespace_for_prefix(&self, prefix: &str) -> Option<&str> {
+        let _ = prefix;
+        None
+    }
+
+    /// The default namespace declared with `@namespace`, if any,
+    /// which applies to type and universal selectors without a prefix.
+    fn default_namespace(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether `:name` is a supported non-standard pseudo-class.
+    /// The name is in ASCII lowercase.
+    fn is_custom_pseudo_class(&self, name: &str) -> bool {
+        let _ = name;
+        false
+    }
+
+    /// Parse the arguments of a non-standard functional pseudo-class `:name(...)`,
+    /// returning them as they should be serialized.
+    /// The name is in ASCII lowercase.
+    fn parse_custom_functional_pseudo_class<'i, 't>(
+        &self,
+        name: &str,
+        arguments: &mut Parser<'i, 't>,
+    ) -> Result<String, BasicParseError<'i>> {
+        let function = Token::Function(name.to_owned().into());
+        Err(arguments.new_basic_unexpected_token_error(function))
+    }
+
+    /// Whether `::name` is a supported non-standard pseudo-element, such as `::-webkit-scrollbar`.
+    /// The name is in ASCII lowercase.
+    fn is_custom_pseudo_element(&self, name: &str) -> bool {
+        let _ = name;
+        false
+    }
+}
+
+/// Resolves namespace prefixes with the `@namespace` rules of a stylesheet.
+impl SelectorParser for NamespaceMap {
+    fn namespace_for_prefix(&self, prefix: &str) -> Option<&str> {
+        self.prefix(prefix)
+    }
+
+    fn default_namespace(&self) -> Option<&str> {
+        NamespaceMap::default_namespace(self)
+    }
+}
+
+/// A comma-separated list of complex selectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectorList(pub Vec<Selector>);
+
+/// A complex selector: compound selectors separated by combinators.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    /// The compound selectors, from left to right. There is at least one.
+    pub compounds: Vec<CompoundSelector>,
+    /// The combinators, `combinators[i]` being between `compounds[i]` and `compounds[i + 1]`.
+    pub combinators: Vec<Combinator>,
+}
+
+/// A relative selector, as in `:has(> img)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelativeSelector {
+    /// The combinator between the anchor element and the selector,
+    /// `Descendant` if none was written.
+    pub combinator: Combinator,
+    /// The selector, relative to the anchor element.
+    pub selector: Selector,
+}
+
+/// https://drafts.csswg.org/selectors-4/#combinators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combinator {
+    /// Whitespace
+    Descendant,
+    /// `>`
+    Child,
+    /// `+`
+    NextSibling,
+    /// `~`
+    SubsequentSibling,
+    /// `||`
+    Column,
+}
+
+/// A sequence of simple selectors that are not separated by a combinator.
+///
+/// A type or universal selector can only come first,
+/// and only pseudo-classes can follow a pseudo-element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompoundSelector(pub Vec<SimpleSelector>);
+
+/// https://drafts.csswg.org/selectors-4/#simple
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimpleSelector {
+    /// `ns|name`
+    Type {
+        /// The namespace of the element.
+        namespace: NamespaceConstraint,
+        /// The local name, as written.
+        local_name: String,
+    },
+    /// `ns|*`
+    Universal {
+        /// The namespace of the element.
+        namespace: NamespaceConstraint,
+    },
+    /// `#id`
+    Id(String),
+    /// `.class`
+    Class(String),
+    /// `[attr]`, `[attr=value i]`, …
+    Attribute(AttributeSelector),
+    /// `:name` or `:name(...)`
+    PseudoClass(PseudoClass),
+    /// `::name` or `::name(...)`, or one of the four legacy `:name` pseudo-elements.
+    PseudoElement(PseudoElement),
+}
+
+/// The namespace part of a type, universal or attribute selector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NamespaceConstraint {
+    /// No prefix and no default namespace: any namespace for elements,
+    /// and no namespace for attributes.
+    Implicit,
+    /// No prefix, for an element in the default namespace.
+    Default(String),
+    /// `*|`: any namespace, including none.
+    Any,
+    /// `|`: no namespace.
+    None,
+    /// `prefix|`
+    Prefixed {
+        /// The prefix, as written.
+        prefix: String,
+        /// The namespace it was declared for.
+        url: String,
+    },
+}
+
+/// https://drafts.csswg.org/selectors-4/#attribute-selectors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeSelector {
+    /// The namespace of the attribute.
+    pub namespace: NamespaceConstraint,
+    /// The local name, as written.
+    pub local_name: String,
+    /// The operator and value, or `None` for `[attr]`.
+    pub operation: Option<(AttributeOperator, String)>,
+    /// The case-sensitivity of the value.
+    pub case: AttributeCase,
+}
+
+/// The operator of an attribute selector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeOperator {
+    /// `=`
+    Equal,
+    /// `~=`
+    Includes,
+    /// `|=`
+    DashMatch,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Substring,
+}
+
+impl AttributeOperator {
+    /// The operator as written in CSS.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            AttributeOperator::Equal => "=",
+            AttributeOperator::Includes => "~=",
+            AttributeOperator::DashMatch => "|=",
+            AttributeOperator::Prefix => "^=",
+            AttributeOperator::Suffix => "$=",
+            AttributeOperator::Substring => "*=",
+        }
+    }
+}
+
+/// The case-sensitivity flag of an attribute selector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeCase {
+    /// No flag: the document language decides.
+    Default,
+    /// `i`: ASCII case-insensitive.
+    Insensitive,
+    /// `s`: case-sensitive.
+    Sensitive,
+}
+
+/// https://drafts.csswg.org/selectors-4/#pseudo-classes
+#[derive(Clone, Debug, PartialEq)]
+pub enum PseudoClass {
+    /// `:is(...)`, a forgiving selector list.
+    Is(SelectorList),
+    /// `:where(...)`, a forgiving selector list without specificity.
+    Where(SelectorList),
+    /// `:not(...)`
+    Not(SelectorList),
+    /// `:has(...)`
+    Has(Vec<RelativeSelector>),
+    /// `:nth-child()`, `:nth-last-child()`, `:nth-of-type()` or `:nth-last-of-type()`.
+    Nth(NthSelector),
+    /// `:first-child`
+    FirstChild,
+    /// `:last-child`
+    LastChild,
+    /// `:only-child`
+    OnlyChild,
+    /// `:first-of-type`
+    FirstOfType,
+    /// `:last-of-type`
+    LastOfType,
+    /// `:only-of-type`
+    OnlyOfType,
+    /// `:root`
+    Root,
+    /// `:empty`
+    Empty,
+    /// `:scope`
+    Scope,
+    /// `:lang(...)`, with the language ranges as written.
+    Lang(Vec<String>),
+    /// `:dir(ltr)` or `:dir(rtl)`
+    Dir(Direction),
+    /// A pseudo-class that depends on the state of the element, such as `:hover`.
+    State(StatePseudoClass),
+    /// A pseudo-class accepted by `SelectorParser::is_custom_pseudo_class`
+    /// or `SelectorParser::parse_custom_functional_pseudo_class`.
+    Custom {
+        /// The name, in ASCII lowercase.
+        name: String,
+        /// The arguments of a functional pseudo-class.
+        arguments: Option<String>,
+    },
+}
+
+/// The arguments of `:dir()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// `ltr`
+    Ltr,
+    /// `rtl`
+    Rtl,
+}
+
+/// Which children `NthSelector` counts, and from which end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NthKind {
+    /// `:nth-child()`
+    Child,
+    /// `:nth-last-child()`
+    LastChild,
+    /// `:nth-of-type()`
+    OfType,
+    /// `:nth-last-of-type()`
+    LastOfType,
+}
+
+impl NthKind {
+    /// The name of the pseudo-class.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NthKind::Child => "nth-child",
+            NthKind::LastChild => "nth-last-child",
+            NthKind::OfType => "nth-of-type",
+            NthKind::LastOfType => "nth-last-of-type",
+        }
+    }
+}
+
+/// `:nth-child(An+B of S)` and the like.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NthSelector {
+    /// The pseudo-class.
+    pub kind: NthKind,
+    /// The indices that match.
+    pub an_plus_b: AnPlusB,
+    /// The `of S` selector list, for `:nth-child()` and `:nth-last-child()`.
+    pub of: Option<SelectorList>,
+}
+
+macro_rules! state_pseudo_classes {
+    ($($variant: ident => $name: expr,)+) => {
+        /// The pseudo-classes that only depend on the state of the element,
+        /// as known by the host.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum StatePseudoClass {
+            $(
+                #[doc = $name]
+                $variant,
+            )+
+        }
+
+        impl StatePseudoClass {
+            /// All the state pseudo-classes.
+            pub const ALL: &'static [StatePseudoClass] = &[$(StatePseudoClass::$variant),+];
+
+            /// The pseudo-class with this name, ASCII case-insensitively.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match_ignore_ascii_case! { name,
+                    $($name => Some(StatePseudoClass::$variant),)+
+                    _ => None,
+                }
+            }
+
+            /// The name of the pseudo-class, without the colon.
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(StatePseudoClass::$variant => $name,)+
+                }
+            }
+        }
+    }
+}
+
+state_pseudo_classes! {
+    Link => "link",
+    AnyLink => "any-link",
+    Visited => "visited",
+    Target => "target",
+    Hover => "hover",
+    Active => "active",
+    Focus => "focus",
+    FocusVisible => "focus-visible",
+    FocusWithin => "focus-within",
+    Enabled => "enabled",
+    Disabled => "disabled",
+    Checked => "checked",
+    Indeterminate => "indeterminate",
+    Default => "default",
+    Required => "required",
+    Optional => "optional",
+    ReadOnly => "read-only",
+    ReadWrite => "read-write",
+    PlaceholderShown => "placeholder-shown",
+    Valid => "valid",
+    Invalid => "invalid",
+    Defined => "defined",
+}
+
+/// https://drafts.csswg.org/css-pseudo-4/
+#[derive(Clone, Debug, PartialEq)]
+pub enum PseudoElement {
+    /// `::before`
+    Before,
+    /// `::after`
+    After,
+    /// `::first-line`
+    FirstLine,
+    /// `::first-letter`
+    FirstLetter,
+    /// `::marker`
+    Marker,
+    /// `::placeholder`
+    Placeholder,
+    /// `::selection`
+    Selection,
+    /// `::target-text`
+    TargetText,
+    /// `::spelling-error`
+    SpellingError,
+    /// `::grammar-error`
+    GrammarError,
+    /// `::backdrop`
+    Backdrop,
+    /// `::file-selector-button`
+    FileSelectorButton,
+    /// `::highlight(name)`
+    Highlight(String),
+    /// `::part(name ...)`
+    Part(Vec<String>),
+    /// `::slotted(compound-selector)`
+    Slotted(CompoundSelector),
+    /// A pseudo-element accepted by `SelectorParser::is_custom_pseudo_element`,
+    /// with its name in ASCII lowercase.
+    Custom(String),
+}
+
+impl PseudoElement {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match_ignore_ascii_case! { name,
+            "before" => PseudoElement::Before,
+            "after" => PseudoElement::After,
+            "first-line" => PseudoElement::FirstLine,
+            "first-letter" => PseudoElement::FirstLetter,
+            "marker" => PseudoElement::Marker,
+            "placeholder" => PseudoElement::Placeholder,
+            "selection" => PseudoElement::Selection,
+            "target-text" => PseudoElement::TargetText,
+            "spelling-error" => PseudoElement::SpellingError,
+            "grammar-error" => PseudoElement::GrammarError,
+            "backdrop" => PseudoElement::Backdrop,
+            "file-selector-button" => PseudoElement::FileSelectorButton,
+            _ => return None,
+        })
+    }
+
+    /// The name of the pseudo-element, without the colons and arguments.
+    pub fn name(&self) -> &str {
+        match *self {
+            PseudoElement::Before => "before",
+            PseudoElement::After => "after",
+            PseudoElement::FirstLine => "first-line",
+            PseudoElement::FirstLetter => "first-letter",
+            PseudoElement::Marker => "marker",
+            PseudoElement::Placeholder => "placeholder",
+            PseudoElement::Selection => "selection",
+            PseudoElement::TargetText => "target-text",
+            PseudoElement::SpellingError => "spelling-error",
+            PseudoElement::GrammarError => "grammar-error",
+            PseudoElement::Backdrop => "backdrop",
+            PseudoElement::FileSelectorButton => "file-selector-button",
+            PseudoElement::Highlight(_) => "highlight",
+            PseudoElement::Part(_) => "part",
+            PseudoElement::Slotted(_) => "slotted",
+            PseudoElement::Custom(ref name) => name,
+        }
+    }
+}
+
+/// Where a selector is parsed, for the restrictions on what it can contain.
+#[derive(Clone, Copy)]
+struct Context {
+    allow_pseudo_elements: bool,
+    in_has: bool,
+}
+
+impl Context {
+    fn nested(self) -> Self {
+        Context {
+            allow_pseudo_elements: false,
+            in_has: self.in_has,
+        }
+    }
+}
+
+const TOP_LEVEL: Context = Context {
+    allow_pseudo_elements: true,
+    in_has: false,
+};
+
+impl SelectorList {
+    /// Parse a `<selector-list>`, such as the prelude of a style rule.
+    pub fn parse<'i, 't, P>(
+        parser: &P,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, BasicParseError<'i>>
+    where
+        P: SelectorParser + ?Sized,
+    {
+        parse_selector_list(parser, input, TOP_LEVEL).map_err(ParseError::basic)
+    }
+
+    /// Parse a `<relative-selector-list>`, such as the arguments of `:has()`.
+    pub fn parse_relative<'i, 't, P>(
+        parser: &P,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Vec<RelativeSelector>, BasicParseError<'i>>
+    where
+        P: SelectorParser + ?Sized,
+    {
+        let context = Context {
+            allow_pseudo_elements: false,
+            in_has: true,
+        };
+        input
+            .parse_comma_separated(|input| parse_relative_selector(parser, input, context))
+            .map_err(ParseError::basic)
+    }
+
+    /// The highest specificity of the selectors of the list, as used by `:is()` and `:not()`.
+    pub fn specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(Selector::specificity)
+            .max()
+            .unwrap_or((0, 0, 0))
+    }
+}
+
+impl Selector {
+    /// The specificity of the selector.
+    pub fn specificity(&self) -> Specificity {
+        self.compounds
+            .iter()
+            .map(CompoundSelector::specificity)
+            .fold((0, 0, 0), add_specificity)
+    }
+
+    /// The pseudo-element of the selector, which can only be in its last compound selector.
+    pub fn pseudo_element(&self) -> Option<&PseudoElement> {
+        self.compounds
+            .last()
+            .and_then(CompoundSelector::pseudo_element)
+    }
+}
+
+impl CompoundSelector {
+    /// The specificity of the compound selector.
+    pub fn specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(SimpleSelector::specificity)
+            .fold((0, 0, 0), add_specificity)
+    }
+
+    /// The pseudo-element of the compound selector, if any.
+    pub fn pseudo_element(&self) -> Option<&PseudoElement> {
+        self.0.iter().find_map(|simple| match *simple {
+            SimpleSelector::PseudoElement(ref pseudo_element) => Some(pseudo_element),
+            _ => None,
+        })
+    }
+}
+
+impl SimpleSelector {
+    /// The specificity of the simple selector.
+    pub fn specificity(&self) -> Specificity {
+        match *self {
+            SimpleSelector::Universal { .. } => (0, 0, 0),
+            SimpleSelector::Type { .. } => (0, 0, 1),
+            SimpleSelector::Id(_) => (1, 0, 0),
+            SimpleSelector::Class(_) | SimpleSelector::Attribute(_) => (0, 1, 0),
+            SimpleSelector::PseudoClass(ref pseudo_class) => pseudo_class.specificity(),
+            SimpleSelector::PseudoElement(PseudoElement::Slotted(ref compound)) => {
+                add_specificity((0, 0, 1), compound.specificity())
+            }
+            SimpleSelector::PseudoElement(_) => (0, 0, 1),
+        }
+    }
+}
+
+impl PseudoClass {
+    /// The specificity of the pseudo-class, which depends on the arguments
+    /// of `:is()`, `:not()`, `:has()` and `:nth-child(An+B of S)`.
+    pub fn specificity(&self) -> Specificity {
+        match *self {
+            PseudoClass::Is(ref list) | PseudoClass::Not(ref list) => list.specificity(),
+            PseudoClass::Where(_) => (0, 0, 0),
+            PseudoClass::Has(ref list) => list
+                .iter()
+                .map(|relative| relative.selector.specificity())
+                .max()
+                .unwrap_or((0, 0, 0)),
+            PseudoClass::Nth(NthSelector {
+                of: Some(ref list), ..
+            }) => add_specificity((0, 1, 0), list.specificity()),
+            _ => (0, 1, 0),
+        }
+    }
+}
+
+type SelectorResult<'i, T> = Result<T, ParseError<'i, ()>>;
+
+fn parse_selector_list<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, SelectorList>
+where
+    P: SelectorParser + ?Sized,
+{
+    let selectors = input.parse_comma_separated(|input| parse_selector(parser, input, context))?;
+    Ok(SelectorList(selectors))
+}
+
+/// https://drafts.csswg.org/selectors-4/#typedef-forgiving-selector-list
+///
+/// Invalid selectors are dropped instead of making the whole list invalid.
+fn parse_forgiving_selector_list<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, SelectorList>
+where
+    P: SelectorParser + ?Sized,
+{
+    let mut selectors = Vec::new();
+    loop {
+        let selector = input.parse_until_before(Delimiter::Comma, |input| {
+            parse_selector(parser, input, context)
+        });
+        if let Ok(selector) = selector {
+            selectors.push(selector)
+        }
+        match input.next() {
+            Err(_) => return Ok(SelectorList(selectors)),
+            Ok(&Token::Comma) => continue,
+            Ok(_) => unreachable!(),
+        }
+    }
+}
+
+fn parse_relative_selector<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, RelativeSelector>
+where
+    P: SelectorParser + ?Sized,
+{
+    let combinator = input
+        .try_parse(|input| match input.next() {
+            Ok(&Token::Delim('>')) => Ok(Combinator::Child),
+            Ok(&Token::Delim('+')) => Ok(Combinator::NextSibling),
+            Ok(&Token::Delim('~')) => Ok(Combinator::SubsequentSibling),
+            _ => Err(()),
+        })
+        .unwrap_or(Combinator::Descendant);
+    let selector = parse_selector(parser, input, context)?;
+    Ok(RelativeSelector {
+        combinator,
+        selector,
+    })
+}
+
+fn parse_selector<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, Selector>
+where
+    P: SelectorParser + ?Sized,
+{
+    input.skip_whitespace();
+    let mut compounds = vec![parse_compound_selector(parser, input, context)?];
+    let mut combinators = Vec::new();
+    while let Some(combinator) = parse_combinator(input)? {
+        // Pseudo-elements can only be in the last compound selector.
+        if compounds
+            .last()
+            .and_then(CompoundSelector::pseudo_element)
+            .is_some()
+        {
+            return Err(input.new_error_for_next_token());
+        }
+        combinators.push(combinator);
+        compounds.push(parse_compound_selector(parser, input, context)?);
+    }
+    Ok(Selector {
+        compounds,
+        combinators,
+    })
+}
+
+/// Parse the combinator after a compound selector, or return `None` at the end of the selector.
+fn parse_combinator<'i, 't>(input: &mut Parser<'i, 't>) -> SelectorResult<'i, Option<Combinator>> {
+    let mut any_whitespace = false;
+    loop {
+        let state = input.state();
+        let combinator = match input.next_including_whitespace() {
+            Err(_) => return Ok(None),
+            Ok(&Token::WhiteSpace(_)) => {
+                any_whitespace = true;
+                continue;
+            }
+            Ok(&Token::Delim('>')) => Some(Combinator::Child),
+            Ok(&Token::Delim('+')) => Some(Combinator::NextSibling),
+            Ok(&Token::Delim('~')) => Some(Combinator::SubsequentSibling),
+            // `|` can also start the type selector of a descendant, as in `a |b`.
+            Ok(&Token::Delim('|')) => None,
+            Ok(_) => {
+                input.reset(&state);
+                return Ok(descendant_or_end(any_whitespace));
+            }
+        };
+        let combinator = match combinator {
+            Some(combinator) => combinator,
+            None if parse_second_column_bar(input) => Combinator::Column,
+            None => {
+                input.reset(&state);
+                return Ok(descendant_or_end(any_whitespace));
+            }
+        };
+        input.skip_whitespace();
+        return Ok(Some(combinator));
+    }
+}
+
+/// After a `|`, consume a second `|` that immediately follows it, as in `||`.
+fn parse_second_column_bar(input: &mut Parser) -> bool {
+    input
+        .try_parse(|input| match input.next_including_whitespace() {
+            Ok(&Token::Delim('|')) => Ok(()),
+            _ => Err(()),
+        })
+        .is_ok()
+}
+
+fn descendant_or_end(any_whitespace: bool) -> Option<Combinator> {
+    if any_whitespace {
+        Some(Combinator::Descendant)
+    } else {
+        None
+    }
+}
+
+fn parse_compound_selector<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, CompoundSelector>
+where
+    P: SelectorParser + ?Sized,
+{
+    let mut simple_selectors = Vec::new();
+    let state = input.state();
+    match parse_qualified_name(parser, input, false)? {
+        Some((namespace, Some(local_name))) => simple_selectors.push(SimpleSelector::Type {
+            namespace,
+            local_name,
+        }),
+        Some((namespace, None)) => simple_selectors.push(SimpleSelector::Universal { namespace }),
+        None => input.reset(&state),
+    }
+    let mut after_pseudo_element = false;
+    loop {
+        let state = input.state();
+        let simple_selector = match input.next_including_whitespace() {
+            Ok(Token::IDHash(id)) => SimpleSelector::Id(id.as_ref().to_owned()),
+            Ok(&Token::Delim('.')) => match *input.next_including_whitespace()? {
+                Token::Ident(ref class) => SimpleSelector::Class(class.as_ref().to_owned()),
+                ref token => {
+                    let token = token.clone();
+                    return Err(input.new_unexpected_token_error(token));
+                }
+            },
+            Ok(&Token::SquareBracketBlock) => SimpleSelector::Attribute(
+                input.parse_nested_block(|input| parse_attribute_selector(parser, input))?,
+            ),
+            Ok(&Token::Colon) => parse_pseudo(parser, input, context)?,
+            _ => {
+                input.reset(&state);
+                break;
+            }
+        };
+        match simple_selector {
+            SimpleSelector::PseudoElement(_) if !after_pseudo_element => {
+                after_pseudo_element = true
+            }
+            SimpleSelector::PseudoClass(_) => {}
+            _ if after_pseudo_element => {
+                input.reset(&state);
+                return Err(input.new_error_for_next_token());
+            }
+            _ => {}
+        }
+        simple_selectors.push(simple_selector);
+    }
+    if simple_selectors.is_empty() {
+        return Err(input.new_error_for_next_token());
+    }
+    Ok(CompoundSelector(simple_selectors))
+}
+
+/// Parse `ns|name`, `ns|*`, `name` or `*`, with `*|` or `|` as the namespace prefix
+/// as well as an identifier.
+///
+/// Return `Ok(None)` without consuming anything if this is not a qualified name,
+/// or `Ok(Some((namespace, None)))` for a universal selector.
+fn parse_qualified_name<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    in_attribute: bool,
+) -> SelectorResult<'i, Option<(NamespaceConstraint, Option<String>)>>
+where
+    P: SelectorParser + ?Sized,
+{
+    let state = input.state();
+    let first = match input.next_including_whitespace() {
+        Ok(Token::Ident(name)) => Some(name.clone()),
+        Ok(&Token::Delim('*')) => None,
+        Ok(&Token::Delim('|')) => {
+            let local_name = parse_local_name(input, in_attribute)?;
+            return Ok(Some((NamespaceConstraint::None, local_name)));
+        }
+        _ => {
+            input.reset(&state);
+            return Ok(None);
+        }
+    };
+    let after_first = input.state();
+    if let Ok(&Token::Delim('|')) = input.next_including_whitespace() {
+        // `a||b` is a column combinator.
+        if !parse_second_column_bar(input) {
+            let namespace = match first {
+                Some(ref prefix) => match parser.namespace_for_prefix(prefix) {
+                    Some(url) => NamespaceConstraint::Prefixed {
+                        prefix: prefix.as_ref().to_owned(),
+                        url: url.to_owned(),
+                    },
+                    None => {
+                        let prefix = prefix.clone();
+                        return Err(state
+                            .source_location()
+                            .new_unexpected_token_error(Token::Ident(prefix)));
+                    }
+                },
+                None => NamespaceConstraint::Any,
+            };
+            let local_name = parse_local_name(input, in_attribute)?;
+            return Ok(Some((namespace, local_name)));
+        }
+    }
+    input.reset(&after_first);
+    let namespace = match parser.default_namespace() {
+        Some(url) if !in_attribute => NamespaceConstraint::Default(url.to_owned()),
+        _ => NamespaceConstraint::Implicit,
+    };
+    match first {
+        Some(name) => Ok(Some((namespace, Some(name.as_ref().to_owned())))),
+        None if in_attribute => Err(state
+            .source_location()
+            .new_unexpected_token_error(Token::Delim('*'))),
+        None => Ok(Some((namespace, None))),
+    }
+}
+
+/// Parse the local name after a namespace prefix, `None` being `*`.
+fn parse_local_name<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    in_attribute: bool,
+) -> SelectorResult<'i, Option<String>> {
+    match *input.next_including_whitespace()? {
+        Token::Ident(ref name) => Ok(Some(name.as_ref().to_owned())),
+        Token::Delim('*') if !in_attribute => Ok(None),
+        ref token => {
+            let token = token.clone();
+            Err(input.new_unexpected_token_error(token))
+        }
+    }
+}
+
+fn parse_attribute_selector<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+) -> SelectorResult<'i, AttributeSelector>
+where
+    P: SelectorParser + ?Sized,
+{
+    input.skip_whitespace();
+    let (namespace, local_name) = match parse_qualified_name(parser, input, true)? {
+        Some((namespace, Some(local_name))) => (namespace, local_name),
+        _ => return Err(input.new_error_for_next_token()),
+    };
+    let operator = match input.next() {
+        Err(_) => {
+            return Ok(AttributeSelector {
+                namespace,
+                local_name,
+                operation: None,
+                case: AttributeCase::Default,
+            })
+        }
+        Ok(&Token::Delim('=')) => AttributeOperator::Equal,
+        Ok(&Token::IncludeMatch) => AttributeOperator::Includes,
+        Ok(&Token::DashMatch) => AttributeOperator::DashMatch,
+        Ok(&Token::PrefixMatch) => AttributeOperator::Prefix,
+        Ok(&Token::SuffixMatch) => AttributeOperator::Suffix,
+        Ok(&Token::SubstringMatch) => AttributeOperator::Substring,
+        Ok(token) => {
+            let token = token.clone();
+            return Err(input.new_unexpected_token_error(token));
+        }
+    };
+    let value = match *input.next()? {
+        Token::Ident(ref value) | Token::QuotedString(ref value) => value.as_ref().to_owned(),
+        ref token => {
+            let token = token.clone();
+            return Err(input.new_unexpected_token_error(token));
+        }
+    };
+    let case = match input.try_parse(|input| input.expect_ident_cloned()) {
+        Ok(flag) => match_ignore_ascii_case! { &flag,
+            "i" => AttributeCase::Insensitive,
+            "s" => AttributeCase::Sensitive,
+            _ => return Err(input.new_unexpected_token_error(Token::Ident(flag.clone()))),
+        },
+        Err(_) => AttributeCase::Default,
+    };
+    input.expect_exhausted()?;
+    Ok(AttributeSelector {
+        namespace,
+        local_name,
+        operation: Some((operator, value)),
+        case,
+    })
+}
+
+/// Parse a pseudo-class or pseudo-element, after the first colon.
+fn parse_pseudo<'i, 't, P>(
+    parser: &P,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, SimpleSelector>
+where
+    P: SelectorParser + ?Sized,
+{
+    let location = input.current_source_location();
+    match input.next_including_whitespace()?.clone() {
+        Token::Colon => {
+            let location = input.current_source_location();
+            let token = input.next_including_whitespace()?.clone();
+            if !context.allow_pseudo_elements {
+                return Err(location.new_unexpected_token_error(token));
+            }
+            let pseudo_element = match token {
+                Token::Ident(ref name) => match PseudoElement::from_name(name) {
+                    Some(pseudo_element) => Some(pseudo_element),
+                    None => Some(name.to_ascii_lowercase())
+                        .filter(|name| parser.is_custom_pseudo_element(name))
+                        .map(PseudoElement::Custom),
+                },
+                Token::Function(ref name) => {
+                    let name = name.clone();
+                    Some(input.parse_nested_block(|input| {
+                        parse_functional_pseudo_element(parser, name, input)
+                    })?)
+                }
+                _ => None,
+            };
+            match pseudo_element {
+                Some(pseudo_element) => Ok(SimpleSelector::PseudoElement(pseudo_element)),
+                None => Err(location.new_unexpected_token_error(token)),
+            }
+        }
+        Token::Ident(name) => {
+            let pseudo_class = match_ignore_ascii_case! { &name,
+                "first-child" => PseudoClass::FirstChild,
+                "last-child" => PseudoClass::LastChild,
+                "only-child" => PseudoClass::OnlyChild,
+                "first-of-type" => PseudoClass::FirstOfType,
+                "last-of-type" => PseudoClass::LastOfType,
+                "only-of-type" => PseudoClass::OnlyOfType,
+                "root" => PseudoClass::Root,
+                "empty" => PseudoClass::Empty,
+                "scope" => PseudoClass::Scope,
+                // https://drafts.csswg.org/selectors-4/#pseudo-element-syntax
+                "before" | "after" | "first-line" | "first-letter" => {
+                    if !context.allow_pseudo_elements {
+                        return Err(location.new_unexpected_token_error(Token::Ident(name)));
+                    }
+                    let pseudo_element = PseudoElement::from_name(&name).unwrap();
+                    return Ok(SimpleSelector::PseudoElement(pseudo_element));
+                },
+                _ => match StatePseudoClass::from_name(&name) {
+                    Some(state) => PseudoClass::State(state),
+                    None => {
+                        let lowercase = name.to_ascii_lowercase();
+                        if !parser.is_custom_pseudo_class(&lowercase) {
+                            return Err(location.new_unexpected_token_error(Token::Ident(name)));
+                        }
+                        PseudoClass::Custom {
+                            name: lowercase,
+                            arguments: None,
+                        }
+                    }
+                }
+            };
+            Ok(SimpleSelector::PseudoClass(pseudo_class))
+        }
+        Token::Function(name) => {
+            let pseudo_class = input.parse_nested_block(|input| {
+                parse_functional_pseudo_class(parser, name, input, context)
+            })?;
+            Ok(SimpleSelector::PseudoClass(pseudo_class))
+        }
+        token => Err(location.new_unexpected_token_error(token)),
+    }
+}
+
+fn parse_functional_pseudo_class<'i, 't, P>(
+    parser: &P,
+    name: CowRcStr<'i>,
+    input: &mut Parser<'i, 't>,
+    context: Context,
+) -> SelectorResult<'i, PseudoClass>
+where
+    P: SelectorParser + ?Sized,
+{
+    let nth_kind = match_ignore_ascii_case! { &name,
+        "is" => return Ok(PseudoClass::Is(parse_forgiving_selector_list(parser, input, context.nested())?)),
+        "where" => return Ok(PseudoClass::Where(parse_forgiving_selector_list(parser, input, context.nested())?)),
+        "not" => return Ok(PseudoClass::Not(parse_selector_list(parser, input, context.nested())?)),
+        "has" => {
+            if context.in_has {
+                return Err(input.new_unexpected_token_error(Token::Function(name)));
+            }
+            return Ok(PseudoClass::Has(SelectorList::parse_relative(parser, input)?));
+        },
+        "lang" => {
+            let ranges = input.parse_comma_separated(|input| {
+                Ok::<_, ParseError<()>>(input.expect_ident_or_string()?.as_ref().to_owned())
+            })?;
+            return Ok(PseudoClass::Lang(ranges));
+        },
+        "dir" => {
+            let location = input.current_source_location();
+            let direction = input.expect_ident()?;
+            return match_ignore_ascii_case! { direction,
+                "ltr" => Ok(PseudoClass::Dir(Direction::Ltr)),
+                "rtl" => Ok(PseudoClass::Dir(Direction::Rtl)),
+                _ => Err(location.new_unexpected_token_error(Token::Ident(direction.clone()))),
+            };
+        },
+        "nth-child" => NthKind::Child,
+        "nth-last-child" => NthKind::LastChild,
+        "nth-of-type" => NthKind::OfType,
+        "nth-last-of-type" => NthKind::LastOfType,
+        _ => {
+            let name = name.to_ascii_lowercase();
+            let arguments = parser.parse_custom_functional_pseudo_class(&name, input)?;
+            return Ok(PseudoClass::Custom {
+                name,
+                arguments: Some(arguments),
+            });
+        }
+    };
+    let (an_plus_b, of) = match nth_kind {
+        NthKind::Child | NthKind::LastChild => AnPlusB::parse_with_of(input, |input| {
+            parse_selector_list(parser, input, context.nested()).map_err(ParseError::basic)
+        })?,
+        NthKind::OfType | NthKind::LastOfType => (AnPlusB::parse(input)?, None),
+    };
+    Ok(PseudoClass::Nth(NthSelector {
+        kind: nth_kind,
+        an_plus_b,
+        of,
+    }))
+}
+
+fn parse_functional_pseudo_element<'i, 't, P>(
+    parser: &P,
+    name: CowRcStr<'i>,
+    input: &mut Parser<'i, 't>,
+) -> SelectorResult<'i, PseudoElement>
+where
+    P: SelectorParser + ?Sized,
+{
+    match_ignore_ascii_case! { &name,
+        "highlight" => Ok(PseudoElement::Highlight(input.expect_ident()?.as_ref().to_owned())),
+        "part" => {
+            let mut names = vec![input.expect_ident()?.as_ref().to_owned()];
+            while let Ok(name) = input.try_parse(|input| input.expect_ident_cloned()) {
+                names.push(name.as_ref().to_owned());
+            }
+            Ok(PseudoElement::Part(names))
+        },
+        "slotted" => {
+            input.skip_whitespace();
+            let context = Context {
+                allow_pseudo_elements: false,
+                in_has: false,
+            };
+            let compound = parse_compound_selector(parser, input, context)?;
+            input.skip_whitespace();
+            Ok(PseudoElement::Slotted(compound))
+        },
+        _ => Err(input.new_error(BasicParseErrorKind::UnexpectedToken(Token::Function(name)))),
+    }
+}
+
+impl ToCss for SelectorList {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        for (i, selector) in self.0.iter().enumerate() {
+            if i > 0 {
+                dest.write_str(", ")?;
+            }
+            selector.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for Selector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.compounds[0].to_css(dest)?;
+        for (combinator, compound) in self.combinators.iter().zip(&self.compounds[1..]) {
+            combinator.to_css(dest)?;
+            compound.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for RelativeSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.combinator != Combinator::Descendant {
+            dest.write_str(self.combinator.as_str())?;
+            dest.write_char(' ')?;
+        }
+        self.selector.to_css(dest)
+    }
+}
+
+impl Combinator {
+    /// The combinator as written in CSS, without whitespace.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Combinator::Descendant => " ",
+            Combinator::Child => ">",
+            Combinator::NextSibling => "+",
+            Combinator::SubsequentSibling => "~",
+            Combinator::Column => "||",
+        }
+    }
+}
+
+impl ToCss for Combinator {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if *self == Combinator::Descendant {
+            return dest.write_char(' ');
+        }
+        dest.write_char(' ')?;
+        dest.write_str(self.as_str())?;
+        dest.write_char(' ')
+    }
+}
+
+impl ToCss for CompoundSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        // https://drafts.csswg.org/cssom/#serializing-selectors
+        // A universal selector without a prefix is omitted
+        // when it is not the only simple selector.
+        let mut simple_selectors = &self.0[..];
+        if let [SimpleSelector::Universal {
+            namespace: NamespaceConstraint::Implicit,
+        }, _, ..]
+        | [SimpleSelector::Universal {
+            namespace: NamespaceConstraint::Default(_),
+        }, _, ..] = simple_selectors
+        {
+            simple_selectors = &simple_selectors[1..];
+        }
+        for simple_selector in simple_selectors {
+            simple_selector.to_css(dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for NamespaceConstraint {
+    /// Serialize the namespace prefix, including the `|` separator if any.
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            NamespaceConstraint::Implicit | NamespaceConstraint::Default(_) => Ok(()),
+            NamespaceConstraint::Any => dest.write_str("*|"),
+            NamespaceConstraint::None => dest.write_char('|'),
+            NamespaceConstraint::Prefixed { ref prefix, .. } => {
+                serialize_identifier(prefix, dest)?;
+                dest.write_char('|')
+            }
+        }
+    }
+}
+
+impl ToCss for SimpleSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            SimpleSelector::Type {
+                ref namespace,
+                ref local_name,
+            } => {
+                namespace.to_css(dest)?;
+                serialize_identifier(local_name, dest)
+            }
+            SimpleSelector::Universal { ref namespace } => {
+                namespace.to_css(dest)?;
+                dest.write_char('*')
+            }
+            SimpleSelector::Id(ref id) => {
+                dest.write_char('#')?;
+                serialize_identifier(id, dest)
+            }
+            SimpleSelector::Class(ref class) => {
+                dest.write_char('.')?;
+                serialize_identifier(class, dest)
+            }
+            SimpleSelector::Attribute(ref attribute) => attribute.to_css(dest),
+            SimpleSelector::PseudoClass(ref pseudo_class) => pseudo_class.to_css(dest),
+            SimpleSelector::PseudoElement(ref pseudo_element) => pseudo_element.to_css(dest),
+        }
+    }
+}
+
+impl ToCss for AttributeSelector {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_char('[')?;
+        self.namespace.to_css(dest)?;
+        serialize_identifier(&self.local_name, dest)?;
+        if let Some((operator, ref value)) = self.operation {
+            dest.write_str(operator.as_str())?;
+            serialize_string(value, dest)?;
+            match self.case {
+                AttributeCase::Default => {}
+                AttributeCase::Insensitive => dest.write_str(" i")?,
+                AttributeCase::Sensitive => dest.write_str(" s")?,
+            }
+        }
+        dest.write_char(']')
+    }
+}
+
+impl ToCss for PseudoClass {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_char(':')?;
+        let (name, list) = match *self {
+            PseudoClass::Is(ref list) => ("is", list),
+            PseudoClass::Where(ref list) => ("where", list),
+            PseudoClass::Not(ref list) => ("not", list),
+            PseudoClass::Has(ref list) => {
+                dest.write_str("has(")?;
+                for (i, relative) in list.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    relative.to_css(dest)?;
+                }
+                return dest.write_char(')');
+            }
+            PseudoClass::Nth(ref nth) => {
+                dest.write_str(nth.kind.name())?;
+                dest.write_char('(')?;
+                nth.an_plus_b.to_css(dest)?;
+                if let Some(ref of) = nth.of {
+                    dest.write_str(" of ")?;
+                    of.to_css(dest)?;
+                }
+                return dest.write_char(')');
+            }
+            PseudoClass::FirstChild => return dest.write_str("first-child"),
+            PseudoClass::LastChild => return dest.write_str("last-child"),
+            PseudoClass::OnlyChild => return dest.write_str("only-child"),
+            PseudoClass::FirstOfType => return dest.write_str("first-of-type"),
+            PseudoClass::LastOfType => return dest.write_str("last-of-type"),
+            PseudoClass::OnlyOfType => return dest.write_str("only-of-type"),
+            PseudoClass::Root => return dest.write_str("root"),
+            PseudoClass::Empty => return dest.write_str("empty"),
+            PseudoClass::Scope => return dest.write_str("scope"),
+            PseudoClass::Lang(ref ranges) => {
+                dest.write_str("lang(")?;
+                for (i, range) in ranges.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    // Wildcards are not valid in identifiers.
+                    if range.contains('*') {
+                        serialize_string(range, dest)?;
+                    } else {
+                        serialize_identifier(range, dest)?;
+                    }
+                }
+                return dest.write_char(')');
+            }
+            PseudoClass::Dir(Direction::Ltr) => return dest.write_str("dir(ltr)"),
+            PseudoClass::Dir(Direction::Rtl) => return dest.write_str("dir(rtl)"),
+            PseudoClass::State(state) => return dest.write_str(state.name()),
+            PseudoClass::Custom {
+                ref name,
+                ref arguments,
+            } => {
+                serialize_identifier(name, dest)?;
+                if let Some(ref arguments) = *arguments {
+                    dest.write_char('(')?;
+                    dest.write_str(arguments)?;
+                    dest.write_char(')')?;
+                }
+                return Ok(());
+            }
+        };
+        dest.write_str(name)?;
+        dest.write_char('(')?;
+        list.to_css(dest)?;
+        dest.write_char(')')
+    }
+}
+
+impl ToCss for PseudoElement {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str("::")?;
+        match *self {
+            PseudoElement::Highlight(ref name) => {
+                dest.write_str("highlight(")?;
+                serialize_identifier(name, dest)?;
+            }
+            PseudoElement::Part(ref names) => {
+                dest.write_str("part(")?;
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_char(' ')?;
+                    }
+                    serialize_identifier(name, dest)?;
+                }
+            }
+            PseudoElement::Slotted(ref compound) => {
+                dest.write_str("slotted(")?;
+                compound.to_css(dest)?;
+            }
+            PseudoElement::Custom(ref name) => return serialize_identifier(name, dest),
+            _ => return dest.write_str(self.name()),
+        }
+        dest.write_char(')')
+    }
+}
@@ -400,6 +400,52 @@ macro_rules! impl_tocss_for_float {
 impl_tocss_for_float!(f32);
 impl_tocss_for_float!(f64);
 
+/// Serialize items with a separator between them, such as `", "`.
+pub(crate) fn write_separated<T: ToCss, W: fmt::Write>(
+    items: &[T],
+    separator: &str,
+    dest: &mut W,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            dest.write_str(separator)?;
+        }
+        item.to_css(dest)?;
+    }
+    Ok(())
+}
+
+/// Serializes the descriptors of an at-rule as a declaration list,
+/// such as `font-family: "a"; font-display: swap;`.
+pub(crate) struct DeclarationWriter<'a, W> {
+    dest: &'a mut W,
+    first: bool,
+}
+
+impl<'a, W> DeclarationWriter<'a, W>
+where
+    W: fmt::Write,
+{
+    pub(crate) fn new(dest: &'a mut W) -> Self {
+        DeclarationWriter { dest, first: true }
+    }
+
+    /// Write a `name: value;` declaration.
+    pub(crate) fn write<F>(&mut self, name: &str, value: F) -> fmt::Result
+    where
+        F: FnOnce(&mut W) -> fmt::Result,
+    {
+        if !self.first {
+            self.dest.write_char(' ')?;
+        }
+        self.first = false;
+        self.dest.write_str(name)?;
+        self.dest.write_str(": ")?;
+        value(self.dest)?;
+        self.dest.write_char(';')
+    }
+}
+
 /// A category of token. See the `needs_separator_when_before` method.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct TokenSerializationType(TokenSerializationTypeVariants);
@@ -45,7 +45,7 @@ size_of_test!(tokenizer, crate::tokenizer::Tokenizer, 72);
 size_of_test!(
     parser_input,
     crate::parser::ParserInput,
-    if cfg!(rustc_has_pr45225) { 136 } else { 144 }
+    if cfg!(rustc_has_pr45225) { 344 } else { 376 }
 );
 size_of_test!(parser, crate::parser::Parser, 16);
 size_of_test!(source_position, crate::SourcePosition, 8);
@@ -0,0 +1,202 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-conditional-4/#at-supports
+
+use std::fmt;
+
+use super::{
+    BasicParseError, Condition, CowRcStr, DeclarationParser, FontFormat, ParseError, Parser,
+    ParserInput, ToCss, Token,
+};
+use crate::serializer::serialize_identifier;
+
+/// A `<supports-condition>`, as found in the prelude of `@supports` rules.
+pub type SupportsCondition = Condition<SupportsFeature>;
+
+impl SupportsCondition {
+    /// Parse a `<supports-condition>`.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let result: Result<_, ParseError<()>> = Self::parse_with(input, true, &mut |input| {
+            SupportsFeature::parse(input).map_err(ParseError::from)
+        });
+        result.map_err(ParseError::basic)
+    }
+
+    /// Whether the condition is true, given a parser that tells what is supported.
+    ///
+    /// Per spec, `<general-enclosed>` evaluates to false here (not “unknown”),
+    /// so `not foo(bar)` is true.
+    pub fn is_supported<P>(&self, parser: &mut P) -> bool
+    where
+        P: for<'a> SupportsParser<'a>,
+    {
+        self.evaluate_with_general_enclosed(Some(false), &mut |feature| {
+            Some(feature.is_supported(parser))
+        })
+        .unwrap_or(false)
+    }
+}
+
+/// A test in a `<supports-condition>`.
+///
+/// Declaration values and selectors are kept as source text,
+/// and only parsed when evaluating, by a `SupportsParser`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SupportsFeature {
+    /// `( <declaration> )`
+    Declaration {
+        /// The property name, ASCII-lowercased unless it is a custom property.
+        name: String,
+        /// The value, with surrounding whitespace removed.
+        value: String,
+    },
+    /// `selector( <complex-selector> )`
+    Selector(String),
+    /// `font-tech( <font-tech> )`, ASCII-lowercased.
+    FontTech(String),
+    /// `font-format( <font-format> )`
+    FontFormat(FontFormat),
+}
+
+impl SupportsFeature {
+    /// Parse a `<supports-feature>`, including its parentheses or function name.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, BasicParseError<'i>> {
+        let location = input.current_source_location();
+        let function = match *input.next()? {
+            Token::ParenthesisBlock => None,
+            Token::Function(ref name) => Some(name.clone()),
+            ref token => return Err(location.new_basic_unexpected_token_error(token.clone())),
+        };
+        let result: Result<_, ParseError<()>> = input.parse_nested_block(|input| {
+            let function = match function {
+                Some(function) => function,
+                None => return Ok(Self::parse_declaration(input)?),
+            };
+            Ok(match_ignore_ascii_case! { &function,
+                "selector" => {
+                    input.skip_whitespace();
+                    let start = input.position();
+                    // The selector itself is only validated when evaluating.
+                    input.next()?;
+                    while input.next().is_ok() {}
+                    SupportsFeature::Selector(input.slice_from(start).trim_end().to_owned())
+                },
+                "font-tech" => {
+                    SupportsFeature::FontTech(input.expect_ident()?.to_ascii_lowercase())
+                },
+                "font-format" => SupportsFeature::FontFormat(FontFormat::parse(input)?),
+                _ => return Err(location.new_unexpected_token_error(Token::Function(function))),
+            })
+        });
+        result.map_err(ParseError::basic)
+    }
+
+    /// Parse a `<declaration>` without its parentheses, as in `@import ... supports(display: grid)`.
+    pub(crate) fn parse_declaration<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, BasicParseError<'i>> {
+        let name = input.expect_ident()?;
+        let name = if name.starts_with("--") {
+            name.to_string()
+        } else {
+            name.to_ascii_lowercase()
+        };
+        input.expect_colon()?;
+        input.skip_whitespace();
+        let start = input.position();
+        while input.next().is_ok() {}
+        let value = input.slice_from(start).trim_end().to_owned();
+        Ok(SupportsFeature::Declaration { name, value })
+    }
+
+    /// Whether this feature is supported, according to the given parser.
+    pub fn is_supported<P>(&self, parser: &mut P) -> bool
+    where
+        P: for<'a> SupportsParser<'a>,
+    {
+        match *self {
+            SupportsFeature::Declaration {
+                ref name,
+                ref value,
+            } => {
+                let mut input = ParserInput::new(value);
+                let mut input = Parser::new(&mut input);
+                input
+                    .parse_entirely(|input| parser.parse_value(CowRcStr::from(&**name), input))
+                    .is_ok()
+            }
+            SupportsFeature::Selector(ref selector) => {
+                let mut input = ParserInput::new(selector);
+                parser.supports_selector(&mut Parser::new(&mut input))
+            }
+            SupportsFeature::FontTech(ref tech) => parser.supports_font_tech(tech),
+            SupportsFeature::FontFormat(ref format) => {
+                parser.supports_font_format(&format.format.to_ascii_lowercase())
+            }
+        }
+    }
+}
+
+impl ToCss for SupportsFeature {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match *self {
+            SupportsFeature::Declaration {
+                ref name,
+                ref value,
+            } => {
+                dest.write_char('(')?;
+                serialize_identifier(name, dest)?;
+                dest.write_str(": ")?;
+                dest.write_str(value)?;
+            }
+            SupportsFeature::Selector(ref selector) => {
+                dest.write_str("selector(")?;
+                dest.write_str(selector)?;
+            }
+            SupportsFeature::FontTech(ref tech) => {
+                dest.write_str("font-tech(")?;
+                serialize_identifier(tech, dest)?;
+            }
+            SupportsFeature::FontFormat(ref format) => {
+                dest.write_str("font-format(")?;
+                format.to_css(dest)?;
+            }
+        }
+        dest.write_char(')')
+    }
+}
+
+/// The callbacks used to evaluate a `SupportsCondition`.
+///
+/// Declarations are tested with `DeclarationParser::parse_value`:
+/// a declaration is supported if its value parses without error
+/// (including a trailing `!important`, if `parse_value` accepts one).
+/// The default implementations of the other methods support nothing.
+pub trait SupportsParser<'i>: DeclarationParser<'i> {
+    /// Whether the given `<complex-selector>` is supported.
+    ///
+    /// `input` contains the arguments of `selector()`.
+    fn supports_selector<'t>(&mut self, input: &mut Parser<'i, 't>) -> bool {
+        let _ = input;
+        false
+    }
+
+    /// Whether the given `<font-tech>` keyword is supported, such as `color-colrv1`.
+    fn supports_font_tech(&mut self, tech: &str) -> bool {
+        let _ = tech;
+        false
+    }
+
+    /// Whether the given `<font-format>` keyword is supported, such as `woff2`.
+    ///
+    /// Formats given as strings are ASCII-lowercased too.
+    fn supports_font_format(&mut self, format: &str) -> bool {
+        let _ = format;
+        false
+    }
+}
@@ -0,0 +1,93 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! https://drafts.csswg.org/css-color-4/#css-system-colors
+
+use std::fmt;
+
+use super::{AbsoluteColor, ColorScheme, ToCss};
+
+macro_rules! system_colors {
+    ($($variant: ident => $name: expr,)+) => {
+        /// A system color keyword, whose value is chosen by the user agent or the user,
+        /// for example for a high-contrast theme.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum SystemColor {
+            $(
+                #[doc = $name]
+                $variant,
+            )+
+        }
+
+        impl SystemColor {
+            /// All the system colors.
+            pub const ALL: &'static [SystemColor] = &[$(SystemColor::$variant),+];
+
+            /// The system color with this name, ASCII case-insensitively.
+            ///
+            /// Deprecated system colors are mapped to their replacement:
+            /// https://drafts.csswg.org/css-color-4/#deprecated-system-colors
+            pub fn from_name(name: &str) -> Option<Self> {
+                Some(match_ignore_ascii_case! { name,
+                    $($name => SystemColor::$variant,)+
+                    "activeborder" | "inactiveborder" | "windowframe" => SystemColor::ButtonBorder,
+                    "threeddarkshadow" | "threedhighlight" => SystemColor::ButtonBorder,
+                    "threedlightshadow" | "threedshadow" => SystemColor::ButtonBorder,
+                    "buttonhighlight" | "buttonshadow" | "threedface" => SystemColor::ButtonFace,
+                    "activecaption" | "appworkspace" | "background" => SystemColor::Canvas,
+                    "inactivecaption" | "infobackground" | "menu" => SystemColor::Canvas,
+                    "scrollbar" | "window" => SystemColor::Canvas,
+                    "captiontext" | "infotext" | "menutext" | "windowtext" => SystemColor::CanvasText,
+                    "inactivecaptiontext" => SystemColor::GrayText,
+                    _ => return None,
+                })
+            }
+
+            /// The name of the system color, in lowercase.
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(SystemColor::$variant => $name,)+
+                }
+            }
+        }
+    }
+}
+
+system_colors! {
+    AccentColor => "accentcolor",
+    AccentColorText => "accentcolortext",
+    ActiveText => "activetext",
+    ButtonBorder => "buttonborder",
+    ButtonFace => "buttonface",
+    ButtonText => "buttontext",
+    Canvas => "canvas",
+    CanvasText => "canvastext",
+    Field => "field",
+    FieldText => "fieldtext",
+    GrayText => "graytext",
+    Highlight => "highlight",
+    HighlightText => "highlighttext",
+    LinkText => "linktext",
+    Mark => "mark",
+    MarkText => "marktext",
+    SelectedItem => "selecteditem",
+    SelectedItemText => "selecteditemtext",
+    VisitedText => "visitedtext",
+}
+
+impl ToCss for SystemColor {
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        dest.write_str(self.name())
+    }
+}
+
+/// The values of the system colors, such as those of the operating system
+/// or of a high-contrast theme, used to resolve a `Color`.
+pub trait ThemeProvider {
+    /// The value of a system color in the used color scheme.
+    fn system_color(&self, color: SystemColor, color_scheme: ColorScheme) -> AbsoluteColor;
+}
@@ -8,16 +8,30 @@ extern crate test;
 use encoding_rs;
 use matches::matches;
 use serde_json::{self, json, Map, Value};
+use std::fmt;
 
 #[cfg(feature = "bench")]
 use self::test::Bencher;
 
+use super::dom::Document;
+use super::matching::{ElementState, MatchingContext};
 use super::{
-    parse_important, parse_nth, parse_one_declaration, parse_one_rule, stylesheet_encoding,
-    AtRuleParser, AtRuleType, BasicParseError, BasicParseErrorKind, Color, CowRcStr,
-    DeclarationListParser, DeclarationParser, Delimiter, EncodingSupport, ParseError,
-    ParseErrorKind, Parser, ParserInput, ParserState, QualifiedRuleParser, RuleListParser,
-    SourceLocation, ToCss, Token, TokenSerializationType, UnicodeRange, RGBA,
+    parse_important, parse_nth, parse_one_declaration, parse_one_rule, resolve_imports,
+    stylesheet_encoding, AbsoluteColor, AnPlusB, AtRuleParser, AtRuleType, BasicParseError,
+    BasicParseErrorKind, ChannelCalc, Color, ColorComponentParser, ColorInterpolationMethod,
+    ColorScheme, ColorSpace, ContainerQuery, CounterStyle, CounterStyleName, CounterStyleRegistry,
+    CowRcStr, DataType, DeclarationListParser, DeclarationParser, Delimiter, Delimiters,
+    EncodingSupport, FontFace, FontStyleRange, FontWeightRange, GamutMapping,
+    HueInterpolationMethod, ImportError, ImportRule, Keyframe, KeyframeListParser,
+    KeyframeSelector, KeyframesName, LayerId, LayerOrder, LayerRule, LoadedStylesheet,
+    MediaEnvironment, MediaQueryList, NamespaceMap, NamespaceRule, NumberOrPercentage,
+    PageMarginBox, PagePseudoClass, PageRule, PageSelector, ParseError, ParseErrorKind, Parser,
+    ParserInput, ParserState, PropertyRule, PropertyRuleError, QualifiedRuleParser, QueryContainer,
+    RelativeColor, RelativeComponent, RuleListParser, ScopeBodyParser, ScopeRule, ScopedRuleParser,
+    SelectorList, SelectorParser, SourceLocation, Specificity, StatePseudoClass, StylesheetDecoder,
+    StylesheetLoader, SupportsCondition, SupportsParser, SyntaxComponent, SyntaxComponentName,
+    SyntaxDefinition, System, SystemColor, ThemeProvider, ToCss, Token, TokenSerializationType,
+    TopLevelRuleKind, TopLevelRuleOrder, UnicodeRange, RGBA,
 };
 
 macro_rules! JArray {
@@ -399,6 +413,68 @@ fn nth() {
     });
 }
 
+#[test]
+fn an_plus_b() {
+    for &(css, expected) in &[
+        ("odd", "2n+1"),
+        ("EVEN", "2n"),
+        ("+n", "n"),
+        ("-n+ 3", "-n+3"),
+        ("0n-0", "0"),
+        ("-7", "-7"),
+        ("3n - 1", "3n-1"),
+        ("-N-2", "-n-2"),
+        ("5000000000n+1", "2147483647n+1"),
+        ("-n-3000000000", "-n-2147483648"),
+        ("n-9999999999", "n-2147483648"),
+        ("n- 3000000000", "n-2147483648"),
+        ("-n - 2147483647", "-n-2147483647"),
+        ("2n + 3000000000", "2n+2147483647"),
+    ] {
+        let mut input = ParserInput::new(css);
+        let mut input = Parser::new(&mut input);
+        let value = AnPlusB::parse(&mut input).unwrap();
+        assert!(input.is_exhausted());
+        assert_eq!(value.to_css_string(), expected);
+        let mut input = ParserInput::new(expected);
+        assert_eq!(AnPlusB::parse(&mut Parser::new(&mut input)), Ok(value));
+    }
+
+    let matching = |a, b| {
+        (1..=10)
+            .filter(|&i| AnPlusB::new(a, b).matches(i))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(matching(2, 1), [1, 3, 5, 7, 9]);
+    assert_eq!(matching(-1, 3), [1, 2, 3]);
+    assert_eq!(matching(0, 4), [4]);
+    assert_eq!(matching(3, -2), [1, 4, 7, 10]);
+    assert_eq!(matching(-2, -1), [0; 0]);
+    assert_eq!(matching(i32::MAX, i32::MIN), [0; 0]);
+    assert!(AnPlusB::new(-1, i32::MAX).matches(1));
+    assert!(AnPlusB::new(i32::MIN, 0).matches(0));
+
+    let mut input = ParserInput::new("2n+1 of .a, b");
+    let mut input = Parser::new(&mut input);
+    let (value, rest) = AnPlusB::parse_with_of(&mut input, |input| {
+        let start = input.position();
+        while input.next().is_ok() {}
+        Ok(input.slice_from(start).trim())
+    })
+    .unwrap();
+    assert_eq!((value, rest), (AnPlusB::new(2, 1), Some(".a, b")));
+
+    let mut input = ParserInput::new("-n+3");
+    let result = AnPlusB::parse_with_of(&mut Parser::new(&mut input), |_| Ok(()));
+    assert_eq!(result, Ok((AnPlusB::new(-1, 3), None)));
+
+    let mut input = ParserInput::new("odd of");
+    let result = AnPlusB::parse_with_of(&mut Parser::new(&mut input), |input| {
+        input.expect_ident_cloned()
+    });
+    assert!(result.is_err());
+}
+
 #[test]
 fn unicode_range() {
     run_json_tests(include_str!("css-parsing-tests/urange.json"), |input| {
@@ -536,6 +612,686 @@ fn serialize_rgba_two_digit_float_if_roundtrips() {
     assert_eq!(c.to_css_string(), "rgba(0, 0, 0, 0.5)");
 }
 
+fn parse_color(css: &str) -> Option<Color> {
+    let mut input = ParserInput::new(css);
+    Parser::new(&mut input)
+        .parse_entirely(|input| Color::parse(input).map_err(Into::<ParseError<()>>::into))
+        .ok()
+}
+
+fn serialize_color(css: &str) -> Option<String> {
+    parse_color(css).map(|color| color.to_css_string())
+}
+
+#[test]
+fn color4() {
+    fn rgba(css: &str) -> Option<RGBA> {
+        parse_color(css)?.to_rgba()
+    }
+
+    assert_eq!(rgba("hwb(120 0% 0%)"), Some(RGBA::new(0, 255, 0, 255)));
+    assert_eq!(
+        rgba("hwb(0 60 60 / 50%)"),
+        Some(RGBA::new(128, 128, 128, 128))
+    );
+    assert_eq!(
+        serialize_color("hwb(none 100% 0%)").unwrap(),
+        "rgb(255, 255, 255)"
+    );
+    assert_eq!(serialize_color("hwb(0, 0%, 0%)"), None);
+
+    assert_eq!(
+        serialize_color("lab(50% 40 -20%)").unwrap(),
+        "lab(50 40 -25)"
+    );
+    assert_eq!(
+        serialize_color("LAB(120 0 0 / 0.5)").unwrap(),
+        "lab(100 0 0 / 0.5)"
+    );
+    assert_eq!(
+        serialize_color("lch(50 -10 400deg)").unwrap(),
+        "lch(50 0 40)"
+    );
+    assert_eq!(
+        serialize_color("lch(50 10 -0.00001)").unwrap(),
+        "lch(50 10 0)"
+    );
+    assert_eq!(
+        serialize_color("oklch(0.5 0.1 -1e-6)").unwrap(),
+        "oklch(0.5 0.1 0)"
+    );
+    assert_eq!(
+        serialize_color("oklab(50% none 0.1 / none)").unwrap(),
+        "oklab(0.5 none 0.1 / none)"
+    );
+    assert_eq!(
+        serialize_color("oklch(1 100% 0.5turn / 1)").unwrap(),
+        "oklch(1 0.4 180)"
+    );
+    assert_eq!(serialize_color("lab(50, 0, 0)"), None);
+    assert_eq!(serialize_color("lab(50 0)"), None);
+    assert_eq!(serialize_color("oklch(50% 0.1 20 /)"), None);
+
+    assert_eq!(
+        serialize_color("color(display-p3 1 0.5 none)").unwrap(),
+        "color(display-p3 1 0.5 none)"
+    );
+    assert_eq!(
+        serialize_color("color(xyz 0.5 50% 2 / 20%)").unwrap(),
+        "color(xyz-d65 0.5 0.5 2 / 0.2)"
+    );
+    assert_eq!(
+        serialize_color("color(srgb-linear 1 2 3)").unwrap(),
+        "color(srgb-linear 1 2 3)"
+    );
+    assert_eq!(serialize_color("color(lab 1 2 3)"), None);
+    assert_eq!(serialize_color("color(rec2020 1 2)"), None);
+
+    let white = RGBA::new(255, 255, 255, 255);
+    for css in &[
+        "lab(100 0 0)",
+        "lch(100 0 none)",
+        "oklab(1 0 0)",
+        "oklch(100% 0 0)",
+        "color(srgb 1 1 1)",
+        "color(srgb-linear 1 1 1)",
+        "color(display-p3 1 1 1)",
+        "color(a98-rgb 1 1 1)",
+        "color(prophoto-rgb 1 1 1)",
+        "color(rec2020 1 1 1)",
+        "color(xyz-d50 0.9642956764295677 1 0.8251046025104602)",
+        "color(xyz-d65 0.9504559270516716 1 1.0890577507598784)",
+    ] {
+        assert_eq!(rgba(css), Some(white), "{}", css);
+    }
+    assert_eq!(
+        rgba("lab(54.29 80.82 69.88)"),
+        Some(RGBA::new(255, 0, 0, 255))
+    );
+    assert_eq!(
+        rgba("oklch(62.8% 0.2577 29.23)"),
+        Some(RGBA::new(255, 0, 0, 255))
+    );
+    assert_eq!(
+        rgba("color(display-p3 0 1 0)"),
+        Some(RGBA::new(0, 255, 0, 255))
+    );
+    assert_eq!(rgba("currentcolor"), None);
+
+    let red = AbsoluteColor::from(RGBA::new(255, 0, 0, 255));
+    let oklch = red.to_space(ColorSpace::Oklch).resolved_components();
+    assert!((oklch[0] - 0.628).abs() < 1e-3);
+    assert!((oklch[1] - 0.2577).abs() < 1e-3);
+    assert!((oklch[2] - 29.23).abs() < 1e-2);
+    let gray = AbsoluteColor::from(RGBA::new(128, 128, 128, 255));
+    assert_eq!(gray.to_space(ColorSpace::Lch).components[2], None);
+    assert_eq!(gray.to_space(ColorSpace::Hsl).components[0], None);
+    assert_eq!(
+        gray.to_space(ColorSpace::Oklab).to_rgba(),
+        RGBA::new(128, 128, 128, 255)
+    );
+    let lab = AbsoluteColor::new(ColorSpace::Lab, [50., 10., -1e-6], 1.);
+    assert_eq!(lab.to_space(ColorSpace::Lch).components[2], Some(0.));
+    let oklab = AbsoluteColor::new(ColorSpace::Oklab, [0.5, 0.1, -1e-8], 1.);
+    assert_eq!(oklab.to_space(ColorSpace::Oklch).components[2], Some(0.));
+}
+
+#[test]
+fn color_mix() {
+    assert_eq!(
+        serialize_color("color-mix(in srgb, red, blue)").unwrap(),
+        "color(srgb 0.5 0 0.5)"
+    );
+    assert_eq!(
+        serialize_color("color-mix(in srgb, 25% red, blue)").unwrap(),
+        "color(srgb 0.25 0 0.75)"
+    );
+    assert_eq!(
+        serialize_color("color-mix(in srgb, red 60%, blue 60%)").unwrap(),
+        "color(srgb 0.5 0 0.5)"
+    );
+    assert_eq!(
+        serialize_color("color-mix(in srgb, red 20%, blue 20%)").unwrap(),
+        "color(srgb 0.5 0 0.5 / 0.4)"
+    );
+    // Premultiplied alpha.
+    assert_eq!(
+        serialize_color("color-mix(in srgb, rgb(255 0 0 / 0), blue)").unwrap(),
+        "color(srgb 0 0 1 / 0.5)"
+    );
+    assert_eq!(
+        serialize_color(
+            "color-mix(in xyz, color(xyz 0.2 0.4 0.6 / 0.5), color(xyz-d65 0.6 0.8 0.2))"
+        )
+        .unwrap(),
+        "color(xyz-d65 0.466667 0.666667 0.333333 / 0.75)"
+    );
+
+    // Hue interpolation methods.
+    let hues = [
+        ("", 10.),
+        (" shorter hue", 10.),
+        (" longer hue", 190.),
+        (" increasing hue", 10.),
+        (" decreasing hue", 190.),
+    ];
+    for &(method, hue) in &hues {
+        let css = format!(
+            "color-mix(in oklch{}, oklch(0.5 0.1 340), oklch(0.5 0.1 40))",
+            method
+        );
+        let color = parse_color(&css).unwrap().to_absolute().unwrap();
+        assert!((color.components[2].unwrap() - hue).abs() < 1e-3, "{}", css);
+    }
+    assert_eq!(
+        serialize_color("color-mix(in lch decreasing hue, lch(50 10 20), lch(50 10 80))").unwrap(),
+        "lch(50 10 230)"
+    );
+
+    // Missing components take the value of the other color,
+    // and are carried forward to analogous components.
+    assert_eq!(
+        serialize_color("color-mix(in oklch, oklch(0.5 0.1 none), oklch(none 0.3 40 / none))")
+            .unwrap(),
+        "oklch(0.5 0.2 40)"
+    );
+    assert_eq!(
+        serialize_color(
+            "color-mix(in oklch, oklch(none none none / none), oklch(none none none / none))"
+        )
+        .unwrap(),
+        "oklch(none none none / none)"
+    );
+    assert!(
+        serialize_color("color-mix(in oklch, white, oklch(0.5 0.1 40))")
+            .unwrap()
+            .ends_with(" 40)")
+    );
+    assert!(
+        serialize_color("color-mix(in lab, lch(none 10 20), lab(50 0 0))")
+            .unwrap()
+            .starts_with("lab(50 ")
+    );
+
+    // Mixes with currentcolor are kept unresolved.
+    let mix = parse_color("color-mix(in oklch longer hue, currentcolor 30%, red)").unwrap();
+    assert_eq!(
+        mix.to_css_string(),
+        "color-mix(in oklch longer hue, currentcolor 30%, rgb(255, 0, 0))"
+    );
+    assert_eq!(mix.to_rgba(), None);
+    let current_color = AbsoluteColor::from(RGBA::new(255, 0, 0, 255));
+    let mix = parse_color("color-mix(in srgb, currentcolor, blue)").unwrap();
+    assert_eq!(
+        mix.resolve(&current_color, &TestTheme, ColorScheme::Light)
+            .to_rgba(),
+        RGBA::new(128, 0, 128, 255)
+    );
+
+    assert_eq!(serialize_color("color-mix(in srgb, red 0%, blue 0%)"), None);
+    assert_eq!(serialize_color("color-mix(in srgb, red 120%, blue)"), None);
+    assert_eq!(
+        serialize_color("color-mix(in srgb, red 10% 10%, blue)"),
+        None
+    );
+    assert_eq!(
+        serialize_color("color-mix(in srgb longer hue, red, blue)"),
+        None
+    );
+    assert_eq!(serialize_color("color-mix(in hsl, red, blue, green)"), None);
+    assert_eq!(serialize_color("color-mix(srgb, red, blue)"), None);
+
+    let method = ColorInterpolationMethod {
+        space: ColorSpace::Srgb,
+        hue: HueInterpolationMethod::Shorter,
+    };
+    let red = Color::RGBA(RGBA::new(255, 0, 0, 255));
+    let blue = Color::RGBA(RGBA::new(0, 0, 255, 255));
+    assert_eq!(
+        red.interpolate(&blue, 0.25, method).unwrap().to_rgba(),
+        RGBA::new(191, 0, 64, 255)
+    );
+    assert_eq!(red.interpolate(&Color::CurrentColor, 0.5, method), None);
+}
+
+#[test]
+fn relative_colors() {
+    assert_eq!(
+        serialize_color("rgb(from rgb(10 20 30) r g calc(b + 20) / 50%)").unwrap(),
+        "color(srgb 0.0392157 0.0784314 0.196078 / 0.5)"
+    );
+    assert_eq!(
+        serialize_color("rgb(from #102030 b g r)").unwrap(),
+        "color(srgb 0.188235 0.12549 0.0627451)"
+    );
+    assert_eq!(
+        serialize_color("rgb(from rgb(10 20 30 / 0.5) 255 calc((r + g) * 2) none)").unwrap(),
+        "color(srgb 1 0.235294 none / 0.501961)"
+    );
+    assert_eq!(
+        serialize_color("lab(from lab(50 10 -20 / 0.4) l a b / calc(alpha * 2))").unwrap(),
+        "lab(50 10 -20 / 0.8)"
+    );
+    assert_eq!(
+        serialize_color("lch(from lch(50 10 20) l calc(c / 2) calc(h + 350))").unwrap(),
+        "lch(50 5 10)"
+    );
+    assert_eq!(
+        serialize_color("color(from color(display-p3 0.2 0.4 0.6) display-p3 g r b)").unwrap(),
+        "color(display-p3 0.4 0.2 0.6)"
+    );
+    assert_eq!(
+        serialize_color("color(from color(xyz 0.2 0.4 0.6) xyz-d65 calc(z - x) y x / 25%)")
+            .unwrap(),
+        "color(xyz-d65 0.4 0.4 0.2 / 0.25)"
+    );
+    assert_eq!(
+        serialize_color("hsl(from rgb(255 0 0) calc(h + 120) s l)").unwrap(),
+        "color(srgb 0 1 0)"
+    );
+    assert_eq!(
+        parse_color("hwb(from red h w b)").unwrap().to_rgba(),
+        Some(RGBA::new(255, 0, 0, 255))
+    );
+    let oklch = parse_color("oklch(from red l c h)")
+        .unwrap()
+        .to_absolute()
+        .unwrap();
+    assert!((oklch.components[0].unwrap() - 0.628).abs() < 1e-3);
+    assert_eq!(oklch.to_rgba(), RGBA::new(255, 0, 0, 255));
+
+    assert_eq!(serialize_color("rgb(from red r g)"), None);
+    assert_eq!(serialize_color("rgb(from red r, g, b)"), None);
+    assert_eq!(serialize_color("rgb(from red l c h)"), None);
+    assert_eq!(serialize_color("rgb(from red r g calc(b +20))"), None);
+    assert_eq!(serialize_color("rgb(from r g b)"), None);
+    assert_eq!(serialize_color("color(from red lab l a b)"), None);
+    assert_eq!(serialize_color("color-mix(from red, red, blue)"), None);
+    assert_eq!(serialize_color("rgb(r g b)"), None);
+
+    // Relative colors from currentcolor are kept unresolved.
+    let relative = parse_color("oklch(from currentcolor l c calc(h + 180) / alpha)").unwrap();
+    assert_eq!(
+        relative.to_css_string(),
+        "oklch(from currentcolor l c calc(h + 180))"
+    );
+    assert_eq!(relative.to_rgba(), None);
+    let current_color = AbsoluteColor::from(RGBA::new(255, 0, 0, 128));
+    let resolved = relative.resolve(&current_color, &TestTheme, ColorScheme::Light);
+    assert!((resolved.components[2].unwrap() - 209.23).abs() < 1e-2);
+    assert_eq!(resolved.alpha, Some(128. / 255.));
+    let relative =
+        parse_color("color(from color-mix(in srgb, currentcolor, blue) srgb b g r)").unwrap();
+    assert_eq!(
+        relative.to_css_string(),
+        "color(from color-mix(in srgb, currentcolor, rgb(0, 0, 255)) srgb b g r)"
+    );
+    assert_eq!(
+        relative
+            .resolve(&current_color, &TestTheme, ColorScheme::Light)
+            .to_rgba(),
+        RGBA::new(170, 0, 85, 192)
+    );
+    assert_eq!(serialize_color("rgb(from currentcolor r g)"), None);
+
+    // Unresolved relative colors serialize canonically.
+    for &(css, expected) in &[
+        (
+            "rgb(from currentcolor  R /* red */ calc( (g + 1)*2 ) 50% / 25%)",
+            "rgb(from currentcolor r calc((g + 1) * 2) 127.5 / 0.25)",
+        ),
+        (
+            "lch(from currentcolor calc(l - (c - h)) none 1turn / none)",
+            "lch(from currentcolor calc(l - (c - h)) none 0 / none)",
+        ),
+        (
+            "color(from currentcolor srgb-linear calc(calc(r) / 2 * b) calc(1) b)",
+            "color(from currentcolor srgb-linear calc(r / 2 * b) calc(1) b)",
+        ),
+    ] {
+        let relative = parse_color(css).unwrap();
+        assert_eq!(relative.to_css_string(), expected);
+        assert_eq!(parse_color(expected).unwrap(), relative);
+    }
+
+    // Components are checked with the component parser of the caller.
+    struct NoPercentages;
+    impl<'i> ColorComponentParser<'i> for NoPercentages {
+        type Error = ();
+        fn parse_number_or_percentage<'t>(
+            &self,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<NumberOrPercentage, ParseError<'i, ()>> {
+            let value = input.expect_number()?;
+            Ok(NumberOrPercentage::Number { value })
+        }
+    }
+    for css in &["rgb(from currentcolor r g 50%)", "rgb(from red r g 50%)"] {
+        let mut input = ParserInput::new(css);
+        let mut input = Parser::new(&mut input);
+        assert!(Color::parse_with(&NoPercentages, &mut input).is_err());
+        assert!(parse_color(css).is_some());
+    }
+
+    // Channel names of another color space are zero.
+    let relative = RelativeColor {
+        origin: Color::CurrentColor,
+        space: ColorSpace::Srgb,
+        color_function: false,
+        components: [
+            RelativeComponent::Number(255.),
+            RelativeComponent::Calc(ChannelCalc::Channel("h")),
+            RelativeComponent::None,
+        ],
+        alpha: RelativeComponent::Number(0.5),
+    };
+    assert_eq!(
+        relative
+            .resolve(&current_color, &TestTheme, ColorScheme::Light)
+            .to_css_string(),
+        "color(srgb 1 0 none / 0.5)"
+    );
+}
+
+/// Black text on white, or white text on black in the dark color scheme, with red links.
+struct TestTheme;
+
+impl ThemeProvider for TestTheme {
+    fn system_color(&self, color: SystemColor, color_scheme: ColorScheme) -> AbsoluteColor {
+        let (background, foreground) = match color_scheme {
+            ColorScheme::Light => (RGBA::new(255, 255, 255, 255), RGBA::new(0, 0, 0, 255)),
+            ColorScheme::Dark => (RGBA::new(0, 0, 0, 255), RGBA::new(255, 255, 255, 255)),
+        };
+        let rgba = match color {
+            SystemColor::Canvas | SystemColor::Field => background,
+            SystemColor::LinkText => RGBA::new(255, 0, 0, 255),
+            _ => foreground,
+        };
+        rgba.into()
+    }
+}
+
+#[test]
+fn system_colors() {
+    for &color in SystemColor::ALL {
+        assert_eq!(SystemColor::from_name(color.name()), Some(color));
+        assert_eq!(parse_color(color.name()), Some(Color::System(color)));
+    }
+    assert_eq!(
+        parse_color("CanvasText"),
+        Some(Color::System(SystemColor::CanvasText))
+    );
+    assert_eq!(
+        parse_color("CanvasText").unwrap().to_css_string(),
+        "canvastext"
+    );
+    assert_eq!(parse_color("canvas-text"), None);
+
+    let deprecated = [
+        ("ActiveBorder", SystemColor::ButtonBorder),
+        ("ActiveCaption", SystemColor::Canvas),
+        ("AppWorkspace", SystemColor::Canvas),
+        ("Background", SystemColor::Canvas),
+        ("ButtonHighlight", SystemColor::ButtonFace),
+        ("ButtonShadow", SystemColor::ButtonFace),
+        ("CaptionText", SystemColor::CanvasText),
+        ("InactiveBorder", SystemColor::ButtonBorder),
+        ("InactiveCaption", SystemColor::Canvas),
+        ("InactiveCaptionText", SystemColor::GrayText),
+        ("InfoBackground", SystemColor::Canvas),
+        ("InfoText", SystemColor::CanvasText),
+        ("Menu", SystemColor::Canvas),
+        ("MenuText", SystemColor::CanvasText),
+        ("Scrollbar", SystemColor::Canvas),
+        ("ThreeDDarkShadow", SystemColor::ButtonBorder),
+        ("ThreeDFace", SystemColor::ButtonFace),
+        ("ThreeDHighlight", SystemColor::ButtonBorder),
+        ("ThreeDLightShadow", SystemColor::ButtonBorder),
+        ("ThreeDShadow", SystemColor::ButtonBorder),
+        ("Window", SystemColor::Canvas),
+        ("WindowFrame", SystemColor::ButtonBorder),
+        ("WindowText", SystemColor::CanvasText),
+    ];
+    for &(name, color) in &deprecated {
+        assert_eq!(parse_color(name), Some(Color::System(color)), "{}", name);
+    }
+
+    let current_color = AbsoluteColor::from(RGBA::new(0, 0, 255, 255));
+    let resolve = |css: &str| {
+        let color = parse_color(css).unwrap();
+        assert_eq!(color.to_rgba(), None, "{}", css);
+        color
+            .resolve(&current_color, &TestTheme, ColorScheme::Light)
+            .to_rgba()
+    };
+    assert_eq!(resolve("LinkText"), RGBA::new(255, 0, 0, 255));
+    assert_eq!(resolve("window"), RGBA::new(255, 255, 255, 255));
+    assert_eq!(
+        resolve("color-mix(in srgb, canvas, canvastext)"),
+        RGBA::new(128, 128, 128, 255)
+    );
+    assert_eq!(
+        resolve("rgb(from LinkText b g r)"),
+        RGBA::new(0, 0, 255, 255)
+    );
+    assert_eq!(
+        parse_color("color-mix(in srgb, Field 25%, currentcolor)")
+            .unwrap()
+            .to_css_string(),
+        "color-mix(in srgb, field 25%, currentcolor)"
+    );
+}
+
+#[test]
+fn light_dark() {
+    let current_color = AbsoluteColor::from(RGBA::new(0, 0, 255, 255));
+    let resolve = |css: &str, color_scheme| {
+        let color = parse_color(css).unwrap();
+        assert_eq!(color.to_rgba(), None, "{}", css);
+        color
+            .resolve(&current_color, &TestTheme, color_scheme)
+            .to_rgba()
+    };
+
+    let color = parse_color("light-dark(white, #000)").unwrap();
+    assert_eq!(
+        color.to_css_string(),
+        "light-dark(rgb(255, 255, 255), rgb(0, 0, 0))"
+    );
+    if let Color::LightDark(ref light_dark) = color {
+        assert_eq!(
+            light_dark.select(ColorScheme::Dark),
+            &Color::RGBA(RGBA::new(0, 0, 0, 255))
+        );
+    } else {
+        panic!("{:?}", color)
+    }
+    assert_eq!(
+        resolve("light-dark(white, #000)", ColorScheme::Light),
+        RGBA::new(255, 255, 255, 255)
+    );
+    assert_eq!(
+        resolve("light-dark(white, #000)", ColorScheme::Dark),
+        RGBA::new(0, 0, 0, 255)
+    );
+    assert_eq!(
+        resolve("light-dark(currentcolor, lab(50 0 0))", ColorScheme::Light),
+        RGBA::new(0, 0, 255, 255)
+    );
+    assert_eq!(
+        resolve("LIGHT-DARK(Canvas, Canvas)", ColorScheme::Dark),
+        RGBA::new(0, 0, 0, 255)
+    );
+    assert_eq!(
+        resolve(
+            "light-dark(light-dark(red, lime), blue)",
+            ColorScheme::Light
+        ),
+        RGBA::new(255, 0, 0, 255)
+    );
+    assert_eq!(
+        resolve(
+            "color-mix(in srgb, light-dark(white, black), blue)",
+            ColorScheme::Dark
+        ),
+        RGBA::new(0, 0, 128, 255)
+    );
+    assert_eq!(
+        resolve(
+            "rgb(from light-dark(white, black) r 0 0)",
+            ColorScheme::Light
+        ),
+        RGBA::new(255, 0, 0, 255)
+    );
+    assert_eq!(
+        parse_color("color-mix(in srgb, light-dark(white, black), blue)")
+            .unwrap()
+            .to_css_string(),
+        "color-mix(in srgb, light-dark(rgb(255, 255, 255), rgb(0, 0, 0)), rgb(0, 0, 255))"
+    );
+
+    assert_eq!(parse_color("light-dark(white)"), None);
+    assert_eq!(parse_color("light-dark(white black)"), None);
+    assert_eq!(parse_color("light-dark(white, black, red)"), None);
+}
+
+#[test]
+fn contrast() {
+    let black = RGBA::new(0, 0, 0, 255);
+    let white = RGBA::new(255, 255, 255, 255);
+    let gray = RGBA::new(0x77, 0x77, 0x77, 255);
+
+    assert_eq!(black.linear_rgb(), [0., 0., 0.]);
+    assert_eq!(white.linear_rgb(), [1., 1., 1.]);
+    let [red, _, _] = RGBA::new(128, 0, 0, 255).linear_rgb();
+    assert!((red - 0.2158605).abs() < 1e-6);
+    assert_eq!(white.relative_luminance(), 1.);
+    assert!((RGBA::new(0, 255, 0, 255).relative_luminance() - 0.7152).abs() < 1e-6);
+
+    assert_eq!(black.contrast_ratio(&white), 21.);
+    assert_eq!(white.contrast_ratio(&black), 21.);
+    assert_eq!(gray.contrast_ratio(&gray), 1.);
+    assert!((gray.contrast_ratio(&white) - 4.48).abs() < 0.01);
+
+    assert!((black.apca_contrast(&white) - 106.04).abs() < 0.01);
+    assert!((white.apca_contrast(&black) + 107.88).abs() < 0.01);
+    assert!((gray.apca_contrast(&white) - 71.11).abs() < 0.01);
+    assert_eq!(gray.apca_contrast(&gray), 0.);
+
+    let translucent_red = RGBA::new(255, 0, 0, 128);
+    assert_eq!(translucent_red.over(&white), RGBA::new(255, 127, 127, 255));
+    assert_eq!(translucent_red.over(&black), RGBA::new(128, 0, 0, 255));
+    assert_eq!(white.over(&translucent_red), white);
+    assert_eq!(
+        translucent_red.over(&RGBA::new(0, 0, 255, 128)),
+        RGBA::new(170, 0, 85, 192)
+    );
+    assert_eq!(
+        RGBA::transparent().over(&RGBA::transparent()),
+        RGBA::transparent()
+    );
+
+    assert_eq!(black.nearest_with_contrast(&white, 7.), Some(black));
+    let darker = gray.nearest_with_contrast(&white, 4.5).unwrap();
+    assert!(darker.contrast_ratio(&white) >= 4.5);
+    assert_eq!(darker, RGBA::new(0x76, 0x76, 0x76, 255));
+    let lighter = gray.nearest_with_contrast(&black, 7.).unwrap();
+    assert!(lighter.contrast_ratio(&black) >= 7.);
+    assert_eq!(lighter, RGBA::new(149, 149, 149, 255));
+    let blue = RGBA::new(80, 80, 255, 255)
+        .nearest_with_contrast(&gray, 4.5)
+        .unwrap();
+    assert!(blue.contrast_ratio(&gray) >= 4.5);
+    assert!(blue.blue > blue.red);
+    assert_eq!(gray.nearest_with_contrast(&gray, 5.), None);
+    assert_eq!(black.nearest_with_contrast(&white, 22.), None);
+}
+
+#[test]
+fn gamut_mapping() {
+    let parse = |css| {
+        let color = Color::parse(&mut Parser::new(&mut ParserInput::new(css))).unwrap();
+        color.to_absolute().unwrap()
+    };
+    let to_css = |color: AbsoluteColor| color.to_css_string();
+
+    assert!(!ColorSpace::Oklch.is_bounded());
+    assert!(ColorSpace::Hwb.is_bounded());
+    assert!(parse("color(srgb 0 1 0.5)").in_gamut());
+    assert!(!parse("color(srgb 0 1.01 0.5)").in_gamut());
+    assert!(parse("lab(100 200 200)").in_gamut());
+    assert!(!parse("color(display-p3 0 1 0)")
+        .to_space(ColorSpace::Srgb)
+        .in_gamut());
+    assert_eq!(
+        to_css(parse("color(srgb -0.5 1.5 0.5 / 0.5)").clip()),
+        "color(srgb 0 1 0.5 / 0.5)"
+    );
+    assert_eq!(parse("red").delta_e_ok(&parse("red")), 0.);
+    assert!((parse("black").delta_e_ok(&parse("white")) - 1.).abs() < 1e-4);
+
+    // In gamut colors and unbounded spaces are only converted.
+    for &mapping in &[GamutMapping::Css, GamutMapping::Clip] {
+        let teal = parse("color(srgb 0 0.5 0.5)");
+        assert_eq!(
+            teal.to_gamut(ColorSpace::DisplayP3, mapping),
+            teal.to_space(ColorSpace::DisplayP3)
+        );
+        let green = parse("color(display-p3 0 1 0)");
+        assert_eq!(
+            green.to_gamut(ColorSpace::Oklch, mapping),
+            green.to_space(ColorSpace::Oklch)
+        );
+    }
+
+    // Clipping shifts the hue, chroma reduction keeps it.
+    let green = parse("color(display-p3 0 1 0)");
+    let hue = |color: AbsoluteColor| color.to_space(ColorSpace::Oklch).resolved_components()[2];
+    let clipped = green.to_gamut(ColorSpace::Srgb, GamutMapping::Clip);
+    assert_eq!(to_css(clipped), "color(srgb 0 1 0)");
+    let mapped = green.to_gamut(ColorSpace::Srgb, GamutMapping::Css);
+    assert!(mapped.in_gamut());
+    assert!((hue(mapped) - hue(green)).abs() < (hue(clipped) - hue(green)).abs());
+    assert_eq!(to_css(mapped), "color(srgb 0 0.985764 0.159742)");
+    assert_eq!(
+        green.to_rgba_in_gamut(GamutMapping::Clip),
+        RGBA::new(0, 255, 0, 255)
+    );
+    assert_eq!(green.to_rgba_in_gamut(GamutMapping::Css), mapped.to_rgba());
+    assert_eq!(
+        green.to_rgba_in_gamut(GamutMapping::Css),
+        RGBA::new(0, 251, 41, 255)
+    );
+
+    // Too light or too dark colors map to white or black.
+    assert_eq!(
+        parse("oklch(1 0.4 120 / 0.5)").to_rgba_in_gamut(GamutMapping::Css),
+        RGBA::new(255, 255, 255, 128)
+    );
+    assert_eq!(
+        parse("oklch(0 0.2 300)").to_rgba_in_gamut(GamutMapping::Css),
+        RGBA::new(0, 0, 0, 255)
+    );
+    assert_eq!(
+        to_css(parse("oklch(0 0.4 120)").to_gamut(ColorSpace::Hsl, GamutMapping::Css)),
+        "rgb(0, 0, 0)"
+    );
+
+    let color = |css| Color::parse(&mut Parser::new(&mut ParserInput::new(css))).unwrap();
+    assert_eq!(
+        color("#123").to_rgba_in_gamut(GamutMapping::Css),
+        Some(RGBA::new(0x11, 0x22, 0x33, 255))
+    );
+    assert_eq!(
+        color("oklch(0.7 0.4 30)").to_rgba_in_gamut(GamutMapping::Css),
+        Some(parse("oklch(0.7 0.4 30)").to_rgba_in_gamut(GamutMapping::Css))
+    );
+    assert_eq!(
+        color("currentcolor").to_rgba_in_gamut(GamutMapping::Css),
+        None
+    );
+}
+
 #[test]
 fn line_numbers() {
     let mut input = ParserInput::new(concat!(
@@ -826,6 +1582,11 @@ impl ToJson for Color {
         match *self {
             Color::RGBA(ref rgba) => json!([rgba.red, rgba.green, rgba.blue, rgba.alpha]),
             Color::CurrentColor => "currentcolor".to_json(),
+            Color::System(ref color) => color.name().to_json(),
+            Color::LightDark(_) => self.to_css_string().to_json(),
+            Color::Absolute(_) | Color::ColorMix(_) | Color::Relative(_) => {
+                self.to_css_string().to_json()
+            }
         }
     }
 }
@@ -1171,6 +1932,1641 @@ fn parse_until_before_stops_at_delimiter_or_end_of_input() {
     }
 }
 
+#[test]
+fn list_combinators() {
+    let mut input = ParserInput::new("12px/1.5 serif");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> =
+        parser.parse_slash_separated(|input| Ok(input.next()?.clone()));
+    assert_eq!(
+        result.unwrap(),
+        vec![
+            Token::Dimension {
+                has_sign: false,
+                value: 12.,
+                int_value: Some(12),
+                unit: "px".into()
+            },
+            Token::Number {
+                has_sign: false,
+                value: 1.5,
+                int_value: None
+            },
+        ]
+    );
+    assert_eq!(parser.expect_ident_cloned(), Ok("serif".into()));
+
+    let mut input = ParserInput::new("1 2 3 4 5");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> =
+        parser.parse_repeated(1, Some(4), |input| Ok(input.expect_integer()?));
+    assert_eq!(result.unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(parser.expect_integer(), Ok(5));
+
+    let mut input = ParserInput::new("1 a");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> =
+        parser.parse_repeated(2, None, |input| Ok(input.expect_integer()?));
+    assert_eq!(
+        result.map_err(|e| e.kind),
+        Err(ParseErrorKind::Basic(BasicParseErrorKind::UnexpectedToken(
+            Token::Ident("a".into())
+        )))
+    );
+
+    let mut input = ParserInput::new("red solid 3");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> = parser.parse_any_order(3, |index, input| {
+        match index {
+            0 => input.expect_ident_matching("solid")?,
+            1 => drop(Color::parse(input)?),
+            _ => input.expect_ident_matching("none")?,
+        }
+        Ok(index)
+    });
+    assert_eq!(result.unwrap(), vec![Some(0), Some(1), None]);
+    assert!(parser
+        .parse_optional(|input| input.expect_ident_cloned())
+        .is_none());
+    assert_eq!(parser.expect_integer(), Ok(3));
+}
+
+#[test]
+fn peek_does_not_consume() {
+    let mut input = ParserInput::new("a f(b, c) [d] e; g");
+    let mut parser = Parser::new(&mut input);
+    assert_eq!(parser.peek(), Ok(&Token::Ident("a".into())));
+    assert_eq!(parser.peek_nth(1), Ok(&Token::Function("f".into())));
+    assert_eq!(parser.peek_nth(2), Ok(&Token::SquareBracketBlock));
+    assert_eq!(parser.peek_nth(3), Ok(&Token::Ident("e".into())));
+    assert_eq!(parser.next(), Ok(&Token::Ident("a".into())));
+    assert_eq!(parser.next(), Ok(&Token::Function("f".into())));
+    assert_eq!(parser.peek(), Ok(&Token::SquareBracketBlock));
+
+    let result: Result<(), ParseError<()>> =
+        parser.parse_until_after(Delimiter::Semicolon, |input| {
+            assert_eq!(input.peek_nth(1), Ok(&Token::Ident("e".into())));
+            assert!(input.peek_nth(2).is_err());
+            input.expect_square_bracket_block()?;
+            input.parse_nested_block(|input| {
+                assert_eq!(input.peek(), Ok(&Token::Ident("d".into())));
+                assert!(input.peek_nth(1).is_err());
+                input.expect_ident_matching("d")?;
+                Ok(())
+            })?;
+            input.expect_ident_matching("e")?;
+            Ok(())
+        });
+    assert!(result.is_ok());
+    assert_eq!(parser.peek(), Ok(&Token::Ident("g".into())));
+    assert_eq!(parser.next(), Ok(&Token::Ident("g".into())));
+    assert!(parser.peek().is_err());
+}
+
+#[test]
+fn peek_nth_caches_tokens_across_whitespace() {
+    let tokenized = || crate::parser::TOKENIZED.with(|tokenized| tokenized.get());
+    let start = tokenized();
+    let mut input = ParserInput::new("a b c d e");
+    let mut parser = Parser::new(&mut input);
+    for name in &["a", "b", "c", "d", "e"] {
+        let _ = parser.peek_nth(2);
+        assert_eq!(
+            parser.next_including_whitespace(),
+            Ok(&Token::Ident((*name).into()))
+        );
+        let _ = parser.next_including_whitespace();
+    }
+    // Each identifier and whitespace token was tokenized once.
+    assert_eq!(tokenized() - start, 9);
+}
+
+#[test]
+fn parse_until_custom_delimiters() {
+    fn idents_until<'i>(input: &mut Parser<'i, '_>) -> Result<Vec<String>, ParseError<'i, ()>> {
+        let mut idents = Vec::new();
+        while let Ok(ident) = input.expect_ident() {
+            idents.push(ident.to_string());
+        }
+        Ok(idents)
+    }
+
+    let mut input = ParserInput::new("a b / c /* / */ d / e");
+    let mut parser = Parser::new(&mut input);
+    assert_eq!(
+        parser.parse_until_after(Delimiter::Slash, idents_until),
+        Ok(vec!["a".to_owned(), "b".to_owned()])
+    );
+    assert_eq!(
+        parser.parse_until_before(Delimiter::Slash, idents_until),
+        Ok(vec!["c".to_owned(), "d".to_owned()])
+    );
+    assert_eq!(parser.next(), Ok(&Token::Delim('/')));
+
+    let mut input = ParserInput::new("a -b - c -/**/- d");
+    let mut parser = Parser::new(&mut input);
+    assert_eq!(
+        parser.parse_until_after(Delimiters::delim('-'), idents_until),
+        Ok(vec!["a".to_owned(), "-b".to_owned()])
+    );
+    assert_eq!(
+        parser.parse_until_after(Delimiters::delim('-') | Delimiter::Comma, idents_until),
+        Ok(vec!["c".to_owned()])
+    );
+    assert_eq!(parser.next(), Ok(&Token::Delim('-')));
+
+    let mut input = ParserInput::new("(a) AND (b) or (c) and");
+    let mut parser = Parser::new(&mut input);
+    let mut conditions = 0;
+    while !parser.is_exhausted() {
+        let result: Result<(), ParseError<()>> =
+            parser.parse_until_after_ident(&["and", "or"], |input| {
+                if input.is_exhausted() {
+                    return Ok(());
+                }
+                input.expect_parenthesis_block()?;
+                conditions += 1;
+                Ok(())
+            });
+        assert!(result.is_ok());
+    }
+    assert_eq!(conditions, 3);
+
+    // Identifiers nest with each other and with other delimiters, without a limit.
+    let mut input = ParserInput::new("a b\n to c d, e");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> = parser.parse_until_before(Delimiter::Comma, |input| {
+        let before = input.parse_until_after_ident(&["to", "from", "by"], |input| {
+            let a = input.parse_until_before_ident(&["b", "c"], idents_until)?;
+            input.expect_ident_matching("b")?;
+            Ok(a)
+        })?;
+        let location = input.current_source_location();
+        Ok((before, location, idents_until(input)?))
+    });
+    assert_eq!(
+        result,
+        Ok((
+            vec!["a".to_owned()],
+            SourceLocation { line: 1, column: 4 },
+            vec!["c".to_owned(), "d".to_owned()]
+        ))
+    );
+    let mut input = ParserInput::new("a (b) or c");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> =
+        parser.parse_until_before_ident(&["b", "or"], |input| {
+            input.expect_ident_matching("a")?;
+            Ok(input.expect_ident_matching("b")?)
+        });
+    assert_eq!(
+        result.map_err(|e| e.location),
+        Err(SourceLocation { line: 0, column: 2 })
+    );
+    assert_eq!(parser.next(), Ok(&Token::Ident("or".into())));
+
+    let mut input = ParserInput::new("a | b c, d");
+    let mut parser = Parser::new(&mut input);
+    let result: Result<_, ParseError<()>> = parser.parse_comma_separated(|input| {
+        let namespace = input.parse_until_after(Delimiter::Bar, idents_until)?;
+        Ok((namespace, idents_until(input)?))
+    });
+    assert_eq!(
+        result,
+        Ok(vec![
+            (vec!["a".to_owned()], vec!["b".to_owned(), "c".to_owned()]),
+            (vec!["d".to_owned()], vec![]),
+        ])
+    );
+}
+
+#[test]
+fn media_queries() {
+    struct Viewport(f32, f32);
+    impl MediaEnvironment for Viewport {
+        fn viewport_width(&self) -> f32 {
+            self.0
+        }
+        fn viewport_height(&self) -> f32 {
+            self.1
+        }
+    }
+
+    fn parse(css: &str) -> MediaQueryList {
+        let mut input = ParserInput::new(css);
+        MediaQueryList::parse(&mut Parser::new(&mut input))
+    }
+
+    for &(css, expected) in &[
+        ("", ""),
+        ("SCREEN", "screen"),
+        ("only screen and (COLOR)", "only screen and (color)"),
+        ("all and (min-width: 40em)", "(min-width: 40em)"),
+        (
+            "not print, (orientation:landscape)",
+            "not print, (orientation: landscape)",
+        ),
+        ("(400px<=width<800px)", "(400px <= width < 800px)"),
+        (
+            "(width > 300px) or (not (hover))",
+            "(width > 300px) or (not (hover))",
+        ),
+        ("(aspect-ratio: 16/9)", "(aspect-ratio: 16 / 9)"),
+        ("(50px < width > 10px)", "(50px < width > 10px)"),
+        ("screen and (a) or (b)", "not all"),
+        ("(a) and (b) or (c), print", "not all, print"),
+        ("(width) and foo(bar)", "(width) and foo(bar)"),
+        (
+            "(width: 100px 200px < height)",
+            "(width: 100px 200px < height)",
+        ),
+        ("(a: 1 2 < b)", "(a: 1 2 < b)"),
+        ("and", "not all"),
+    ] {
+        assert_eq!(parse(css).to_css_string(), expected, "{}", css);
+    }
+
+    let small = Viewport(500., 800.);
+    let large = Viewport(1200., 800.);
+    for &(css, on_small, on_large) in &[
+        ("", true, true),
+        ("print", false, false),
+        ("not print", true, true),
+        ("(400px <= width < 800px)", true, false),
+        ("(min-width: 40em)", false, true),
+        ("(max-width: 6in)", true, false),
+        ("(orientation: portrait)", true, false),
+        ("(min-aspect-ratio: 4/3)", false, true),
+        ("(width < 600px) or (height > 1000px)", true, false),
+        ("not ((width < 600px) and (hover: hover))", false, true),
+        ("(resolution >= 1dppx) and (color)", true, true),
+        ("(unknown-feature)", false, false),
+        ("not (unknown-feature)", false, false),
+        ("(width) or foo(bar)", true, true),
+        ("print, (width > 1000px)", false, true),
+    ] {
+        let list = parse(css);
+        assert_eq!(list.matches(&small), on_small, "{} on small", css);
+        assert_eq!(list.matches(&large), on_large, "{} on large", css);
+    }
+}
+
+#[test]
+fn supports_conditions() {
+    struct Supports;
+    impl<'i> DeclarationParser<'i> for Supports {
+        type Declaration = ();
+        type Error = ();
+
+        fn parse_value<'t>(
+            &mut self,
+            name: CowRcStr<'i>,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<(), ParseError<'i, ()>> {
+            if name.starts_with("--") {
+                while input.next().is_ok() {}
+                return Ok(());
+            }
+            match_ignore_ascii_case! { &name,
+                "display" => {
+                    let location = input.current_source_location();
+                    let ident = input.expect_ident()?;
+                    if !ident.eq_ignore_ascii_case("grid") && !ident.eq_ignore_ascii_case("block") {
+                        return Err(location.new_unexpected_token_error(Token::Ident(ident.clone())));
+                    }
+                },
+                _ => return Err(input.new_custom_error(())),
+            }
+            let _ = input.try_parse(parse_important);
+            Ok(())
+        }
+    }
+    impl<'i> SupportsParser<'i> for Supports {
+        fn supports_selector<'t>(&mut self, input: &mut Parser<'i, 't>) -> bool {
+            input.expect_colon().is_ok() && input.expect_ident_matching("hover").is_ok()
+        }
+
+        fn supports_font_format(&mut self, format: &str) -> bool {
+            format == "woff2"
+        }
+    }
+
+    for &(css, expected, supported) in &[
+        ("(display: grid)", "(display: grid)", true),
+        (
+            "( DISPLAY :grid !important )",
+            "(display: grid !important)",
+            true,
+        ),
+        ("(display: flex)", "(display: flex)", false),
+        ("(--Foo: [ ])", "(--Foo: [ ])", true),
+        (
+            "(display: grid) and (not (foo: bar))",
+            "(display: grid) and (not (foo: bar))",
+            true,
+        ),
+        (
+            "(foo: bar) or selector(:hover)",
+            "(foo: bar) or selector(:hover)",
+            true,
+        ),
+        ("selector( :focus )", "selector(:focus)", false),
+        (
+            "font-format(WOFF2) and font-tech(color-COLRv1)",
+            "font-format(woff2) and font-tech(color-colrv1)",
+            false,
+        ),
+        ("font-format(woff2)", "font-format(woff2)", true),
+        ("font-format(\"woff2\")", "font-format(\"woff2\")", true),
+        ("font-format(\"WOFF2\")", "font-format(\"WOFF2\")", true),
+        ("font-format(1)", "font-format(1)", false),
+        ("foo(bar)", "foo(bar)", false),
+        ("not foo(bar)", "not foo(bar)", true),
+        ("not (display: grid) or (display: block)", "", false),
+        (
+            "(display: grid) and (display: grid) or (display: block)",
+            "",
+            false,
+        ),
+    ] {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        let condition = parser
+            .parse_entirely(|input| SupportsCondition::parse(input).map_err(ParseError::from));
+        let condition: Result<_, ParseError<()>> = condition;
+        match condition {
+            Ok(condition) => {
+                assert_eq!(condition.to_css_string(), expected, "{}", css);
+                assert_eq!(condition.is_supported(&mut Supports), supported, "{}", css);
+            }
+            Err(_) => assert_eq!(expected, "", "{}", css),
+        }
+    }
+}
+
+#[test]
+fn container_queries() {
+    struct Container {
+        width: Option<f32>,
+        height: Option<f32>,
+    }
+    impl QueryContainer for Container {
+        fn has_name(&self, name: &str) -> bool {
+            name == "sidebar"
+        }
+        fn width(&self) -> Option<f32> {
+            self.width
+        }
+        fn height(&self) -> Option<f32> {
+            self.height
+        }
+        fn computed_value(&self, name: &str) -> Option<String> {
+            match name {
+                "--responsive" => Some(" true".to_owned()),
+                "display" => Some("block".to_owned()),
+                _ => None,
+            }
+        }
+        fn scroll_state(&self, name: &str) -> Option<&str> {
+            match name {
+                "stuck" => Some("top"),
+                _ => None,
+            }
+        }
+    }
+
+    let inline = Container {
+        width: Some(500.),
+        height: None,
+    };
+    for &(css, expected, matches) in &[
+        (
+            "sidebar (min-width: 400px) and style(--responsive: true)",
+            "sidebar (min-width: 400px) and style(--responsive: true)",
+            true,
+        ),
+        ("sidebar", "sidebar", true),
+        ("main (width > 0)", "main (width > 0)", false),
+        (
+            "(400px<inline-size<=40em)",
+            "(400px < inline-size <= 40em)",
+            true,
+        ),
+        ("(height > 0px)", "(height > 0px)", false),
+        ("not (height > 0px)", "not (height > 0px)", false),
+        (
+            "(height > 0px) or (width > 0px)",
+            "(height > 0px) or (width > 0px)",
+            true,
+        ),
+        (
+            "STYLE((--responsive) and (not (display: flex)))",
+            "style((--responsive) and (not (display: flex)))",
+            true,
+        ),
+        ("style(--other)", "style(--other)", false),
+        ("style(color: red)", "style(color: red)", false),
+        ("scroll-state(stuck: top)", "scroll-state(stuck: top)", true),
+        (
+            "scroll-state((stuck) and (snapped: x))",
+            "scroll-state((stuck) and (snapped: x))",
+            false,
+        ),
+        (
+            "(width: 100px 200px < height)",
+            "(width: 100px 200px < height)",
+            false,
+        ),
+        ("none (width > 0)", "", false),
+        ("sidebar and", "", false),
+        ("", "", false),
+    ] {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        let query: Result<_, ParseError<()>> =
+            parser.parse_entirely(|input| ContainerQuery::parse(input).map_err(ParseError::from));
+        match query {
+            Ok(query) => {
+                assert_eq!(query.to_css_string(), expected, "{}", css);
+                assert_eq!(query.matches(&inline), matches, "{}", css);
+            }
+            Err(_) => assert_eq!(expected, "", "{}", css),
+        }
+    }
+}
+
+#[test]
+fn import_rules() {
+    for &(css, expected) in &[
+        ("\"a.css\"", "url(\"a.css\")"),
+        (
+            "url(a.css) LAYER supports(display: grid) screen, print",
+            "url(\"a.css\") layer supports((display: grid)) screen, print",
+        ),
+        (
+            "url(\"a.css\") layer(base.reset) supports((not (display: grid)) or selector(a > b))",
+            "url(\"a.css\") layer(base.reset) supports((not (display: grid)) or selector(a > b))",
+        ),
+        (
+            "url(a.css) supports(not (display: grid) or selector(a > b))",
+            "",
+        ),
+        ("'a.css' layer(initial)", ""),
+        ("url(a.css) supports((a) and (b) or (c)) screen", ""),
+        ("url(a.css) supports() screen", ""),
+        ("a.css", ""),
+    ] {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        let import: Result<_, ParseError<()>> =
+            parser.parse_entirely(|input| ImportRule::parse(input).map_err(ParseError::from));
+        match import {
+            Ok(import) => assert_eq!(import.to_css_string(), expected, "{}", css),
+            Err(_) => assert_eq!(expected, "", "{}", css),
+        }
+    }
+}
+
+#[test]
+fn import_resolution() {
+    struct EncodingRs;
+    impl EncodingSupport for EncodingRs {
+        type Encoding = &'static encoding_rs::Encoding;
+
+        fn utf8() -> Self::Encoding {
+            encoding_rs::UTF_8
+        }
+
+        fn is_utf16_be_or_le(encoding: &Self::Encoding) -> bool {
+            *encoding == encoding_rs::UTF_16LE || *encoding == encoding_rs::UTF_16BE
+        }
+
+        fn from_label(ascii_label: &[u8]) -> Option<Self::Encoding> {
+            encoding_rs::Encoding::for_label(ascii_label)
+        }
+    }
+    impl StylesheetDecoder for EncodingRs {
+        fn decode(bytes: &[u8], encoding: &Self::Encoding) -> String {
+            encoding.decode(bytes).0.into_owned()
+        }
+    }
+
+    struct Sheets(Vec<(&'static str, &'static [u8])>);
+    impl StylesheetLoader for Sheets {
+        type Error = ();
+
+        fn resolve_url(&self, base_url: &str, url: &str) -> String {
+            match base_url.rfind('/') {
+                Some(i) => format!("{}/{}", &base_url[..i], url),
+                None => url.to_owned(),
+            }
+        }
+
+        fn load(&mut self, url: &str) -> Result<LoadedStylesheet, ()> {
+            let css = self.0.iter().find(|sheet| sheet.0 == url).ok_or(())?.1;
+            Ok(LoadedStylesheet {
+                bytes: css.to_vec(),
+                protocol_encoding_label: None,
+            })
+        }
+    }
+
+    let mut sheets = Sheets(vec![
+        (
+            "main.css",
+            b"@charset \"utf-8\";\n@layer a, b;\n@import \"lib/reset.css\" layer(a) supports(display: grid) screen;\n\
+             @import url(lib/base.css);\np { color: red }\n@import \"ignored.css\";",
+        ),
+        ("lib/reset.css", b"@import 'base.css' print; * { margin: 0 }"),
+        ("lib/base.css", b"body { margin: 1px }"),
+        ("a.css", b"@import \"b.css\";"),
+        ("b.css", b"@import \"a.css\";"),
+        (
+            "latin1.css",
+            b"@charset \"windows-1252\";\n@import \"quote.css\";\np::before { content: \"\xE9\" }",
+        ),
+        ("quote.css", b"q::before { content: \"\xAB\" }"),
+        ("invalid.css", b"@import \"a.css\" supports((a) and (b) or (c));"),
+    ]);
+    assert_eq!(
+        resolve_imports::<EncodingRs, _>("main.css", &mut sheets),
+        Ok("@layer a, b;\n\
+            @media screen {\n@supports (display: grid) {\n@layer a {\n\
+            @media print {\nbody { margin: 1px }\n}\n* { margin: 0 }\n}\n}\n}\n\
+            body { margin: 1px }\n\
+            p { color: red }\n"
+            .to_owned())
+    );
+    assert_eq!(
+        resolve_imports::<EncodingRs, _>("a.css", &mut sheets),
+        Err(ImportError::Cycle(vec![
+            "a.css".to_owned(),
+            "b.css".to_owned(),
+            "a.css".to_owned(),
+        ]))
+    );
+    // Imported stylesheets without `@charset` default to the encoding of the importing one.
+    assert_eq!(
+        resolve_imports::<EncodingRs, _>("latin1.css", &mut sheets),
+        Ok("q::before { content: \"«\" }\np::before { content: \"é\" }\n".to_owned())
+    );
+    assert_eq!(
+        resolve_imports::<EncodingRs, _>("invalid.css", &mut sheets),
+        Ok(String::new())
+    );
+    assert_eq!(
+        resolve_imports::<EncodingRs, _>("missing.css", &mut sheets),
+        Err(ImportError::Load {
+            url: "missing.css".to_owned(),
+            error: ()
+        })
+    );
+}
+
+#[test]
+fn layer_order() {
+    struct Walker<'a> {
+        order: &'a mut LayerOrder,
+        rules: &'a mut Vec<(String, LayerId)>,
+        preludes: &'a mut Vec<String>,
+    }
+
+    impl<'a, 'i> AtRuleParser<'i> for Walker<'a> {
+        type PreludeNoBlock = LayerRule;
+        type PreludeBlock = LayerRule;
+        type AtRule = ();
+        type Error = ();
+
+        fn parse_prelude<'t>(
+            &mut self,
+            name: CowRcStr<'i>,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<AtRuleType<LayerRule, LayerRule>, ParseError<'i, ()>> {
+            assert!(name.eq_ignore_ascii_case("layer"));
+            Ok(LayerRule::parse_prelude(input)?)
+        }
+
+        fn rule_without_block(&mut self, prelude: LayerRule, _: &ParserState) {
+            self.preludes.push(prelude.to_css_string());
+            if let LayerRule::Statement(ref names) = prelude {
+                self.order.declare(names);
+            }
+        }
+
+        fn rule_without_optional_block(
+            &mut self,
+            prelude: LayerRule,
+            start: &ParserState,
+        ) -> Option<()> {
+            self.rule_without_block(prelude.without_block()?, start);
+            Some(())
+        }
+
+        fn parse_block<'t>(
+            &mut self,
+            prelude: LayerRule,
+            _: &ParserState,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<(), ParseError<'i, ()>> {
+            self.preludes.push(prelude.to_css_string());
+            if let LayerRule::Block(ref name) = prelude {
+                self.order.enter(name.as_ref());
+            }
+            let nested = Walker {
+                order: &mut *self.order,
+                rules: &mut *self.rules,
+                preludes: &mut *self.preludes,
+            };
+            for result in RuleListParser::new_for_nested_rule(input, nested) {
+                assert!(result.is_ok());
+            }
+            self.order.exit();
+            Ok(())
+        }
+    }
+
+    impl<'a, 'i> QualifiedRuleParser<'i> for Walker<'a> {
+        type Prelude = String;
+        type QualifiedRule = ();
+        type Error = ();
+
+        fn parse_prelude<'t>(
+            &mut self,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<String, ParseError<'i, ()>> {
+            Ok(input.expect_ident()?.to_string())
+        }
+
+        fn parse_block<'t>(
+            &mut self,
+            prelude: String,
+            _: &ParserState,
+            _: &mut Parser<'i, 't>,
+        ) -> Result<(), ParseError<'i, ()>> {
+            self.rules.push((prelude, self.order.current()));
+            Ok(())
+        }
+    }
+
+    let css = "@layer reset, base.elements; p {} @layer base { q {} @layer elements { a {} } } \
+               @layer { b {} } @layer reset { r {} } @LAYER theme; @layer;";
+    let mut order = LayerOrder::new();
+    let mut rules = Vec::new();
+    let mut preludes = Vec::new();
+    let mut input = ParserInput::new(css);
+    let mut input = Parser::new(&mut input);
+    let walker = Walker {
+        order: &mut order,
+        rules: &mut rules,
+        preludes: &mut preludes,
+    };
+    let results: Vec<_> = RuleListParser::new_for_stylesheet(&mut input, walker).collect();
+    assert_eq!(results.len(), 7);
+    assert!(results[..6].iter().all(|result| result.is_ok()));
+    assert!(results[6].is_err());
+    assert_eq!(
+        preludes,
+        [
+            "reset, base.elements",
+            "base",
+            "elements",
+            "",
+            "reset",
+            "theme"
+        ]
+    );
+
+    let ranks = order.ranks();
+    let ranked: Vec<_> = rules
+        .iter()
+        .map(|&(ref rule, layer)| {
+            let name = order.name(layer).map(|name| name.to_css_string());
+            (rule.as_str(), name, ranks[layer.0])
+        })
+        .collect();
+    assert_eq!(
+        ranked,
+        [
+            ("p", None, 5),
+            ("q", Some("base".to_owned()), 2),
+            ("a", Some("base.elements".to_owned()), 1),
+            ("b", None, 3),
+            ("r", Some("reset".to_owned()), 0),
+        ]
+    );
+    assert_eq!(order.rank(LayerId::UNLAYERED), 5);
+}
+
+#[test]
+fn font_face_descriptors() {
+    let css = "
+        font-family: Open  Sans;
+        src: local( 'Open Sans' ), url(a.woff2) FORMAT(woff2) tech(variations, color-COLRv1),
+             bogus(x), url(\"a.ttf\") format('TrueType');
+        font-weight: bold 300;
+        font-stretch: condensed 150%;
+        font-style: oblique 0.25turn -10deg;
+        unicode-range: U+0-7F, u+4??;
+        font-display: SWAP;
+        font-feature-settings: 'liga' off, \"kern\", 'ss01' 2;
+        ascent-override: 90%;
+        descent-override: normal;
+        line-gap-override: -1%;
+        size-adjust: 105%;
+        unknown: 1;
+        @media screen {}
+        font-display: invalid;
+    ";
+    let mut input = ParserInput::new(css);
+    let font_face = FontFace::parse(&mut Parser::new(&mut input));
+    assert_eq!(font_face.weight, Some(FontWeightRange::Range(700., 300.)));
+    assert_eq!(font_face.style, Some(FontStyleRange::Oblique(90., -10.)));
+    assert_eq!(font_face.line_gap_override, None);
+    assert_eq!(
+        font_face.to_css_string(),
+        "font-family: \"Open Sans\"; \
+         src: local(\"Open Sans\"), url(\"a.woff2\") format(woff2) tech(variations, color-colrv1), \
+         url(\"a.ttf\") format(\"TrueType\"); \
+         font-weight: 700 300; font-stretch: 75% 150%; font-style: oblique 90deg -10deg; \
+         unicode-range: U+0-7F, U+400-4FF; font-display: swap; \
+         font-feature-settings: \"liga\" 0, \"kern\", \"ss01\" 2; \
+         ascent-override: 90%; descent-override: normal; size-adjust: 105%;"
+    );
+
+    let mut input = ParserInput::new(
+        "src: bogus(x); font-family: inherit; font-weight: 0; font-style: oblique 91deg",
+    );
+    assert_eq!(
+        FontFace::parse(&mut Parser::new(&mut input)),
+        FontFace::default()
+    );
+
+    // Sources with leftover input are dropped, not the whole descriptor.
+    for &(css, expected) in &[
+        (
+            "src: url(a.woff2) format(1), url(b.woff)",
+            "src: url(\"b.woff\");",
+        ),
+        (
+            "src: url(a.woff2) format(\"woff2\"), url(b.woff) foo",
+            "src: url(\"a.woff2\") format(\"woff2\");",
+        ),
+    ] {
+        let mut input = ParserInput::new(css);
+        let font_face = FontFace::parse(&mut Parser::new(&mut input));
+        assert_eq!(font_face.to_css_string(), expected, "{}", css);
+    }
+}
+
+#[test]
+fn keyframes() {
+    struct Opacity;
+    impl<'i> DeclarationParser<'i> for Opacity {
+        type Declaration = (String, f32);
+        type Error = ();
+
+        fn parse_value<'t>(
+            &mut self,
+            name: CowRcStr<'i>,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<(String, f32), ParseError<'i, ()>> {
+            Ok((name.as_ref().to_owned(), input.expect_number()?))
+        }
+    }
+
+    let mut input = ParserInput::new("'fade in'");
+    let name = KeyframesName::parse(&mut Parser::new(&mut input)).unwrap();
+    assert_eq!(name, KeyframesName::String("fade in".into()));
+    assert_eq!(name.to_css_string(), "\"fade in\"");
+    let mut input = ParserInput::new("Fade");
+    assert_eq!(
+        KeyframesName::parse(&mut Parser::new(&mut input)).map(|n| n.to_css_string()),
+        Ok("Fade".into())
+    );
+    for css in &["none", "initial", "12", ""] {
+        let mut input = ParserInput::new(css);
+        assert!(KeyframesName::parse(&mut Parser::new(&mut input)).is_err());
+    }
+
+    let css = "
+        to { opacity: 1 }
+        50%, FROM { opacity: 0.5; bogus: x; width: 1 }
+        101% { opacity: 2 }
+        entry 20% { opacity: 0.2 }
+        exit 120% { opacity: 0 }
+        @media screen { 0% { opacity: 0 } }
+        0% { opacity: 0 }
+        25.5%, entry 20% { opacity: 0.25 }
+    ";
+    let mut input = ParserInput::new(css);
+    let mut input = Parser::new(&mut input);
+    let keyframes: Vec<_> =
+        RuleListParser::new_for_nested_rule(&mut input, KeyframeListParser::new(Opacity)).collect();
+    assert_eq!(keyframes.len(), 8);
+    let keyframes: Vec<_> = keyframes.into_iter().filter_map(Result::ok).collect();
+    assert_eq!(keyframes.len(), 5);
+    assert_eq!(
+        keyframes[1].selectors,
+        vec![
+            KeyframeSelector::Percentage(0.5),
+            KeyframeSelector::Percentage(0.)
+        ]
+    );
+    assert_eq!(keyframes[1].declarations.len(), 2);
+
+    let merged = Keyframe::sort_and_merge(keyframes);
+    let summary: Vec<_> = merged
+        .iter()
+        .map(|keyframe| {
+            let values: Vec<_> = keyframe.declarations.iter().map(|d| d.1).collect();
+            (keyframe.selectors[0].to_css_string(), values)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("0%".to_owned(), vec![0.5, 1., 0.]),
+            ("25.5%".to_owned(), vec![0.25]),
+            ("50%".to_owned(), vec![0.5, 1.]),
+            ("100%".to_owned(), vec![1.]),
+            ("entry 20%".to_owned(), vec![0.2, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn keyframes_sort_nan_offset() {
+    let keyframe = |offset: f32| Keyframe {
+        selectors: vec![KeyframeSelector::Percentage(offset)],
+        declarations: vec![offset],
+    };
+    let merged = Keyframe::sort_and_merge(vec![keyframe(1.), keyframe(f32::NAN), keyframe(0.)]);
+    let offsets: Vec<_> = merged
+        .iter()
+        .map(|keyframe| keyframe.declarations[0])
+        .collect();
+    assert_eq!(offsets[..2], [0., 1.]);
+    assert!(offsets[2].is_nan());
+}
+
+#[test]
+fn page_rules() {
+    struct Declaration(String, String);
+    impl ToCss for Declaration {
+        fn to_css<W>(&self, dest: &mut W) -> fmt::Result
+        where
+            W: fmt::Write,
+        {
+            write!(dest, "{}: {}", self.0, self.1)
+        }
+    }
+    struct Declarations;
+    impl<'i> DeclarationParser<'i> for Declarations {
+        type Declaration = Declaration;
+        type Error = ();
+
+        fn parse_value<'t>(
+            &mut self,
+            name: CowRcStr<'i>,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<Declaration, ParseError<'i, ()>> {
+            let start = input.position();
+            input.expect_ident_or_string()?;
+            let value = input.slice_from(start).trim().to_owned();
+            Ok(Declaration(name.as_ref().to_owned(), value))
+        }
+    }
+
+    let mut input = ParserInput::new("chapter:FIRST:left , :blank");
+    let selectors = PageSelector::parse_list(&mut Parser::new(&mut input)).unwrap();
+    assert_eq!(selectors.len(), 2);
+    assert_eq!(selectors[0].name, Some("chapter".into()));
+    assert_eq!(
+        selectors[0].pseudo_classes,
+        vec![PagePseudoClass::First, PagePseudoClass::Left]
+    );
+    assert_eq!(selectors[0].specificity(), (1, 1, 1));
+    assert_eq!(selectors[1].specificity(), (0, 1, 0));
+    let mut input = ParserInput::new("");
+    assert_eq!(
+        PageSelector::parse_list(&mut Parser::new(&mut input)),
+        Ok(vec![])
+    );
+    for css in &[
+        ":first name",
+        "name :first",
+        ": first",
+        ":nth(1)",
+        ":first,",
+    ] {
+        let mut input = ParserInput::new(css);
+        let mut input = Parser::new(&mut input);
+        assert!(
+            input
+                .parse_entirely(
+                    |input| PageSelector::parse_list(input).map_err(ParseError::<()>::from)
+                )
+                .is_err(),
+            "{}",
+            css
+        );
+    }
+
+    let css = "
+        size: a4;
+        @top-center { content: 'Title'; bogus: 1 }
+        margin: auto;
+        @media print { margin: none }
+        @bottom-right-corner foo { content: x }
+        @Bottom-Right-Corner { content: counter }
+    ";
+    let mut input = ParserInput::new(css);
+    let rule = PageRule::parse_block(selectors, &mut Parser::new(&mut input), &mut Declarations);
+    assert_eq!(rule.declarations.len(), 2);
+    assert_eq!(rule.margin_rules.len(), 2);
+    assert_eq!(
+        rule.margin_rules[1].margin_box,
+        PageMarginBox::BottomRightCorner
+    );
+    assert_eq!(
+        rule.to_css_string(),
+        "@page chapter:first:left, :blank { size: a4; margin: auto; \
+         @top-center { content: 'Title'; } @bottom-right-corner { content: counter; } }"
+    );
+}
+
+#[test]
+fn counter_styles() {
+    let css = "
+        system: fixed -1;
+        system: extends Lower-Roman;
+        negative: '(' ')';
+        prefix: '[';
+        suffix: ']';
+        range: 1 10, -5 infinite;
+        pad: '0' 3;
+        fallback: upper-ALPHA;
+        speak-as: spell-out;
+        additive-symbols: 1 a, 5 b;
+        range: 10 1;
+    ";
+    let mut input = ParserInput::new(css);
+    let style = CounterStyle::parse(&mut Parser::new(&mut input));
+    assert_eq!(
+        style.system,
+        Some(System::Extends(CounterStyleName("lower-roman".into())))
+    );
+    assert_eq!(style.additive_symbols, None);
+    assert!(style.is_valid());
+    assert_eq!(
+        style.to_css_string(),
+        "system: extends lower-roman; negative: \"(\" \")\"; prefix: \"[\"; suffix: \"]\"; \
+         range: 1 10, -5 infinite; pad: 3 \"0\"; fallback: upper-alpha; speak-as: spell-out;"
+    );
+
+    for css in &["decimal", "DISC", "none", "inherit"] {
+        let mut input = ParserInput::new(css);
+        assert!(CounterStyleName::parse_rule_name(&mut Parser::new(&mut input)).is_err());
+    }
+
+    let mut registry = CounterStyleRegistry::new();
+    let decimal =
+        |registry: &CounterStyleRegistry, value| registry.representation("decimal", value);
+    assert_eq!(decimal(&registry, -12), "-12");
+    assert_eq!(registry.representation("decimal-leading-zero", 7), "07");
+    assert_eq!(registry.representation("decimal-leading-zero", -7), "-7");
+    assert_eq!(registry.representation("lower-roman", 1994), "mcmxciv");
+    assert_eq!(registry.representation("upper-roman", 4000), "4000");
+    assert_eq!(registry.representation("lower-alpha", 28), "ab");
+    assert_eq!(registry.representation("lower-alpha", 0), "0");
+    assert_eq!(registry.representation("cjk-decimal", 2024), "二〇二四");
+    assert_eq!(registry.representation("unknown", 3), "3");
+    assert_eq!(registry.marker("disc", 3), "• ");
+    assert_eq!(registry.marker("hiragana", 2), "い、");
+    assert_eq!(registry.marker("lower-greek", 3), "γ. ");
+    assert_eq!(registry.representation("hebrew", 15), "טו");
+    assert_eq!(registry.representation("hebrew", 11000), "11000");
+    assert_eq!(registry.representation("armenian", 1999), "ՌՋՂԹ");
+    assert_eq!(registry.representation("lower-armenian", 12), "ժբ");
+    assert_eq!(registry.representation("georgian", 10001), "ჵა");
+    assert_eq!(registry.representation("tamil", 42), "௪௨");
+    assert_eq!(registry.representation("khmer", 7), "៧");
+    assert_eq!(registry.marker("katakana-iroha", 3), "ハ、");
+    assert_eq!(registry.marker("cjk-heavenly-stem", 11), "11. ");
+    assert_eq!(registry.representation("cjk-ideographic", 3), "3");
+
+    let mut define = |name: &str, css: &str| {
+        let mut input = ParserInput::new(css);
+        let style = CounterStyle::parse(&mut Parser::new(&mut input));
+        registry.register(CounterStyleName(name.into()), style)
+    };
+    assert!(define(
+        "roman",
+        "system: extends lower-roman; negative: '(' ')'; range: -5 5; prefix: '<'"
+    ));
+    assert!(define(
+        "stars",
+        "system: symbolic; symbols: '*' '+'; fallback: loop-a"
+    ));
+    assert!(define(
+        "loop-a",
+        "system: cyclic; symbols: x; range: 1 1; fallback: loop-b"
+    ));
+    assert!(define(
+        "loop-b",
+        "system: fixed 2; symbols: y; fallback: loop-a"
+    ));
+    assert!(define("self", "system: extends self; suffix: ')'"));
+    assert!(define(
+        "padded",
+        "system: extends decimal; pad: 4 '0'; negative: '−'"
+    ));
+    assert!(define(
+        "huge-pad",
+        "system: extends lower-roman; pad: 2147483647 '0'"
+    ));
+    assert!(!define("decimal", "system: cyclic; symbols: x"));
+    assert!(!define("broken", "system: alphabetic; symbols: x"));
+    assert!(!define("broken", "system: extends decimal; symbols: x"));
+    assert_eq!(registry.representation("roman", -3), "(iii)");
+    assert_eq!(registry.marker("roman", 4), "<iv. ");
+    assert_eq!(registry.representation("roman", 6), "6");
+    assert_eq!(registry.representation("stars", 5), "***");
+    assert_eq!(registry.representation("stars", 1000), "1000");
+    assert_eq!(registry.representation("loop-b", 2), "y");
+    assert_eq!(registry.representation("stars", -2), "-2");
+    assert_eq!(registry.marker("self", 5), "5)");
+    assert_eq!(registry.representation("padded", -5), "−005");
+    assert_eq!(registry.representation("huge-pad", 3), "3");
+    assert_eq!(decimal(&registry, i32::MIN), "-2147483648");
+}
+
+#[test]
+fn property_rules() {
+    fn parse(css: &str) -> Result<PropertyRule, PropertyRuleError> {
+        let mut input = ParserInput::new(css);
+        PropertyRule::parse_block("--x".into(), &mut Parser::new(&mut input))
+    }
+    fn syntax(css: &str) -> Result<SyntaxDefinition, ()> {
+        let mut input = ParserInput::new(css);
+        Parser::new(&mut input)
+            .parse_entirely(|input| SyntaxDefinition::parse(input).map_err(ParseError::<()>::from))
+            .map_err(|_| ())
+    }
+    fn matches(syntax_css: &str, value: &str) -> bool {
+        let mut input = ParserInput::new(value);
+        syntax(syntax_css)
+            .unwrap()
+            .matches(&mut Parser::new(&mut input))
+    }
+
+    let rule = parse("syntax: ' <length>|auto '; inherits: FALSE; initial-value:  0px ;").unwrap();
+    assert_eq!(
+        rule.syntax,
+        SyntaxDefinition::Components(vec![
+            SyntaxComponent {
+                name: SyntaxComponentName::DataType(DataType::Length),
+                multiplier: None,
+            },
+            SyntaxComponent {
+                name: SyntaxComponentName::Ident("auto".into()),
+                multiplier: None,
+            },
+        ])
+    );
+    assert_eq!(
+        rule.to_css_string(),
+        "@property --x { syntax: \"<length> | auto\"; inherits: false; initial-value: 0px; }"
+    );
+    let mut rule = parse("syntax: '*'; inherits: true").unwrap();
+    assert_eq!(rule.initial_value, None);
+    rule.name = "x".into();
+    assert_eq!(
+        rule.to_css_string(),
+        "@property x { syntax: \"*\"; inherits: true; }"
+    );
+    rule.name = "-é".into();
+    assert_eq!(
+        rule.to_css_string(),
+        "@property -é { syntax: \"*\"; inherits: true; }"
+    );
+    let mut input = ParserInput::new("--my-color");
+    assert_eq!(
+        PropertyRule::parse_name(&mut Parser::new(&mut input)),
+        Ok("--my-color".into())
+    );
+    let mut input = ParserInput::new("color");
+    assert!(PropertyRule::parse_name(&mut Parser::new(&mut input)).is_err());
+
+    assert_eq!(
+        parse("inherits: false; initial-value: 0"),
+        Err(PropertyRuleError::MissingSyntax)
+    );
+    assert_eq!(
+        parse("syntax: '<length>'; inherits: maybe; initial-value: 0"),
+        Err(PropertyRuleError::MissingInherits)
+    );
+    assert_eq!(
+        parse("syntax: '<length>'; inherits: true"),
+        Err(PropertyRuleError::MissingInitialValue)
+    );
+    assert_eq!(
+        parse("syntax: '<length>'; inherits: true; initial-value: red"),
+        Err(PropertyRuleError::InitialValueMismatch)
+    );
+    assert_eq!(
+        parse("syntax: '<length>'; inherits: true; initial-value: calc(1px + 2em)"),
+        Err(PropertyRuleError::InitialValueNotComputationallyIndependent)
+    );
+
+    for css in &[
+        "<length>+ | <color># | foo",
+        "<transform-list>",
+        "*",
+        "<custom-ident>+",
+    ] {
+        assert_eq!(syntax(css).unwrap().to_css_string(), *css);
+    }
+    for css in &[
+        "",
+        "< length >",
+        "<length> +",
+        "<transform-list>+",
+        "<unknown>",
+        "<length> <angle>",
+        "inherit",
+        "* | <length>",
+        "<Length>",
+    ] {
+        assert_eq!(syntax(css), Err(()), "{}", css);
+    }
+
+    assert!(matches("<length>+", "1px 0 2in"));
+    assert!(matches("<length-percentage>#", "1px, 10%, calc(5% - 1px)"));
+    assert!(!matches("<length>", "calc(5% - 1px)"));
+    assert!(!matches("<length>", "1px 2px"));
+    assert!(matches("<integer> | <angle>", "90deg"));
+    assert!(!matches("<integer>", "1.5"));
+    assert!(matches("<color> | none", "none"));
+    assert!(matches("<color>", "#fff"));
+    assert!(matches("<image>", "linear-gradient(red, blue)"));
+    assert!(matches("<url>", "url(a.png)"));
+    assert!(matches(
+        "<transform-list>",
+        "rotate(1turn) translate(1px, 2px)"
+    ));
+    assert!(!matches(
+        "<transform-function>",
+        "rotate(1turn) translate(1px, 2px)"
+    ));
+    assert!(matches("<custom-ident>", "Foo"));
+    assert!(!matches("<custom-ident>", "inherit"));
+    assert!(!matches("foo", "FOO"));
+    assert!(matches("<string>+", "'a' 'b'"));
+    assert!(matches("<time> | <resolution>", "2dppx"));
+    assert!(matches("*", "anything { goes }"));
+}
+
+#[test]
+fn namespaces() {
+    let css = "
+        @charset 'utf-8';
+        @layer base;
+        @import 'a.css';
+        @namespace url(http://www.w3.org/1999/xhtml);
+        @namespace svg 'http://www.w3.org/2000/svg';
+        @charset 'utf-8';
+        @namespace math url('http://www.w3.org/1998/Math/MathML') screen;
+        @namespace svg url(http://example.com/svg);
+        @import 'b.css';
+        @layer late;
+        @namespace late url(late);
+        svg|a {}
+    ";
+    let mut input = ParserInput::new(css);
+    let mut input = Parser::new(&mut input);
+    let mut order = TopLevelRuleOrder::default();
+    let mut namespaces = NamespaceMap::new();
+    let mut accepted = Vec::new();
+    while let Ok(token) = input.next() {
+        let kind = match *token {
+            Token::AtKeyword(ref name) => match_ignore_ascii_case! { name,
+                "charset" => TopLevelRuleKind::Charset,
+                "import" => TopLevelRuleKind::Import,
+                "layer" => TopLevelRuleKind::LayerStatement,
+                "namespace" => TopLevelRuleKind::Namespace,
+                _ => TopLevelRuleKind::Other,
+            },
+            _ => TopLevelRuleKind::Other,
+        };
+        let prelude = input.parse_until_after(
+            Delimiter::Semicolon | Delimiter::CurlyBracketBlock,
+            |input| {
+                if kind == TopLevelRuleKind::Namespace {
+                    return Ok(Some(NamespaceRule::parse(input)?));
+                }
+                while input.next().is_ok() {}
+                Ok::<_, ParseError<()>>(None)
+            },
+        );
+        let valid = match prelude {
+            Ok(Some(rule)) => {
+                let valid = order.accept(kind);
+                if valid {
+                    namespaces.insert(rule);
+                }
+                valid
+            }
+            Ok(None) => order.accept(kind),
+            Err(_) => false,
+        };
+        accepted.push(valid);
+    }
+    assert_eq!(
+        accepted,
+        [true, true, true, true, true, false, false, true, false, true, false, true]
+    );
+    assert_eq!(
+        namespaces.default_namespace(),
+        Some("http://www.w3.org/1999/xhtml")
+    );
+    assert_eq!(namespaces.prefix("svg"), Some("http://example.com/svg"));
+    assert_eq!(namespaces.prefix("SVG"), None);
+    assert_eq!(namespaces.prefix("math"), None);
+    assert_eq!(namespaces.prefix("late"), None);
+
+    let mut order = TopLevelRuleOrder::new();
+    assert!(order.accept(TopLevelRuleKind::LayerStatement));
+    assert!(!order.accept(TopLevelRuleKind::Charset));
+    assert!(order.accept(TopLevelRuleKind::Import));
+    assert!(order.accept(TopLevelRuleKind::LayerStatement));
+    assert!(order.accept(TopLevelRuleKind::Namespace));
+
+    let mut input = ParserInput::new("svg 'http://www.w3.org/2000/svg'");
+    let rule = NamespaceRule::parse(&mut Parser::new(&mut input)).unwrap();
+    assert_eq!(
+        rule.to_css_string(),
+        "svg url(\"http://www.w3.org/2000/svg\")"
+    );
+}
+
+#[test]
+fn scope_rules() {
+    for (css, expected) in &[
+        ("(.card) to (.content)", Some("(.card) to (.content)")),
+        ("( .card > img ,main )", Some("(.card > img ,main)")),
+        ("to (.content)", Some("to (.content)")),
+        ("", Some("")),
+        ("()", None),
+        ("(.card) (.content)", None),
+        ("(.card) to", None),
+    ] {
+        let mut input = ParserInput::new(css);
+        let rule = ScopeRule::parse(&mut Parser::new(&mut input));
+        assert_eq!(rule.ok().map(|r| r.to_css_string()).as_deref(), *expected);
+    }
+
+    struct ScopedParser;
+
+    impl<'i> DeclarationParser<'i> for ScopedParser {
+        type Declaration = String;
+        type Error = ();
+
+        fn parse_value<'t>(
+            &mut self,
+            name: CowRcStr<'i>,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<String, ParseError<'i, ()>> {
+            let start = input.position();
+            while input.next().is_ok() {}
+            Ok(format!("{}:{}", name, input.slice_from(start).trim()))
+        }
+    }
+
+    impl<'i> AtRuleParser<'i> for ScopedParser {
+        type PreludeNoBlock = ();
+        type PreludeBlock = ();
+        type AtRule = String;
+        type Error = ();
+    }
+
+    impl<'i> QualifiedRuleParser<'i> for ScopedParser {
+        type Prelude = String;
+        type QualifiedRule = String;
+        type Error = ();
+
+        fn parse_prelude<'t>(
+            &mut self,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<String, ParseError<'i, ()>> {
+            let start = input.position();
+            while input.next().is_ok() {}
+            Ok(input.slice_from(start).trim().to_owned())
+        }
+
+        fn parse_block<'t>(
+            &mut self,
+            prelude: String,
+            _: &ParserState,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<String, ParseError<'i, ()>> {
+            let start = input.position();
+            while input.next().is_ok() {}
+            Ok(format!(
+                "{} {{{}}}",
+                prelude,
+                input.slice_from(start).trim()
+            ))
+        }
+    }
+
+    impl<'i> ScopedRuleParser<'i> for ScopedParser {
+        fn parse_scoped_prelude<'t>(
+            &mut self,
+            scope: &ScopeRule,
+            input: &mut Parser<'i, 't>,
+        ) -> Result<String, ParseError<'i, ()>> {
+            let selector = QualifiedRuleParser::parse_prelude(self, input)?;
+            Ok(format!(
+                "{} {}",
+                scope.start.as_deref().unwrap_or(":scope"),
+                selector
+            ))
+        }
+    }
+
+    let css = "
+        color: red;
+        img:hover { border: none }
+        --custom: { a: b };
+        p { }
+        @media print { }
+        a:b { c }
+        div;
+        margin: 0
+    ";
+    let mut input = ParserInput::new("(.card) to (.content)");
+    let scope = ScopeRule::parse(&mut Parser::new(&mut input)).unwrap();
+    let mut input = ParserInput::new(css);
+    let mut input = Parser::new(&mut input);
+    let items = ScopeBodyParser::new(&mut input, scope, ScopedParser)
+        .map(|item| item.map_err(|(_, slice)| slice))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        items,
+        vec![
+            Ok("color:red".to_owned()),
+            Ok(".card img:hover {border: none}".to_owned()),
+            Ok("--custom:{ a: b }".to_owned()),
+            Ok(".card p {}".to_owned()),
+            Err("@media print "),
+            Ok(".card a:b {c}".to_owned()),
+            Err("div;"),
+            Ok("margin:0".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn selectors() {
+    struct TestSelectorParser(NamespaceMap);
+
+    impl SelectorParser for TestSelectorParser {
+        fn namespace_for_prefix(&self, prefix: &str) -> Option<&str> {
+            self.0.prefix(prefix)
+        }
+
+        fn default_namespace(&self) -> Option<&str> {
+            self.0.default_namespace()
+        }
+
+        fn is_custom_pseudo_class(&self, name: &str) -> bool {
+            name == "-x-open"
+        }
+
+        fn parse_custom_functional_pseudo_class<'i, 't>(
+            &self,
+            name: &str,
+            arguments: &mut Parser<'i, 't>,
+        ) -> Result<String, BasicParseError<'i>> {
+            if name != "state" {
+                return Err(arguments.new_basic_error(BasicParseErrorKind::QualifiedRuleInvalid));
+            }
+            Ok(arguments.expect_ident()?.to_ascii_lowercase())
+        }
+
+        fn is_custom_pseudo_element(&self, name: &str) -> bool {
+            name.starts_with("-webkit-")
+        }
+    }
+
+    let mut namespaces = NamespaceMap::new();
+    let mut input = ParserInput::new("svg url(http://www.w3.org/2000/svg)");
+    namespaces.insert(NamespaceRule::parse(&mut Parser::new(&mut input)).unwrap());
+    let parser = TestSelectorParser(namespaces);
+
+    let cases: &[(&str, Option<&str>, Specificity)] = &[
+        ("a", Some("a"), (0, 0, 1)),
+        ("*", Some("*"), (0, 0, 0)),
+        ("*.a#b", Some(".a#b"), (1, 1, 0)),
+        ("a>b  +c~ d e||f", Some("a > b + c ~ d e || f"), (0, 0, 6)),
+        ("a | b", None, (0, 0, 0)),
+        ("a | | b", None, (0, 0, 0)),
+        ("a| |b", None, (0, 0, 0)),
+        (
+            "svg|a, *|b, |c, svg|*",
+            Some("svg|a, *|b, |c, svg|*"),
+            (0, 0, 1),
+        ),
+        ("math|a", None, (0, 0, 0)),
+        ("[href]", Some("[href]"), (0, 1, 0)),
+        (
+            "[ svg|href ^= 'x' i ]",
+            Some("[svg|href^=\"x\" i]"),
+            (0, 1, 0),
+        ),
+        ("[*|lang|=en s]", Some("[*|lang|=\"en\" s]"), (0, 1, 0)),
+        ("[a=b c]", None, (0, 0, 0)),
+        ("[*]", None, (0, 0, 0)),
+        ("a:HOVER::before", Some("a:hover::before"), (0, 1, 2)),
+        ("a:after", Some("a::after"), (0, 0, 2)),
+        ("::before:hover", Some("::before:hover"), (0, 1, 1)),
+        ("::before.a", None, (0, 0, 0)),
+        ("::before a", None, (0, 0, 0)),
+        (
+            "::-webkit-scrollbar",
+            Some("::-webkit-scrollbar"),
+            (0, 0, 1),
+        ),
+        ("::nope", None, (0, 0, 0)),
+        (":is(#a, .b, !)", Some(":is(#a, .b)"), (1, 0, 0)),
+        (":where(#a, .b)", Some(":where(#a, .b)"), (0, 0, 0)),
+        (":not(#a, .b)", Some(":not(#a, .b)"), (1, 0, 0)),
+        (":not(#a, !)", None, (0, 0, 0)),
+        (":is(::before)", Some(":is()"), (0, 0, 0)),
+        (
+            ":has(> img, + .a, b c)",
+            Some(":has(> img, + .a, b c)"),
+            (0, 1, 0),
+        ),
+        (":has(:is(:has(a)))", Some(":has(:is())"), (0, 0, 0)),
+        (":has(:has(a))", None, (0, 0, 0)),
+        (":nth-child(odd)", Some(":nth-child(2n+1)"), (0, 1, 0)),
+        (
+            ":nth-child(-n+ 3 of li.important)",
+            Some(":nth-child(-n+3 of li.important)"),
+            (0, 2, 1),
+        ),
+        (
+            ":nth-last-of-type(2n)",
+            Some(":nth-last-of-type(2n)"),
+            (0, 1, 0),
+        ),
+        (":nth-of-type(2n of a)", None, (0, 0, 0)),
+        (
+            ":lang(en, \"*-CH\")",
+            Some(":lang(en, \"*-CH\")"),
+            (0, 1, 0),
+        ),
+        (":dir(RTL)", Some(":dir(rtl)"), (0, 1, 0)),
+        (
+            ":-x-open:state(Busy)",
+            Some(":-x-open:state(busy)"),
+            (0, 2, 0),
+        ),
+        (":-x-closed", None, (0, 0, 0)),
+        ("::part(a b)::slotted(c)", None, (0, 0, 0)),
+        ("::slotted(span.a)", Some("::slotted(span.a)"), (0, 1, 2)),
+        ("#\\31 23", Some("#\\31 23"), (1, 0, 0)),
+        ("#123", None, (0, 0, 0)),
+        ("", None, (0, 0, 0)),
+        ("a,", None, (0, 0, 0)),
+    ];
+    for &(css, expected, specificity) in cases {
+        let mut input = ParserInput::new(css);
+        let list = SelectorList::parse(&parser, &mut Parser::new(&mut input));
+        assert_eq!(
+            list.as_ref().ok().map(|l| l.to_css_string()).as_deref(),
+            expected,
+            "{}",
+            css
+        );
+        if let Ok(list) = list {
+            assert_eq!(list.specificity(), specificity, "{}", css);
+        }
+    }
+}
+
+#[test]
+fn selector_matching() {
+    let svg_ns = "http://www.w3.org/2000/svg";
+    let xlink_ns = "http://www.w3.org/1999/xlink";
+    let mut doc = Document::new();
+    let html = doc.append_element(None, "html");
+    doc.set_attribute(html, "lang", "en-US");
+    let body = doc.append_element(Some(html), "body");
+    let list = doc.append_element(Some(body), "ul");
+    doc.set_attribute(list, "id", "list");
+    let items: Vec<_> = (0..5)
+        .map(|_| doc.append_element(Some(list), "li"))
+        .collect();
+    doc.append_text(items[0], "one");
+    doc.set_attribute(items[1], "class", "b  important");
+    doc.set_attribute(items[3], "class", "important");
+    doc.set_attribute(items[4], "data-x", "en-gb foo");
+    let p = doc.append_element(Some(body), "p");
+    doc.set_attribute(p, "dir", "rtl");
+    doc.set_attribute(p, "lang", "fr-Latn-CH");
+    let span = doc.append_element(Some(p), "span");
+    let svg = doc.append_element_ns(Some(body), Some(svg_ns), "svg");
+    let link = doc.append_element_ns(Some(svg), Some(svg_ns), "a");
+    doc.set_attribute_ns(link, Some(xlink_ns), "href", "#top");
+    let div = doc.append_element(Some(body), "div");
+    let mut state = ElementState::empty();
+    state.insert(StatePseudoClass::Hover);
+    doc.set_state(div, state);
+    let img = doc.append_element(Some(div), "img");
+
+    let mut namespaces = NamespaceMap::new();
+    for rule in &[
+        "svg url(http://www.w3.org/2000/svg)",
+        "xlink 'http://www.w3.org/1999/xlink'",
+    ] {
+        let mut input = ParserInput::new(rule);
+        namespaces.insert(NamespaceRule::parse(&mut Parser::new(&mut input)).unwrap());
+    }
+    let select = |css: &str| {
+        let mut input = ParserInput::new(css);
+        let list = SelectorList::parse(&namespaces, &mut Parser::new(&mut input)).unwrap();
+        doc.select(&list)
+    };
+
+    assert_eq!(select("li"), items);
+    assert_eq!(select("#list > .important"), [items[1], items[3]]);
+    assert_eq!(
+        select("html li:first-child, body > :last-child"),
+        [items[0], div]
+    );
+    assert_eq!(select("li:nth-child(2n+1)"), [items[0], items[2], items[4]]);
+    assert_eq!(select("li:nth-last-child(-n+2)"), [items[3], items[4]]);
+    assert_eq!(select("li:nth-child(2 of .important)"), [items[3]]);
+    assert_eq!(
+        select(".important + li, .b ~ li"),
+        [items[2], items[3], items[4]]
+    );
+    assert_eq!(
+        select("li:empty:not(:last-child)"),
+        [items[1], items[2], items[3]]
+    );
+    assert_eq!(
+        select("[data-x~=EN-GB i], [data-x|=en], [class$=tant]"),
+        [items[1], items[3], items[4]]
+    );
+    assert_eq!(select("[data-x^=en-gb s], [data-x*='b f']"), [items[4]]);
+    assert_eq!(select("[data-x~=EN-GB]"), []);
+    assert_eq!(select("svg|a, svg|*:root"), [link]);
+    assert_eq!(select("a"), [link]);
+    assert_eq!(select("|a"), []);
+    assert_eq!(select("[xlink|href], [*|href='#top']"), [link]);
+    assert_eq!(select("[href]"), []);
+    assert_eq!(
+        select(":lang(en):not(li)"),
+        [html, body, list, svg, link, div, img]
+    );
+    assert_eq!(select(":lang(\"*-CH\") , :lang(de)"), [p, span]);
+    assert_eq!(select(":dir(rtl)"), [p, span]);
+    assert_eq!(
+        select(":hover > *, :root:has(> body > :hover)"),
+        [html, img]
+    );
+    assert_eq!(
+        select(":has(+ p > span), ul:has(.important + li:empty)"),
+        [list]
+    );
+    assert_eq!(select("body:has(img, li.nope)"), [body]);
+    assert_eq!(
+        select("li:only-of-type, span:only-child, :is(p, div):first-of-type"),
+        [p, span, div]
+    );
+    assert_eq!(select("p::before, ul || li"), []);
+
+    let before = {
+        let mut input = ParserInput::new("p::before");
+        SelectorList::parse(&namespaces, &mut Parser::new(&mut input)).unwrap()
+    };
+    let mut context = MatchingContext::new();
+    context.pseudo_element = before.0[0].pseudo_element().cloned();
+    assert!(before.matches(&doc.element(p).unwrap(), &context));
+    context.scope = doc.element(p);
+    let scoped = {
+        let mut input = ParserInput::new(":scope > span");
+        SelectorList::parse(&namespaces, &mut Parser::new(&mut input)).unwrap()
+    };
+    context.pseudo_element = None;
+    assert!(scoped.matches(&doc.element(span).unwrap(), &context));
+    assert!(!scoped.matches(&doc.element(span).unwrap(), &MatchingContext::new()));
+}
+
 #[test]
 fn parser_maintains_current_line() {
     let mut input = ParserInput::new("ident ident;\nident ident ident;\nident");
@@ -310,6 +310,12 @@ impl<'a> Tokenizer<'a> {
         &self.input[range.start.0..range.end.0]
     }
 
+    /// The input up to the given position, so that tokenizing it gives the same positions.
+    #[inline]
+    pub fn input_until(&self, end: SourcePosition) -> &'a str {
+        &self.input[..end.0]
+    }
+
     pub fn current_source_line(&self) -> &'a str {
         let current = self.position;
         let start = self.input[0..current]